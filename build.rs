@@ -15,6 +15,10 @@ const LIBAOM_VERSION: &str = "3.9.1";
 const LIBAOM_URL: &str = "https://storage.googleapis.com/aom-releases/libaom-3.9.1.tar.gz";
 const AOM_DIR: &str = "~/.hyperflow/aom";
 const AOM_BUILD_DIR: &str = "~/.hyperflow/aom_build";
+// Optional cmake generator for the libaom build, e.g. "Ninja"
+const GENERATOR_ENV: &str = "AOM_CMAKE_GENERATOR";
+// Written in the build directory, records the generator of the cached build
+const GENERATOR_STAMP: &str = "aom-rs-generator";
 
 const HEADERS: [&str; 5] = [
     "aom/aom.h",
@@ -98,6 +102,18 @@ fn build_libaom() {
     let aom_src_dir = shellexpand::tilde(AOM_DIR).to_string();
     let aom_build_dir = shellexpand::tilde(AOM_BUILD_DIR).to_string();
 
+    // empty for cmake's default generator
+    let generator = env::var(GENERATOR_ENV).unwrap_or_default();
+    let stamp_path = Path::new(&aom_build_dir).join(GENERATOR_STAMP);
+
+    // cmake can't switch generators in an existing build tree, start over
+    if Path::new(&aom_build_dir).exists()
+        && fs::read_to_string(&stamp_path).unwrap_or_default() != generator
+    {
+        println!("cmake generator changed, removing {:?}", aom_build_dir);
+        fs::remove_dir_all(&aom_build_dir).expect("Failed to remove aom_build directory");
+    }
+
     if !Path::new(&aom_build_dir).exists() {
         fs::create_dir_all(&aom_build_dir).expect("Failed to create aom_build directory");
         println!("Created directory: {:?}", aom_build_dir);
@@ -108,35 +124,84 @@ fn build_libaom() {
     if !Path::new(&format!("{}/libaom.so", aom_build_dir)).exists() {
         println!("Building libaom...");
 
-        let cmake_status = Command::new("cmake")
+        let mut cmake = Command::new("cmake");
+        cmake
             .arg(&aom_src_dir)
             .arg(format!("-DCMAKE_INSTALL_PREFIX={}", aom_src_dir))
             .arg("-DBUILD_SHARED_LIBS=ON")
-            .current_dir(&aom_build_dir)
-            .status()
-            .expect("Failed to run cmake");
+            .current_dir(&aom_build_dir);
+
+        if !generator.is_empty() {
+            println!("Using cmake generator: {}", generator);
+            cmake.arg("-G").arg(&generator);
+        }
+
+        let cmake_status = cmake.status().expect("Failed to run cmake");
 
         if !cmake_status.success() {
             panic!("CMake failed");
         }
 
-        let make_status = Command::new("make")
+        // cargo sets NUM_JOBS to the -j of the cargo invocation
+        let jobs = env::var("NUM_JOBS").unwrap_or_else(|_| "1".to_string());
+        println!("Building with {} jobs", jobs);
+
+        // cmake --build drives whatever generator was picked (make, ninja, msbuild...)
+        let build_status = Command::new("cmake")
+            .arg("--build")
+            .arg(".")
+            .arg("--parallel")
+            .arg(&jobs)
             .current_dir(&aom_build_dir)
             .status()
-            .expect("Failed to run make");
+            .expect("Failed to run cmake --build");
 
-        if !make_status.success() {
-            panic!("Make failed");
+        if !build_status.success() {
+            panic!("Build failed");
         }
 
+        fs::write(&stamp_path, &generator).expect("Failed to write the generator stamp");
+
         println!("libaom built successfully!");
     } else {
         println!("libaom is already built");
     }
 }
 
+// Version of the libaom that was built, from the aom_version.h cmake generates. Falls
+// back to LIBAOM_VERSION when the header is missing.
+fn built_libaom_version(aom_build_path: &str) -> String {
+    let header = Path::new(aom_build_path)
+        .join("config")
+        .join("aom_version.h");
+    let content = match fs::read_to_string(&header) {
+        Ok(content) => content,
+        Err(_) => return LIBAOM_VERSION.to_string(),
+    };
+
+    let define = |name: &str| -> Option<u32> {
+        content.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("#define"), Some(key), Some(value)) if key == name => value.parse().ok(),
+                _ => None,
+            }
+        })
+    };
+
+    match (
+        define("VERSION_MAJOR"),
+        define("VERSION_MINOR"),
+        define("VERSION_PATCH"),
+    ) {
+        (Some(major), Some(minor), Some(patch)) => format!("{}.{}.{}", major, minor, patch),
+        _ => LIBAOM_VERSION.to_string(),
+    }
+}
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={}", GENERATOR_ENV);
 
     let aom_include_path = shellexpand::tilde(AOM_DIR).to_string();
     let aom_build_path = shellexpand::tilde(AOM_BUILD_DIR).to_string();
@@ -211,6 +276,12 @@ fn main() {
     fs::copy(&bindings_file_path, &src_bindings_path)
         .expect("Failed to copy generated bindings to src directory");
 
+    // read by AOM_BUILD_VERSION in src/core/version.rs
+    println!(
+        "cargo:rustc-env=AOM_BUILD_VERSION={}",
+        built_libaom_version(&aom_build_path)
+    );
+
     println!("cargo:rustc-link-search=native={}", aom_build_path);
     println!("cargo:rustc-link-lib=aom");
 }
@@ -0,0 +1,90 @@
+//! Encodes a short synthetic clip in two passes at a target bitrate and writes the
+//! second pass bitstream (low-overhead OBUs) to the file given as first argument.
+//!
+//! cargo run --example two_pass -- out.obu
+
+use aom_rs::{
+    aom::aom_rational,
+    core::{
+        config::{AV1EncoderConfig, RateControl},
+        encoder::{AOMPacket, AV1Encoder},
+        twopass::TwoPassEncoder,
+        AomCodecEncCfgTrait,
+    },
+};
+use av_data::{
+    frame::{Frame, FrameBufferConv, FrameType, VideoInfo},
+    pixel::formats::YUV420,
+    timeinfo::TimeInfo,
+};
+use std::{env, error::Error, fs, sync::Arc};
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 240;
+const FRAMES: usize = 30;
+
+// A diagonal gradient scrolling to the right, with flat chroma
+fn gradient_frame(index: usize) -> Frame {
+    let info: VideoInfo = VideoInfo::new(WIDTH, HEIGHT, false, FrameType::OTHER, Arc::new(*YUV420));
+    let t: TimeInfo = TimeInfo {
+        pts: Some(index as i64),
+        duration: Some(1),
+        ..Default::default()
+    };
+    let mut frame: Frame = Frame::new_default_frame(info, Some(t));
+
+    let stride: usize = frame.buf.linesize(0).unwrap();
+    let luma: &mut [u8] = frame.buf.as_mut_slice(0).unwrap();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            luma[y * stride + x] = ((x + y + index * 4) % 256) as u8;
+        }
+    }
+    for plane in 1..3 {
+        let chroma: &mut [u8] = frame.buf.as_mut_slice(plane).unwrap();
+        chroma.fill(128);
+    }
+
+    frame
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let output: String = env::args().nth(1).unwrap_or_else(|| "two_pass.obu".into());
+    let frames: Vec<Frame> = (0..FRAMES).map(gradient_frame).collect();
+
+    let mut config: AV1EncoderConfig = AV1EncoderConfig::for_good_quality()?;
+    config
+        .g_w(WIDTH as u32)
+        .g_h(HEIGHT as u32)
+        .g_timebase(aom_rational { num: 1, den: 30 })
+        .rc_target_bitrate(300)
+        .rate_control(RateControl::Vbr);
+
+    let mut two_pass: TwoPassEncoder = TwoPassEncoder::new(&config)?;
+    for frame in &frames {
+        two_pass.first_pass(frame)?;
+    }
+    println!("first pass: {} bytes of stats", two_pass.stats_len());
+
+    let mut encoder: AV1Encoder = two_pass.second_pass()?;
+    let mut packets: Vec<AOMPacket> = Vec::new();
+    for frame in &frames {
+        encoder.aom_codec_encode(frame)?;
+        packets.extend(encoder.packets());
+    }
+    packets.extend(encoder.finish()?);
+
+    let mut data: Vec<u8> = Vec::new();
+    for pkt in packets {
+        if let AOMPacket::Frame(packet) = pkt {
+            data.extend_from_slice(&packet.data);
+        }
+    }
+
+    // bitrate over the clip duration, 30 frames at 30 fps
+    let kbps: f64 = (data.len() * 8) as f64 / 1000.0 / (FRAMES as f64 / 30.0);
+    println!("second pass: {} bytes, {:.0} kbps", data.len(), kbps);
+    fs::write(&output, &data)?;
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use super::errors::AomError;
+use std::{
+    alloc::{self, Layout},
+    ops::Deref,
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// Growable byte buffer whose start is aligned to a caller chosen power of two, e.g.
+/// for DMA or GPU uploads that need page or cache line aligned data. Filled by
+/// `AV1Encoder::get_packet_into`, the allocation is reused across packets.
+pub struct AlignedVec {
+    ptr: NonNull<u8>,
+    len: usize,
+    cap: usize,
+    align: usize,
+}
+
+impl AlignedVec {
+    /// Empty buffer, nothing is allocated until data is added.
+    /// `align` must be a power of two, otherwise `AomError::InvalidParam` is returned.
+    pub fn new(align: usize) -> Result<Self, AomError> {
+        Self::with_capacity(align, 0)
+    }
+
+    pub fn with_capacity(align: usize, capacity: usize) -> Result<Self, AomError> {
+        if !align.is_power_of_two() {
+            return Err(AomError::InvalidParam);
+        }
+
+        let mut buf: AlignedVec = AlignedVec {
+            // dangling but aligned, like the pointer of an empty Vec
+            ptr: NonNull::new(align as *mut u8).unwrap(),
+            len: 0,
+            cap: 0,
+            align,
+        };
+        buf.reserve(capacity)?;
+        Ok(buf)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    pub fn alignment(&self) -> usize {
+        self.align
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    /// Drops the contents, the allocation is kept
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Makes room for at least `additional` more bytes. Fails with
+    /// `AomError::MemError` when the allocation fails or the size overflows.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), AomError> {
+        let needed: usize = self.len.checked_add(additional).ok_or(AomError::MemError)?;
+        if needed <= self.cap {
+            return Ok(());
+        }
+
+        let cap: usize = needed.max(self.cap * 2);
+        let layout: Layout =
+            Layout::from_size_align(cap, self.align).map_err(|_| AomError::MemError)?;
+        let ptr: *mut u8 = unsafe {
+            if self.cap == 0 {
+                alloc::alloc(layout)
+            } else {
+                alloc::realloc(self.ptr.as_ptr(), self.layout(), cap)
+            }
+        };
+
+        self.ptr = NonNull::new(ptr).ok_or(AomError::MemError)?;
+        self.cap = cap;
+        Ok(())
+    }
+
+    pub fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), AomError> {
+        self.reserve(data.len())?;
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), self.ptr.as_ptr().add(self.len), data.len());
+        }
+        self.len += data.len();
+        Ok(())
+    }
+
+    fn layout(&self) -> Layout {
+        // checked when the current allocation was made
+        unsafe { Layout::from_size_align_unchecked(self.cap, self.align) }
+    }
+}
+
+impl Deref for AlignedVec {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl Drop for AlignedVec {
+    fn drop(&mut self) {
+        if self.cap > 0 {
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout()) };
+        }
+    }
+}
+
+// The buffer is uniquely owned, like a Vec<u8>
+unsafe impl Send for AlignedVec {}
+unsafe impl Sync for AlignedVec {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_aligned(buf: &AlignedVec) -> bool {
+        buf.as_ptr() as usize % buf.alignment() == 0
+    }
+
+    #[test]
+    fn alignment_survives_growth() {
+        for align in [1, 16, 64, 4096] {
+            let mut buf: AlignedVec = AlignedVec::new(align).unwrap();
+            // the dangling pointer of an empty buffer is aligned too
+            assert!(is_aligned(&buf));
+            assert_eq!(buf.capacity(), 0);
+
+            let mut expected: Vec<u8> = Vec::new();
+            for chunk in 0..20u8 {
+                let data: Vec<u8> = vec![chunk; 100 + chunk as usize * 37];
+                buf.extend_from_slice(&data).unwrap();
+                expected.extend_from_slice(&data);
+                assert!(is_aligned(&buf));
+            }
+            assert_eq!(buf.as_slice(), &expected[..]);
+
+            buf.reserve(1 << 20).unwrap();
+            assert!(buf.capacity() >= expected.len() + (1 << 20));
+            assert!(is_aligned(&buf));
+            assert_eq!(buf.as_slice(), &expected[..]);
+        }
+    }
+
+    #[test]
+    fn clear_keeps_the_allocation() {
+        let mut buf: AlignedVec = AlignedVec::with_capacity(64, 256).unwrap();
+        assert!(buf.capacity() >= 256);
+        buf.extend_from_slice(&[1; 200]).unwrap();
+        let ptr: *const u8 = buf.as_ptr();
+        let cap: usize = buf.capacity();
+
+        buf.clear();
+        assert!(buf.is_empty());
+        buf.extend_from_slice(&[2; 100]).unwrap();
+        assert_eq!((buf.as_ptr(), buf.capacity()), (ptr, cap));
+        assert_eq!(buf.as_slice(), &[2; 100][..]);
+    }
+
+    #[test]
+    fn rejects_invalid_alignments_and_sizes() {
+        for align in [0, 3, 24, 100] {
+            assert!(matches!(
+                AlignedVec::new(align),
+                Err(AomError::InvalidParam)
+            ));
+            assert!(matches!(
+                AlignedVec::with_capacity(align, 16),
+                Err(AomError::InvalidParam)
+            ));
+        }
+
+        let mut buf: AlignedVec = AlignedVec::new(16).unwrap();
+        assert!(matches!(buf.reserve(usize::MAX), Err(AomError::MemError)));
+        buf.extend_from_slice(&[0; 8]).unwrap();
+        assert!(matches!(buf.reserve(usize::MAX), Err(AomError::MemError)));
+        assert_eq!(buf.len(), 8);
+    }
+}
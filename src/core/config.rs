@@ -1,15 +1,16 @@
-use super::AomCodecEncCfgTrait;
+use super::{errors::AomError, AomCodecEncCfgTrait};
 use crate::aom::{
     aom_bit_depth, aom_codec_av1_cx, aom_codec_ctx, aom_codec_enc_cfg,
     aom_codec_enc_config_default, aom_codec_er_flags_t, aom_codec_err_t,
-    aom_codec_err_t_AOM_CODEC_OK, aom_enc_pass, aom_fixed_buf_t, aom_kf_mode, aom_rational,
-    aom_rc_mode, aom_superres_mode, cfg_options_t,
+    aom_codec_err_t_AOM_CODEC_OK, aom_enc_pass, aom_enc_pass_AOM_RC_FIRST_PASS,
+    aom_enc_pass_AOM_RC_LAST_PASS, aom_enc_pass_AOM_RC_ONE_PASS, aom_fixed_buf_t, aom_kf_mode,
+    aom_kf_mode_AOM_KF_AUTO, aom_kf_mode_AOM_KF_DISABLED, aom_rational, aom_rc_mode,
+    aom_rc_mode_AOM_CBR, aom_rc_mode_AOM_CQ, aom_rc_mode_AOM_Q, aom_rc_mode_AOM_VBR,
+    aom_superres_mode, cfg_options_t, AOM_USAGE_ALL_INTRA, AOM_USAGE_GOOD_QUALITY,
+    AOM_USAGE_REALTIME,
 };
 use core::mem::MaybeUninit;
-use std::{
-    error::Error,
-    ops::{Deref, DerefMut},
-};
+use std::{ops::Deref, os::raw::c_void, sync::Arc};
 
 /// A struct representing an AV1 Encoder.
 ///
@@ -17,33 +18,163 @@ use std::{
 /// using the AV1 codec. It holds the configuration settings defined in the
 /// `aom_codec_enc_cfg` structure, allowing users to set various encoding parameters.
 ///
-/// # Fields
-/// - `enc_cfg`: An instance of `aom_codec_enc_cfg` that contains all the necessary
-///   configuration options for the encoder.
-///
 /// # Usage
-/// To create an instance of `AV1Encoder`, you can initialize the `enc_cfg` field
-/// with the desired encoding parameters. After initializing, you can modify the
-/// settings using method chaining.
+/// Create an instance with `init`, which starts from libaom's defaults for a usage,
+/// then modify the settings using method chaining. The raw configuration can be read
+/// through `Deref`. Writing it directly is `unsafe` (`as_raw_mut`), because it holds
+/// the pointers of the two-pass buffers; those are set with `rc_twopass_stats_in` and
+/// `rc_firstpass_mb_stats_in`, which keep the buffers alive.
 ///
 /// # Example
-/// ```rust
-/// let mut encoder = AV1Encoder {
-///     enc_cfg: aom_codec_enc_cfg {
-///         // Initialize with default values or your custom settings.
-///         ..Default::default()
-///     },
-/// };
+/// ```rust,no_run
+/// # use aom_rs::core::{config::{AV1EncoderConfig, RateControl}, AomCodecEncCfgTrait};
+/// # fn main() -> Result<(), aom_rs::core::AomError> {
+/// let mut config = AV1EncoderConfig::for_good_quality()?;
 ///
-/// encoder.rc_target_bitrate(3000) // Set target bitrate to 3000 kbps
-///     .rc_end_usage(aom_rc_mode::AOM_VBR); // Set rate control mode to VBR
+/// config.rc_target_bitrate(3000) // Set target bitrate to 3000 kbps
+///     .rate_control(RateControl::Vbr); // Set rate control mode to VBR
+/// # Ok(())
+/// # }
 /// ```
+#[derive(Clone)]
 pub struct AV1EncoderConfig {
-    pub enc_cfg: aom_codec_enc_cfg,
+    pub(crate) enc_cfg: aom_codec_enc_cfg,
+    // owners of the buffers rc_twopass_stats_in and rc_firstpass_mb_stats_in point to
+    pub(crate) twopass_stats: Option<FixedBuf>,
+    pub(crate) mb_stats: Option<FixedBuf>,
+    // AOME_SET_CQ_LEVEL is a control, AV1Encoder::new applies it after init
+    pub(crate) cq_level: Option<u32>,
+}
+
+/// Owned, immutable buffer for the aom_fixed_buf_t fields of the configuration.
+///
+/// libaom reads two-pass stats straight from the buffer for the whole second pass, so
+/// it must outlive every encoder created from the configuration. Clones share the
+/// data: the configuration and each encoder keep a reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedBuf(Arc<[u8]>);
+
+impl FixedBuf {
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn to_aom(&self) -> aom_fixed_buf_t {
+        aom_fixed_buf_t {
+            buf: self.0.as_ptr() as *mut c_void,
+            sz: self.0.len(),
+        }
+    }
+}
+
+impl From<Vec<u8>> for FixedBuf {
+    fn from(data: Vec<u8>) -> Self {
+        FixedBuf(data.into())
+    }
+}
+
+impl From<&[u8]> for FixedBuf {
+    fn from(data: &[u8]) -> Self {
+        FixedBuf(data.into())
+    }
+}
+
+/// Internal resize mode (rc_resize_mode), libaom's RESIZE_* values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResizeMode {
+    /// Always code at the configured size
+    #[default]
+    None = 0,
+    /// Code every frame at the scale given by the denominators
+    Fixed = 1,
+    /// Pick a random scale per frame, meant for testing decoders
+    Random = 2,
+    /// Scale down and back up with rate control pressure (realtime usage, one pass CBR)
+    Dynamic = 3,
+}
+
+/// Rate control mode (rc_end_usage)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateControl {
+    /// AOM_VBR, variable bitrate around rc_target_bitrate
+    Vbr,
+    /// AOM_CBR, constant bitrate for streaming, bounded by the rc_buf_* buffer model
+    Cbr,
+    /// AOM_CQ, constrained quality: the CQ level, capped at rc_target_bitrate
+    Cq,
+    /// AOM_Q, constant quality, no bitrate target
+    Q,
+}
+
+impl From<RateControl> for aom_rc_mode {
+    fn from(mode: RateControl) -> Self {
+        match mode {
+            RateControl::Vbr => aom_rc_mode_AOM_VBR,
+            RateControl::Cbr => aom_rc_mode_AOM_CBR,
+            RateControl::Cq => aom_rc_mode_AOM_CQ,
+            RateControl::Q => aom_rc_mode_AOM_Q,
+        }
+    }
+}
+
+/// Encoding pass (g_pass)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodePass {
+    /// AOM_RC_ONE_PASS, a single pass without first pass stats
+    One,
+    /// AOM_RC_FIRST_PASS, only collects stats (`AOMPacket::TwoPassStats`)
+    First,
+    /// AOM_RC_LAST_PASS, the final pass of a two-pass encode, reads rc_twopass_stats_in
+    Last,
+}
+
+impl From<EncodePass> for aom_enc_pass {
+    fn from(pass: EncodePass) -> Self {
+        match pass {
+            EncodePass::One => aom_enc_pass_AOM_RC_ONE_PASS,
+            EncodePass::First => aom_enc_pass_AOM_RC_FIRST_PASS,
+            EncodePass::Last => aom_enc_pass_AOM_RC_LAST_PASS,
+        }
+    }
+}
+
+/// Keyframe placement (kf_mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyframeMode {
+    /// AOM_KF_AUTO, keyframes at scene cuts and at most kf_max_dist frames apart
+    Auto,
+    /// AOM_KF_DISABLED, only the first frame and forced ones (AOM_EFLAG_FORCE_KF)
+    Disabled,
 }
 
+impl From<KeyframeMode> for aom_kf_mode {
+    fn from(mode: KeyframeMode) -> Self {
+        match mode {
+            KeyframeMode::Auto => aom_kf_mode_AOM_KF_AUTO,
+            KeyframeMode::Disabled => aom_kf_mode_AOM_KF_DISABLED,
+        }
+    }
+}
+
+// rc_min_quantizer / rc_max_quantizer are on the 0..=63 scale
+const MAX_QUANTIZER: u32 = 63;
+// entries of tile_widths / tile_heights
+const MAX_TILE_COUNT: i32 = 64;
+
+// Resize and superres scale frames by 8 / denominator
+const SCALE_NUMERATOR: u32 = 8;
+const SCALE_DENOMINATOR_MAX: u32 = 16;
+
 impl AV1EncoderConfig {
-    pub fn init(config: u32) -> Result<Self, Box<dyn Error>> {
+    pub fn init(config: u32) -> Result<Self, AomError> {
         // Initialize the variable only when it has a value later on.
         let mut cfg: MaybeUninit<aom_codec_enc_cfg> = MaybeUninit::uninit();
         // This is either 0 or 1 depending on whether the function passes or fails
@@ -54,11 +185,173 @@ impl AV1EncoderConfig {
             aom_codec_err_t_AOM_CODEC_OK => {
                 let cfg: aom_codec_enc_cfg = unsafe { cfg.assume_init() };
 
-                Ok(AV1EncoderConfig { enc_cfg: cfg })
+                Ok(AV1EncoderConfig {
+                    enc_cfg: cfg,
+                    twopass_stats: None,
+                    mb_stats: None,
+                    cq_level: None,
+                })
+            }
+            _ => Err(is_success.into()),
+        }
+    }
+
+    /// `init` with AOM_USAGE_GOOD_QUALITY: offline encoding with lookahead, the libaom
+    /// default. Fails on CONFIG_REALTIME_ONLY builds of libaom
+    /// (see `EncoderCaps::realtime_only`), which only have `for_realtime`.
+    pub fn for_good_quality() -> Result<Self, AomError> {
+        Self::init(AOM_USAGE_GOOD_QUALITY)
+    }
+
+    /// `init` with AOM_USAGE_REALTIME: low latency encoding (video calls, streaming)
+    pub fn for_realtime() -> Result<Self, AomError> {
+        Self::init(AOM_USAGE_REALTIME)
+    }
+
+    /// `init` with AOM_USAGE_ALL_INTRA: every frame is a keyframe (still images, AVIF)
+    pub fn for_all_intra() -> Result<Self, AomError> {
+        Self::init(AOM_USAGE_ALL_INTRA)
+    }
+
+    /// Sets rc_resize_mode, rc_resize_denominator and rc_resize_kf_denominator together.
+    /// Frames are coded at 8 / `denom` of the configured size (keyframes at
+    /// 8 / `kf_denom`), so both denominators must be in 8..=16; 8 means no scaling.
+    pub fn configure_resize(
+        &mut self,
+        mode: ResizeMode,
+        denom: u32,
+        kf_denom: u32,
+    ) -> Result<&mut Self, AomError> {
+        let valid = SCALE_NUMERATOR..=SCALE_DENOMINATOR_MAX;
+        if !valid.contains(&denom) || !valid.contains(&kf_denom) {
+            return Err(AomError::InvalidParam);
+        }
+
+        Ok(self
+            .rc_resize_mode(mode as u32)
+            .rc_resize_denominator(denom)
+            .rc_resize_kf_denominator(kf_denom))
+    }
+
+    /// Typed `rc_end_usage`
+    pub fn rate_control(&mut self, mode: RateControl) -> &mut Self {
+        self.rc_end_usage(mode.into())
+    }
+
+    /// Typed `g_pass`
+    pub fn encode_pass(&mut self, pass: EncodePass) -> &mut Self {
+        self.g_pass(pass.into())
+    }
+
+    /// Typed `kf_mode`
+    pub fn keyframe_mode(&mut self, mode: KeyframeMode) -> &mut Self {
+        self.kf_mode(mode.into())
+    }
+
+    /// Constant quality rate control (AOM_CQ) at level `cq` (0..=63, lower is better),
+    /// with rc_target_bitrate as a cap. The level is a control rather than a field, so
+    /// it is kept here and applied by `AV1Encoder::new`. Fails with
+    /// `AomError::InvalidParam` for a level above 63.
+    pub fn constant_quality(&mut self, cq: u32) -> Result<&mut Self, AomError> {
+        if cq > MAX_QUANTIZER {
+            return Err(AomError::InvalidParam);
+        }
+
+        self.cq_level = Some(cq);
+        Ok(self.rate_control(RateControl::Cq))
+    }
+
+    /// Level set with `constant_quality`
+    pub fn cq_level(&self) -> Option<u32> {
+        self.cq_level
+    }
+
+    /// Lossless coding: rc_min_quantizer and rc_max_quantizer at 0 with AOM_Q (fixed
+    /// quantizer) rate control, so every frame is coded at qindex 0, which AV1 codes
+    /// without loss. Decoded frames are bit-exact copies of the input, in the input's
+    /// format (chroma subsampling is not undone). Pair it with
+    /// `AV1Encoder::set_lossless`.
+    pub fn lossless(&mut self) -> &mut Self {
+        self.rc_min_quantizer(0)
+            .rc_max_quantizer(0)
+            .rate_control(RateControl::Q)
+    }
+
+    /// Sets fwd_kf_enabled. Forward keyframes are placed at the end of a GOP and coded
+    /// ahead of the frames before them (which can reference them), so the encoder needs
+    /// a lookahead and automatic keyframe placement. Enabling them fails with
+    /// `AomError::InvalidParam` unless g_lag_in_frames > 0, kf_mode is AOM_KF_AUTO with
+    /// kf_max_dist > 0, and the usage is not realtime. Set those first; `validate`
+    /// checks them again in case they change afterwards.
+    pub fn enable_forward_keyframes(&mut self, on: bool) -> Result<&mut Self, AomError> {
+        if on && !self.forward_keyframes_allowed() {
+            return Err(AomError::InvalidParam);
+        }
+
+        Ok(self.fwd_kf_enabled(on as i32))
+    }
+
+    /// Checks the fields whose mistakes libaom only reports as a bare
+    /// AOM_CODEC_INVALID_PARAM from aom_codec_enc_init_ver: a non-zero size, a
+    /// quantizer range within 0..=63 with min <= max, tile counts within 0..=64, and
+    /// the requirements of forward keyframes when fwd_kf_enabled is set. Fails with
+    /// `AomError::InvalidConfig` naming the field. `AV1Encoder::new` calls it before
+    /// initializing libaom.
+    pub fn validate(&self) -> Result<(), AomError> {
+        let cfg: &aom_codec_enc_cfg = &self.enc_cfg;
+        let invalid =
+            |field: &'static str, reason: String| Err(AomError::InvalidConfig { field, reason });
+
+        if cfg.g_w == 0 {
+            return invalid("g_w", "must be greater than 0".to_string());
+        }
+        if cfg.g_h == 0 {
+            return invalid("g_h", "must be greater than 0".to_string());
+        }
+        if cfg.rc_max_quantizer > MAX_QUANTIZER {
+            return invalid(
+                "rc_max_quantizer",
+                format!("{} is above {}", cfg.rc_max_quantizer, MAX_QUANTIZER),
+            );
+        }
+        if cfg.rc_min_quantizer > cfg.rc_max_quantizer {
+            return invalid(
+                "rc_min_quantizer",
+                format!(
+                    "{} is above rc_max_quantizer ({})",
+                    cfg.rc_min_quantizer, cfg.rc_max_quantizer
+                ),
+            );
+        }
+        for (field, count) in [
+            ("tile_width_count", cfg.tile_width_count),
+            ("tile_height_count", cfg.tile_height_count),
+        ] {
+            if !(0..=MAX_TILE_COUNT).contains(&count) {
+                return invalid(
+                    field,
+                    format!("{} is outside 0..={}", count, MAX_TILE_COUNT),
+                );
             }
-            // Convert aom_codec_err_t to Box<dyn Error>> and return
-            _ => Err(format!("Failed to initialize encoder: error code {is_success}").into()),
         }
+        if cfg.fwd_kf_enabled != 0 && !self.forward_keyframes_allowed() {
+            return invalid(
+                "fwd_kf_enabled",
+                "needs g_lag_in_frames > 0, kf_mode AOM_KF_AUTO with kf_max_dist > 0 and a \
+                 non realtime usage"
+                    .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    // the conditions of `enable_forward_keyframes`
+    fn forward_keyframes_allowed(&self) -> bool {
+        self.enc_cfg.g_lag_in_frames > 0
+            && self.enc_cfg.kf_mode == aom_kf_mode_AOM_KF_AUTO
+            && self.enc_cfg.kf_max_dist > 0
+            && self.enc_cfg.g_usage != AOM_USAGE_REALTIME
     }
 }
 
@@ -185,6 +478,12 @@ impl AomCodecEncCfgTrait for AV1EncoderConfig {
 
     /// Sets the time base for the encoder configuration.
     ///
+    /// Frame pts and durations are counted in ticks of this time base, so a frame that is
+    /// shown for `duration` ticks lasts `duration * num / den` seconds. Rate control spreads
+    /// the bitrate over that time: with a 1/90000 time base, a frame encoded with
+    /// `encode_with(frame, 2, 0)` only gets 2/90000 s worth of bits. For variable frame rate
+    /// content pick a fine time base and pass every frame's real duration.
+    ///
     /// # Parameters
     /// - `value`: An `aom_rational` that represents the time base.
     ///
@@ -353,25 +652,35 @@ impl AomCodecEncCfgTrait for AV1EncoderConfig {
 
     /// Sets the input statistics for the two-pass encoding.
     ///
+    /// The configuration keeps the buffer alive, and so does every encoder created
+    /// from it.
+    ///
     /// # Parameters
-    /// - `value`: An `aom_fixed_buf_t` that contains the statistics buffer.
+    /// - `value`: A `FixedBuf` that contains the statistics buffer.
     ///
     /// # Returns
     /// A mutable reference to `Self`, allowing method chaining.
-    fn rc_twopass_stats_in(&mut self, value: aom_fixed_buf_t) -> &mut Self {
-        self.enc_cfg.rc_twopass_stats_in = value;
+    fn rc_twopass_stats_in(&mut self, value: FixedBuf) -> &mut Self {
+        self.enc_cfg.rc_twopass_stats_in = value.to_aom();
+        self.twopass_stats = Some(value);
         self
     }
 
     /// Sets the first-pass macroblock statistics for the encoder.
     ///
+    /// The buffer is the concatenation of the AOMPacket::FirstPassMBStats packets of the
+    /// first pass, see `MbStatsBuffer`. libaom only produces and reads these stats when it
+    /// is built with the (experimental) first pass MB stats support; a stock build never
+    /// emits the packets and ignores this buffer. There is no flag or control to turn it on.
+    ///
     /// # Parameters
-    /// - `value`: An `aom_fixed_buf_t` that contains the first-pass macroblock statistics buffer.
+    /// - `value`: A `FixedBuf` that contains the first-pass macroblock statistics buffer.
     ///
     /// # Returns
     /// A mutable reference to `Self`, allowing method chaining.
-    fn rc_firstpass_mb_stats_in(&mut self, value: aom_fixed_buf_t) -> &mut Self {
-        self.enc_cfg.rc_firstpass_mb_stats_in = value;
+    fn rc_firstpass_mb_stats_in(&mut self, value: FixedBuf) -> &mut Self {
+        self.enc_cfg.rc_firstpass_mb_stats_in = value.to_aom();
+        self.mb_stats = Some(value);
         self
     }
 
@@ -712,6 +1021,201 @@ impl AomCodecEncCfgTrait for AV1EncoderConfig {
     }
 }
 
+impl AV1EncoderConfig {
+    /// Fields that differ between two configurations, as (field name, value in `self`,
+    /// value in `other`), in declaration order. Fields of `encoder_cfg` are prefixed
+    /// with `encoder_cfg.`, only the used entries of the tile size arrays are
+    /// compared, and the two-pass buffers are compared by content but shown by size.
+    pub fn diff(&self, other: &Self) -> Vec<(&'static str, String, String)> {
+        let (a, b) = (&self.enc_cfg, &other.enc_cfg);
+        let mut fields: Vec<(&'static str, String, String)> = Vec::new();
+
+        macro_rules! diff_fields {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if a.$field != b.$field {
+                        fields.push((
+                            stringify!($field),
+                            format!("{:?}", a.$field),
+                            format!("{:?}", b.$field),
+                        ));
+                    }
+                )*
+            };
+        }
+        macro_rules! diff_encoder_cfg {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if a.encoder_cfg.$field != b.encoder_cfg.$field {
+                        fields.push((
+                            concat!("encoder_cfg.", stringify!($field)),
+                            format!("{:?}", a.encoder_cfg.$field),
+                            format!("{:?}", b.encoder_cfg.$field),
+                        ));
+                    }
+                )*
+            };
+        }
+
+        diff_fields!(
+            g_usage,
+            g_threads,
+            g_profile,
+            g_w,
+            g_h,
+            g_limit,
+            g_forced_max_frame_width,
+            g_forced_max_frame_height,
+            g_bit_depth,
+            g_input_bit_depth,
+        );
+        if (a.g_timebase.num, a.g_timebase.den) != (b.g_timebase.num, b.g_timebase.den) {
+            fields.push((
+                "g_timebase",
+                format!("{}/{}", a.g_timebase.num, a.g_timebase.den),
+                format!("{}/{}", b.g_timebase.num, b.g_timebase.den),
+            ));
+        }
+        diff_fields!(
+            g_error_resilient,
+            g_pass,
+            g_lag_in_frames,
+            rc_dropframe_thresh,
+            rc_resize_mode,
+            rc_resize_denominator,
+            rc_resize_kf_denominator,
+            rc_superres_mode,
+            rc_superres_denominator,
+            rc_superres_kf_denominator,
+            rc_superres_qthresh,
+            rc_superres_kf_qthresh,
+            rc_end_usage,
+        );
+        if self.twopass_stats != other.twopass_stats {
+            fields.push((
+                "rc_twopass_stats_in",
+                describe_buf(&self.twopass_stats),
+                describe_buf(&other.twopass_stats),
+            ));
+        }
+        if self.mb_stats != other.mb_stats {
+            fields.push((
+                "rc_firstpass_mb_stats_in",
+                describe_buf(&self.mb_stats),
+                describe_buf(&other.mb_stats),
+            ));
+        }
+        if self.cq_level != other.cq_level {
+            fields.push((
+                "cq_level",
+                format!("{:?}", self.cq_level),
+                format!("{:?}", other.cq_level),
+            ));
+        }
+        diff_fields!(
+            rc_target_bitrate,
+            rc_min_quantizer,
+            rc_max_quantizer,
+            rc_undershoot_pct,
+            rc_overshoot_pct,
+            rc_buf_sz,
+            rc_buf_initial_sz,
+            rc_buf_optimal_sz,
+            rc_2pass_vbr_bias_pct,
+            rc_2pass_vbr_minsection_pct,
+            rc_2pass_vbr_maxsection_pct,
+            fwd_kf_enabled,
+            kf_mode,
+            kf_min_dist,
+            kf_max_dist,
+            sframe_dist,
+            sframe_mode,
+            large_scale_tile,
+            monochrome,
+            full_still_picture_hdr,
+            save_as_annexb,
+            tile_width_count,
+            tile_height_count,
+            use_fixed_qp_offsets,
+            fixed_qp_offsets,
+        );
+
+        let (widths_a, widths_b) = (
+            used_tiles(&a.tile_widths, a.tile_width_count),
+            used_tiles(&b.tile_widths, b.tile_width_count),
+        );
+        if widths_a != widths_b {
+            fields.push((
+                "tile_widths",
+                format!("{widths_a:?}"),
+                format!("{widths_b:?}"),
+            ));
+        }
+        let (heights_a, heights_b) = (
+            used_tiles(&a.tile_heights, a.tile_height_count),
+            used_tiles(&b.tile_heights, b.tile_height_count),
+        );
+        if heights_a != heights_b {
+            fields.push((
+                "tile_heights",
+                format!("{heights_a:?}"),
+                format!("{heights_b:?}"),
+            ));
+        }
+
+        diff_encoder_cfg!(
+            init_by_cfg_file,
+            super_block_size,
+            max_partition_size,
+            min_partition_size,
+            disable_ab_partition_type,
+            disable_rect_partition_type,
+            disable_1to4_partition_type,
+            disable_flip_idtx,
+            disable_cdef,
+            disable_lr,
+            disable_obmc,
+            disable_warp_motion,
+            disable_global_motion,
+            disable_dist_wtd_comp,
+            disable_diff_wtd_comp,
+            disable_inter_intra_comp,
+            disable_masked_comp,
+            disable_one_sided_comp,
+            disable_palette,
+            disable_intrabc,
+            disable_cfl,
+            disable_smooth_intra,
+            disable_filter_intra,
+            disable_dual_filter,
+            disable_intra_angle_delta,
+            disable_intra_edge_filter,
+            disable_tx_64x64,
+            disable_smooth_inter_intra,
+            disable_inter_inter_wedge,
+            disable_inter_intra_wedge,
+            disable_paeth_intra,
+            disable_trellis_quant,
+            disable_ref_frame_mv,
+            reduced_reference_set,
+            reduced_tx_type_set,
+        );
+        fields
+    }
+}
+
+fn describe_buf(buf: &Option<FixedBuf>) -> String {
+    match buf {
+        Some(buf) => format!("{} bytes", buf.len()),
+        None => "none".to_string(),
+    }
+}
+
+// tile_widths / tile_heights entries that are in use, by their count field
+fn used_tiles(sizes: &[i32], count: i32) -> &[i32] {
+    &sizes[..count.clamp(0, sizes.len() as i32) as usize]
+}
+
 impl Deref for AV1EncoderConfig {
     type Target = aom_codec_enc_cfg;
 
@@ -720,8 +1224,186 @@ impl Deref for AV1EncoderConfig {
     }
 }
 
-impl DerefMut for AV1EncoderConfig {
-    fn deref_mut(&mut self) -> &mut Self::Target {
+impl AV1EncoderConfig {
+    /// Mutable access to the raw configuration, for fields without a setter.
+    ///
+    /// # Safety
+    /// `rc_twopass_stats_in` and `rc_firstpass_mb_stats_in` must stay untouched or
+    /// point to memory that outlives every encoder created from this configuration.
+    pub unsafe fn as_raw_mut(&mut self) -> &mut aom_codec_enc_cfg {
         &mut self.enc_cfg
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // field named by the InvalidConfig error of `validate`
+    fn invalid_field(cfg: &AV1EncoderConfig) -> Option<&'static str> {
+        match cfg.validate() {
+            Ok(()) => None,
+            Err(AomError::InvalidConfig { field, .. }) => Some(field),
+            Err(err) => panic!("unexpected error {err:?}"),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_the_defaults() {
+        for cfg in [
+            AV1EncoderConfig::for_good_quality().unwrap(),
+            AV1EncoderConfig::for_realtime().unwrap(),
+            AV1EncoderConfig::for_all_intra().unwrap(),
+        ] {
+            assert_eq!(invalid_field(&cfg), None);
+        }
+
+        let mut cfg: AV1EncoderConfig = AV1EncoderConfig::for_good_quality().unwrap();
+        cfg.lossless()
+            .tile_width_count(MAX_TILE_COUNT)
+            .tile_height_count(0);
+        assert_eq!(invalid_field(&cfg), None);
+    }
+
+    #[test]
+    fn validate_names_the_invalid_field() {
+        let base: AV1EncoderConfig = AV1EncoderConfig::for_good_quality().unwrap();
+
+        let mut cfg: AV1EncoderConfig = base.clone();
+        cfg.g_w(0);
+        assert_eq!(invalid_field(&cfg), Some("g_w"));
+
+        let mut cfg: AV1EncoderConfig = base.clone();
+        cfg.g_h(0);
+        assert_eq!(invalid_field(&cfg), Some("g_h"));
+
+        let mut cfg: AV1EncoderConfig = base.clone();
+        cfg.rc_max_quantizer(MAX_QUANTIZER + 1);
+        assert_eq!(invalid_field(&cfg), Some("rc_max_quantizer"));
+
+        let mut cfg: AV1EncoderConfig = base.clone();
+        cfg.rc_min_quantizer(40).rc_max_quantizer(20);
+        assert_eq!(invalid_field(&cfg), Some("rc_min_quantizer"));
+
+        let mut cfg: AV1EncoderConfig = base.clone();
+        cfg.tile_width_count(MAX_TILE_COUNT + 1);
+        assert_eq!(invalid_field(&cfg), Some("tile_width_count"));
+
+        let mut cfg: AV1EncoderConfig = base.clone();
+        cfg.tile_height_count(-1);
+        assert_eq!(invalid_field(&cfg), Some("tile_height_count"));
+
+        // the reason carries the offending value
+        let mut cfg: AV1EncoderConfig = base;
+        cfg.rc_max_quantizer(70);
+        match cfg.validate() {
+            Err(AomError::InvalidConfig { reason, .. }) => assert!(reason.contains("70")),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn forward_keyframe_requirements() {
+        let mut cfg: AV1EncoderConfig = AV1EncoderConfig::for_good_quality().unwrap();
+        cfg.g_lag_in_frames(35)
+            .keyframe_mode(KeyframeMode::Auto)
+            .kf_max_dist(120);
+        cfg.enable_forward_keyframes(true).unwrap();
+        assert_eq!(invalid_field(&cfg), None);
+
+        // each requirement broken after enabling them
+        let mut no_lag: AV1EncoderConfig = cfg.clone();
+        no_lag.g_lag_in_frames(0);
+        let mut no_auto: AV1EncoderConfig = cfg.clone();
+        no_auto.keyframe_mode(KeyframeMode::Disabled);
+        let mut no_distance: AV1EncoderConfig = cfg.clone();
+        no_distance.kf_max_dist(0);
+        let mut realtime: AV1EncoderConfig = cfg.clone();
+        realtime.g_usage(AOM_USAGE_REALTIME);
+
+        for mut broken in [no_lag, no_auto, no_distance, realtime] {
+            assert_eq!(invalid_field(&broken), Some("fwd_kf_enabled"));
+            assert!(matches!(
+                broken.enable_forward_keyframes(true),
+                Err(AomError::InvalidParam)
+            ));
+            // disabling them is always possible
+            broken.enable_forward_keyframes(false).unwrap();
+            assert_eq!(invalid_field(&broken), None);
+        }
+    }
+
+    #[test]
+    fn diff_lists_changed_fields_in_order() {
+        let mut a: AV1EncoderConfig = AV1EncoderConfig::for_good_quality().unwrap();
+        a.g_w(640).g_timebase(aom_rational { num: 1, den: 30 });
+        assert!(a.diff(&a.clone()).is_empty());
+
+        let mut b: AV1EncoderConfig = a.clone();
+        let mut encoder_cfg: cfg_options_t = b.encoder_cfg;
+        encoder_cfg.disable_cdef = 1;
+        b.encoder_cfg(encoder_cfg)
+            .g_timebase(aom_rational { num: 1, den: 60 })
+            .g_w(1280)
+            .constant_quality(20)
+            .unwrap();
+
+        let fields: Vec<(&'static str, String, String)> = a.diff(&b);
+        let names: Vec<&str> = fields.iter().map(|(name, _, _)| *name).collect();
+        assert_eq!(
+            names,
+            [
+                "g_w",
+                "g_timebase",
+                "rc_end_usage",
+                "cq_level",
+                "encoder_cfg.disable_cdef"
+            ]
+        );
+        assert_eq!(fields[0], ("g_w", "640".to_string(), "1280".to_string()));
+        assert_eq!(
+            fields[1],
+            ("g_timebase", "1/30".to_string(), "1/60".to_string())
+        );
+        assert_eq!(
+            fields[3],
+            ("cq_level", "None".to_string(), "Some(20)".to_string())
+        );
+
+        // the other way around swaps the values
+        let reversed: Vec<(&'static str, String, String)> = b.diff(&a);
+        assert_eq!(reversed[0], ("g_w", "1280".to_string(), "640".to_string()));
+    }
+
+    #[test]
+    fn diff_compares_used_tiles_and_buffer_contents() {
+        let mut a: AV1EncoderConfig = AV1EncoderConfig::for_good_quality().unwrap();
+        let mut widths: [i32; 64] = [0; 64];
+        widths[..2].copy_from_slice(&[4, 4]);
+        a.tile_width_count(2)
+            .tile_widths(widths)
+            .rc_twopass_stats_in(vec![1, 2, 3, 4].into());
+
+        // entries past tile_width_count and a copy of the stats are no difference
+        let mut b: AV1EncoderConfig = a.clone();
+        widths[5] = 9;
+        b.tile_widths(widths)
+            .rc_twopass_stats_in(vec![1, 2, 3, 4].into());
+        assert!(a.diff(&b).is_empty());
+
+        widths[1] = 8;
+        b.tile_widths(widths)
+            .rc_twopass_stats_in(vec![1, 2, 3, 5, 6].into());
+        assert_eq!(
+            a.diff(&b),
+            [
+                (
+                    "rc_twopass_stats_in",
+                    "4 bytes".to_string(),
+                    "5 bytes".to_string()
+                ),
+                ("tile_widths", "[4, 4]".to_string(), "[4, 8]".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,811 @@
+use super::errors::AomError;
+use crate::aom::{
+    aom_active_map, aom_color_primaries, aom_color_range, aom_color_range_AOM_CR_FULL_RANGE,
+    aom_color_range_AOM_CR_STUDIO_RANGE, aom_com_control_id_AV1_COPY_NEW_FRAME_IMAGE,
+    aom_com_control_id_AV1_COPY_REFERENCE, aom_com_control_id_AV1_GET_NEW_FRAME_IMAGE,
+    aom_com_control_id_AV1_GET_REFERENCE, aom_com_control_id_AV1_SET_REFERENCE, aom_dec_control_id,
+    aom_dec_control_id_AOMD_GET_ALTREF_PRESENT, aom_dec_control_id_AOMD_GET_BASE_Q_IDX,
+    aom_dec_control_id_AOMD_GET_FRAME_CORRUPTED, aom_dec_control_id_AOMD_GET_FRAME_FLAGS,
+    aom_dec_control_id_AOMD_GET_FWD_KF_PRESENT, aom_dec_control_id_AOMD_GET_LAST_QUANTIZER,
+    aom_dec_control_id_AOMD_GET_LAST_REF_UPDATES, aom_dec_control_id_AOMD_GET_LAST_REF_USED,
+    aom_dec_control_id_AOMD_GET_ORDER_HINT, aom_dec_control_id_AOMD_GET_SB_SIZE,
+    aom_dec_control_id_AOMD_GET_SCREEN_CONTENT_TOOLS_INFO,
+    aom_dec_control_id_AOMD_GET_SHOW_EXISTING_FRAME_FLAG,
+    aom_dec_control_id_AOMD_GET_SHOW_FRAME_FLAG, aom_dec_control_id_AOMD_GET_STILL_PICTURE,
+    aom_dec_control_id_AOMD_GET_S_FRAME_INFO, aom_dec_control_id_AOMD_GET_TILE_INFO,
+    aom_dec_control_id_AV1D_GET_BIT_DEPTH, aom_dec_control_id_AV1D_GET_DISPLAY_SIZE,
+    aom_dec_control_id_AV1D_GET_FRAME_HEADER_INFO, aom_dec_control_id_AV1D_GET_FRAME_SIZE,
+    aom_dec_control_id_AV1D_GET_IMG_FORMAT, aom_dec_control_id_AV1D_GET_MI_INFO,
+    aom_dec_control_id_AV1D_GET_TILE_COUNT, aom_dec_control_id_AV1D_GET_TILE_DATA,
+    aom_dec_control_id_AV1D_GET_TILE_SIZE, aom_dec_control_id_AV1D_SET_EXT_REF_PTR,
+    aom_dec_control_id_AV1_GET_ACCOUNTING, aom_dec_control_id_AV1_SET_INSPECTION_CALLBACK,
+    aom_matrix_coefficients, aom_matrix_coefficients_AOM_CICP_MC_BT_601,
+    aom_matrix_coefficients_AOM_CICP_MC_BT_709, aom_superblock_size_AOM_SUPERBLOCK_SIZE_128X128,
+    aom_superblock_size_AOM_SUPERBLOCK_SIZE_64X64, aom_superblock_size_AOM_SUPERBLOCK_SIZE_DYNAMIC,
+    aom_superblock_size_t, aom_svc_layer_id, aom_svc_params, aom_svc_ref_frame_config,
+    aom_transfer_characteristics, aom_tune_content, aom_tune_content_AOM_CONTENT_DEFAULT,
+    aom_tune_content_AOM_CONTENT_FILM, aom_tune_content_AOM_CONTENT_SCREEN, aom_tune_metric,
+    aom_tune_metric_AOM_TUNE_BUTTERAUGLI, aom_tune_metric_AOM_TUNE_PSNR,
+    aom_tune_metric_AOM_TUNE_SSIM, aom_tune_metric_AOM_TUNE_VMAF_MAX_GAIN,
+    aom_tune_metric_AOM_TUNE_VMAF_NEG_MAX_GAIN,
+    aom_tune_metric_AOM_TUNE_VMAF_WITHOUT_PREPROCESSING,
+    aom_tune_metric_AOM_TUNE_VMAF_WITH_PREPROCESSING, aome_enc_control_id,
+    aome_enc_control_id_AOME_GET_LAST_QUANTIZER, aome_enc_control_id_AOME_GET_LAST_QUANTIZER_64,
+    aome_enc_control_id_AOME_GET_LOOPFILTER_LEVEL, aome_enc_control_id_AOME_SET_ACTIVEMAP,
+    aome_enc_control_id_AOME_SET_ROI_MAP, aome_enc_control_id_AOME_SET_SCALEMODE,
+    aome_enc_control_id_AV1E_GET_ACTIVEMAP, aome_enc_control_id_AV1E_GET_BASELINE_GF_INTERVAL,
+    aome_enc_control_id_AV1E_GET_LUMA_CDEF_STRENGTH,
+    aome_enc_control_id_AV1E_GET_NUM_OPERATING_POINTS, aome_enc_control_id_AV1E_GET_SEQ_LEVEL_IDX,
+    aome_enc_control_id_AV1E_GET_TARGET_SEQ_LEVEL_IDX, aome_enc_control_id_AV1E_SET_DELTALF_MODE,
+    aome_enc_control_id_AV1E_SET_ENABLE_CDEF, aome_enc_control_id_AV1E_SET_ENABLE_DIFF_WTD_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_DIST_WTD_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_INTERINTER_WEDGE,
+    aome_enc_control_id_AV1E_SET_ENABLE_INTERINTRA_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_INTERINTRA_WEDGE,
+    aome_enc_control_id_AV1E_SET_ENABLE_MASKED_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_ONESIDED_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_RESTORATION,
+    aome_enc_control_id_AV1E_SET_ENABLE_SMOOTH_INTERINTRA,
+    aome_enc_control_id_AV1E_SET_EXTERNAL_PARTITION, aome_enc_control_id_AV1E_SET_FILM_GRAIN_TABLE,
+    aome_enc_control_id_AV1E_SET_LOOPFILTER_CONTROL,
+    aome_enc_control_id_AV1E_SET_PARTITION_INFO_PATH,
+    aome_enc_control_id_AV1E_SET_RATE_DISTRIBUTION_INFO, aome_enc_control_id_AV1E_SET_RENDER_SIZE,
+    aome_enc_control_id_AV1E_SET_SVC_LAYER_ID, aome_enc_control_id_AV1E_SET_SVC_PARAMS,
+    aome_enc_control_id_AV1E_SET_SVC_REF_FRAME_COMP_PRED,
+    aome_enc_control_id_AV1E_SET_SVC_REF_FRAME_CONFIG,
+    aome_enc_control_id_AV1E_SET_VMAF_MODEL_PATH, AOM_MAX_LAYERS, AOM_MAX_SS_LAYERS,
+    AOM_MAX_TS_LAYERS,
+};
+use av_data::pixel;
+use std::mem;
+
+/// Sample value range signaled in the sequence header (AV1E_SET_COLOR_RANGE)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorRange {
+    /// Studio / limited range, 16-235 for 8-bit luma
+    Limited,
+    /// Full range, 0-255 for 8-bit luma
+    Full,
+}
+
+impl From<ColorRange> for aom_color_range {
+    fn from(range: ColorRange) -> Self {
+        match range {
+            ColorRange::Limited => aom_color_range_AOM_CR_STUDIO_RANGE,
+            ColorRange::Full => aom_color_range_AOM_CR_FULL_RANGE,
+        }
+    }
+}
+
+/// YCbCr matrix used when the crate converts RGB input itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMatrix {
+    /// SD content
+    Bt601,
+    /// HD content, the usual choice for screen captures
+    Bt709,
+}
+
+impl ColorMatrix {
+    /// Luma weights (Kr, Kb) of the matrix, Kg is 1 - Kr - Kb
+    pub(crate) fn coefficients(&self) -> (f32, f32) {
+        match self {
+            ColorMatrix::Bt601 => (0.299, 0.114),
+            ColorMatrix::Bt709 => (0.2126, 0.0722),
+        }
+    }
+}
+
+impl From<ColorMatrix> for aom_matrix_coefficients {
+    fn from(matrix: ColorMatrix) -> Self {
+        match matrix {
+            ColorMatrix::Bt601 => aom_matrix_coefficients_AOM_CICP_MC_BT_601,
+            ColorMatrix::Bt709 => aom_matrix_coefficients_AOM_CICP_MC_BT_709,
+        }
+    }
+}
+
+/// Color primaries signaled in the sequence header (AV1E_SET_COLOR_PRIMARIES), with
+/// their ITU-T H.273 code points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorPrimaries {
+    Bt709 = 1,
+    Unspecified = 2,
+    Bt470M = 4,
+    Bt470BG = 5,
+    Bt601 = 6,
+    Smpte240 = 7,
+    GenericFilm = 8,
+    /// Wide gamut, used by HDR10 and HLG
+    Bt2020 = 9,
+    Xyz = 10,
+    /// DCI-P3
+    Smpte431 = 11,
+    /// Display P3
+    Smpte432 = 12,
+    Ebu3213 = 22,
+}
+
+impl From<ColorPrimaries> for aom_color_primaries {
+    fn from(primaries: ColorPrimaries) -> Self {
+        primaries as aom_color_primaries
+    }
+}
+
+/// Reserved values map to `Unspecified`
+impl From<pixel::ColorPrimaries> for ColorPrimaries {
+    fn from(primaries: pixel::ColorPrimaries) -> Self {
+        match primaries {
+            pixel::ColorPrimaries::BT709 => ColorPrimaries::Bt709,
+            pixel::ColorPrimaries::BT470M => ColorPrimaries::Bt470M,
+            pixel::ColorPrimaries::BT470BG => ColorPrimaries::Bt470BG,
+            pixel::ColorPrimaries::ST170M => ColorPrimaries::Bt601,
+            pixel::ColorPrimaries::ST240M => ColorPrimaries::Smpte240,
+            pixel::ColorPrimaries::Film => ColorPrimaries::GenericFilm,
+            pixel::ColorPrimaries::BT2020 => ColorPrimaries::Bt2020,
+            pixel::ColorPrimaries::ST428 => ColorPrimaries::Xyz,
+            pixel::ColorPrimaries::P3DCI => ColorPrimaries::Smpte431,
+            pixel::ColorPrimaries::P3Display => ColorPrimaries::Smpte432,
+            pixel::ColorPrimaries::Tech3213 => ColorPrimaries::Ebu3213,
+            _ => ColorPrimaries::Unspecified,
+        }
+    }
+}
+
+/// Transfer characteristics signaled in the sequence header
+/// (AV1E_SET_TRANSFER_CHARACTERISTICS), with their ITU-T H.273 code points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferCharacteristics {
+    Bt709 = 1,
+    Unspecified = 2,
+    Bt470M = 4,
+    Bt470BG = 5,
+    Bt601 = 6,
+    Smpte240 = 7,
+    Linear = 8,
+    Log100 = 9,
+    Log100Sqrt10 = 10,
+    Iec61966 = 11,
+    Bt1361 = 12,
+    Srgb = 13,
+    Bt2020TenBit = 14,
+    Bt2020TwelveBit = 15,
+    /// PQ, used by HDR10
+    Smpte2084 = 16,
+    Smpte428 = 17,
+    /// Hybrid log-gamma
+    Hlg = 18,
+}
+
+impl From<TransferCharacteristics> for aom_transfer_characteristics {
+    fn from(transfer: TransferCharacteristics) -> Self {
+        transfer as aom_transfer_characteristics
+    }
+}
+
+/// Reserved values map to `Unspecified`
+impl From<pixel::TransferCharacteristic> for TransferCharacteristics {
+    fn from(transfer: pixel::TransferCharacteristic) -> Self {
+        match transfer {
+            pixel::TransferCharacteristic::BT1886 => TransferCharacteristics::Bt709,
+            pixel::TransferCharacteristic::BT470M => TransferCharacteristics::Bt470M,
+            pixel::TransferCharacteristic::BT470BG => TransferCharacteristics::Bt470BG,
+            pixel::TransferCharacteristic::ST170M => TransferCharacteristics::Bt601,
+            pixel::TransferCharacteristic::ST240M => TransferCharacteristics::Smpte240,
+            pixel::TransferCharacteristic::Linear => TransferCharacteristics::Linear,
+            pixel::TransferCharacteristic::Logarithmic100 => TransferCharacteristics::Log100,
+            pixel::TransferCharacteristic::Logarithmic316 => TransferCharacteristics::Log100Sqrt10,
+            pixel::TransferCharacteristic::XVYCC => TransferCharacteristics::Iec61966,
+            pixel::TransferCharacteristic::BT1361E => TransferCharacteristics::Bt1361,
+            pixel::TransferCharacteristic::SRGB => TransferCharacteristics::Srgb,
+            pixel::TransferCharacteristic::BT2020Ten => TransferCharacteristics::Bt2020TenBit,
+            pixel::TransferCharacteristic::BT2020Twelve => TransferCharacteristics::Bt2020TwelveBit,
+            pixel::TransferCharacteristic::PerceptualQuantizer => {
+                TransferCharacteristics::Smpte2084
+            }
+            pixel::TransferCharacteristic::ST428 => TransferCharacteristics::Smpte428,
+            pixel::TransferCharacteristic::HybridLogGamma => TransferCharacteristics::Hlg,
+            _ => TransferCharacteristics::Unspecified,
+        }
+    }
+}
+
+/// Matrix coefficients signaled in the sequence header
+/// (AV1E_SET_MATRIX_COEFFICIENTS), with their ITU-T H.273 code points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixCoefficients {
+    /// RGB (GBR) input, only valid with 4:4:4
+    Identity = 0,
+    Bt709 = 1,
+    Unspecified = 2,
+    Fcc = 4,
+    Bt470BG = 5,
+    Bt601 = 6,
+    Smpte240 = 7,
+    YCgCo = 8,
+    /// Used by HDR10 and HLG
+    Bt2020Ncl = 9,
+    Bt2020Cl = 10,
+    Smpte2085 = 11,
+    ChromaticityNcl = 12,
+    ChromaticityCl = 13,
+    ICtCp = 14,
+}
+
+impl From<MatrixCoefficients> for aom_matrix_coefficients {
+    fn from(matrix: MatrixCoefficients) -> Self {
+        matrix as aom_matrix_coefficients
+    }
+}
+
+impl From<ColorMatrix> for MatrixCoefficients {
+    fn from(matrix: ColorMatrix) -> Self {
+        match matrix {
+            ColorMatrix::Bt601 => MatrixCoefficients::Bt601,
+            ColorMatrix::Bt709 => MatrixCoefficients::Bt709,
+        }
+    }
+}
+
+/// Reserved values map to `Unspecified`
+impl From<pixel::MatrixCoefficients> for MatrixCoefficients {
+    fn from(matrix: pixel::MatrixCoefficients) -> Self {
+        match matrix {
+            pixel::MatrixCoefficients::Identity => MatrixCoefficients::Identity,
+            pixel::MatrixCoefficients::BT709 => MatrixCoefficients::Bt709,
+            pixel::MatrixCoefficients::BT470M => MatrixCoefficients::Fcc,
+            pixel::MatrixCoefficients::BT470BG => MatrixCoefficients::Bt470BG,
+            pixel::MatrixCoefficients::ST170M => MatrixCoefficients::Bt601,
+            pixel::MatrixCoefficients::ST240M => MatrixCoefficients::Smpte240,
+            pixel::MatrixCoefficients::YCgCo => MatrixCoefficients::YCgCo,
+            pixel::MatrixCoefficients::BT2020NonConstantLuminance => MatrixCoefficients::Bt2020Ncl,
+            pixel::MatrixCoefficients::BT2020ConstantLuminance => MatrixCoefficients::Bt2020Cl,
+            pixel::MatrixCoefficients::ST2085 => MatrixCoefficients::Smpte2085,
+            pixel::MatrixCoefficients::ChromaticityDerivedNonConstantLuminance => {
+                MatrixCoefficients::ChromaticityNcl
+            }
+            pixel::MatrixCoefficients::ChromaticityDerivedConstantLuminance => {
+                MatrixCoefficients::ChromaticityCl
+            }
+            pixel::MatrixCoefficients::ICtCp => MatrixCoefficients::ICtCp,
+            _ => MatrixCoefficients::Unspecified,
+        }
+    }
+}
+
+/// Metric the encoder optimizes its decisions for (AOME_SET_TUNING). The VMAF variants
+/// need a libaom built with CONFIG_TUNE_VMAF and a model (AV1E_SET_VMAF_MODEL_PATH),
+/// `Butteraugli` one built with CONFIG_TUNE_BUTTERAUGLI; other builds reject them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TuneMetric {
+    /// libaom's default
+    #[default]
+    Psnr,
+    /// Better perceived quality than PSNR tuning for most content
+    Ssim,
+    VmafWithPreprocessing,
+    VmafWithoutPreprocessing,
+    VmafMaxGain,
+    VmafNegMaxGain,
+    Butteraugli,
+}
+
+impl From<TuneMetric> for aom_tune_metric {
+    fn from(metric: TuneMetric) -> Self {
+        match metric {
+            TuneMetric::Psnr => aom_tune_metric_AOM_TUNE_PSNR,
+            TuneMetric::Ssim => aom_tune_metric_AOM_TUNE_SSIM,
+            TuneMetric::VmafWithPreprocessing => aom_tune_metric_AOM_TUNE_VMAF_WITH_PREPROCESSING,
+            TuneMetric::VmafWithoutPreprocessing => {
+                aom_tune_metric_AOM_TUNE_VMAF_WITHOUT_PREPROCESSING
+            }
+            TuneMetric::VmafMaxGain => aom_tune_metric_AOM_TUNE_VMAF_MAX_GAIN,
+            TuneMetric::VmafNegMaxGain => aom_tune_metric_AOM_TUNE_VMAF_NEG_MAX_GAIN,
+            TuneMetric::Butteraugli => aom_tune_metric_AOM_TUNE_BUTTERAUGLI,
+        }
+    }
+}
+
+/// Kind of content the encoder tunes its tools for (AV1E_SET_TUNE_CONTENT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TuneContent {
+    #[default]
+    Default,
+    /// Screen captures (desktops, slides): enables palette and intra block copy, which
+    /// code text and flat areas far more compactly
+    Screen,
+    /// Grainy film content
+    Film,
+}
+
+impl From<TuneContent> for aom_tune_content {
+    fn from(content: TuneContent) -> Self {
+        match content {
+            TuneContent::Default => aom_tune_content_AOM_CONTENT_DEFAULT,
+            TuneContent::Screen => aom_tune_content_AOM_CONTENT_SCREEN,
+            TuneContent::Film => aom_tune_content_AOM_CONTENT_FILM,
+        }
+    }
+}
+
+/// Superblock size (AV1E_SET_SUPERBLOCK_SIZE)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SuperblockSize {
+    /// Picked by libaom from the resolution and speed, the default
+    #[default]
+    Dynamic,
+    Sb64,
+    /// 128x128, usually more efficient on high resolution content
+    Sb128,
+}
+
+impl From<SuperblockSize> for aom_superblock_size_t {
+    fn from(size: SuperblockSize) -> Self {
+        match size {
+            SuperblockSize::Dynamic => aom_superblock_size_AOM_SUPERBLOCK_SIZE_DYNAMIC,
+            SuperblockSize::Sb64 => aom_superblock_size_AOM_SUPERBLOCK_SIZE_64X64,
+            SuperblockSize::Sb128 => aom_superblock_size_AOM_SUPERBLOCK_SIZE_128X128,
+        }
+    }
+}
+
+/// Adaptive quantization mode (AV1E_SET_AQ_MODE): how the quantizer varies between
+/// segments of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AqMode {
+    /// Same quantizer for the whole frame, libaom's default outside realtime
+    #[default]
+    None = 0,
+    /// Finer quantization of flat (low variance) blocks, where artifacts show most
+    Variance = 1,
+    /// Spends bits by block complexity
+    Complexity = 2,
+    /// Refreshes a cycling set of blocks at better quality, for realtime streams without
+    /// frequent keyframes
+    CyclicRefresh = 3,
+}
+
+/// Per 16x16 block on/off map for AOME_SET_ACTIVEMAP. Inactive blocks are skipped by
+/// the encoder, which saves a lot of CPU on mostly static content (e.g. screen share).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveMap {
+    rows: u32,
+    cols: u32,
+    map: Vec<u8>,
+}
+
+impl ActiveMap {
+    /// Map covering a `width`x`height` frame, with every block active
+    pub fn new(width: u32, height: u32) -> Self {
+        let rows: u32 = (height + 15) / 16;
+        let cols: u32 = (width + 15) / 16;
+        ActiveMap {
+            rows,
+            cols,
+            map: vec![1; (rows * cols) as usize],
+        }
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn cols(&self) -> u32 {
+        self.cols
+    }
+
+    pub fn is_active(&self, row: u32, col: u32) -> bool {
+        self.map[(row * self.cols + col) as usize] != 0
+    }
+
+    pub fn set_active(&mut self, row: u32, col: u32, active: bool) {
+        self.map[(row * self.cols + col) as usize] = active as u8;
+    }
+
+    /// One byte per block in raster order, 1 = active, 0 = skipped
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.map
+    }
+
+    /// aom_active_map pointing into this map. It must not outlive `self`.
+    pub(crate) fn to_aom(&self) -> aom_active_map {
+        aom_active_map {
+            // libaom only reads the map on set
+            active_map: self.map.as_ptr() as *mut u8,
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    pub(crate) fn to_aom_mut(&mut self) -> aom_active_map {
+        aom_active_map {
+            active_map: self.map.as_mut_ptr(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+}
+
+/// seq_tier of an operating point (AV1E_SET_TIER_MASK). The high tier raises the
+/// bitrate limits of the level, it only exists for levels 4.0 and up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tier {
+    #[default]
+    Main,
+    High,
+}
+
+/// Layer layout and per-layer rate control for AV1E_SET_SVC_PARAMS.
+///
+/// Per layer arrays hold `number_spatial_layers * number_temporal_layers` entries,
+/// the entry of spatial layer `sl` and temporal layer `tl` is at
+/// `sl * number_temporal_layers + tl`. Bitrates are cumulative, in kbps: the entry of a
+/// layer includes the bitrate of all the layers it depends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvcParams {
+    pub number_spatial_layers: u32,
+    pub number_temporal_layers: u32,
+    /// Target bitrate of every layer in kbps
+    pub layer_target_bitrate: Vec<i32>,
+    /// Frame rate divider of every temporal layer, e.g. [4, 2, 1] for three layers
+    /// where the base layer runs at a quarter of the full frame rate
+    pub framerate_factor: Vec<i32>,
+    /// Max quantizer of every layer, empty for 63 everywhere
+    pub max_quantizers: Vec<i32>,
+    /// Min quantizer of every layer, empty for 0 everywhere
+    pub min_quantizers: Vec<i32>,
+    /// Downscaling of every spatial layer as (num, den), empty for full resolution
+    pub scaling_factors: Vec<(i32, i32)>,
+}
+
+impl SvcParams {
+    /// Params for the given layout with every per-layer array left to fill
+    pub fn new(number_spatial_layers: u32, number_temporal_layers: u32) -> Self {
+        SvcParams {
+            number_spatial_layers,
+            number_temporal_layers,
+            layer_target_bitrate: Vec::new(),
+            framerate_factor: Vec::new(),
+            max_quantizers: Vec::new(),
+            min_quantizers: Vec::new(),
+            scaling_factors: Vec::new(),
+        }
+    }
+
+    /// Number of layers, the expected length of the per-layer arrays
+    pub fn layer_count(&self) -> usize {
+        (self.number_spatial_layers * self.number_temporal_layers) as usize
+    }
+
+    /// Checks the layer counts against libaom's limits and the array lengths against
+    /// the layer counts. Optional arrays may be empty.
+    pub fn validate(&self) -> Result<(), AomError> {
+        let layers: usize = self.layer_count();
+        let spatial: usize = self.number_spatial_layers as usize;
+        let temporal: usize = self.number_temporal_layers as usize;
+
+        if !(1..=AOM_MAX_SS_LAYERS as usize).contains(&spatial)
+            || !(1..=AOM_MAX_TS_LAYERS as usize).contains(&temporal)
+            || layers > AOM_MAX_LAYERS as usize
+        {
+            return Err(AomError::InvalidParam);
+        }
+        if self.layer_target_bitrate.len() != layers || self.framerate_factor.len() != temporal {
+            return Err(AomError::InvalidParam);
+        }
+        if self.framerate_factor.iter().any(|factor| *factor <= 0) {
+            return Err(AomError::InvalidParam);
+        }
+
+        let optional_len = |len: usize, expected: usize| len == 0 || len == expected;
+        if !optional_len(self.max_quantizers.len(), layers)
+            || !optional_len(self.min_quantizers.len(), layers)
+            || !optional_len(self.scaling_factors.len(), spatial)
+        {
+            return Err(AomError::InvalidParam);
+        }
+        if self
+            .scaling_factors
+            .iter()
+            .any(|(num, den)| *num <= 0 || *den <= 0)
+        {
+            return Err(AomError::InvalidParam);
+        }
+
+        Ok(())
+    }
+
+    /// Validates the params and fills the aom_svc_params struct
+    pub(crate) fn to_aom(&self) -> Result<aom_svc_params, AomError> {
+        self.validate()?;
+
+        let mut params: aom_svc_params = unsafe { mem::zeroed() };
+        params.number_spatial_layers = self.number_spatial_layers as i32;
+        params.number_temporal_layers = self.number_temporal_layers as i32;
+
+        for layer in 0..self.layer_count() {
+            params.layer_target_bitrate[layer] = self.layer_target_bitrate[layer];
+            params.max_quantizers[layer] = self.max_quantizers.get(layer).copied().unwrap_or(63);
+            params.min_quantizers[layer] = self.min_quantizers.get(layer).copied().unwrap_or(0);
+        }
+        for (tl, factor) in self.framerate_factor.iter().enumerate() {
+            params.framerate_factor[tl] = *factor;
+        }
+        for sl in 0..self.number_spatial_layers as usize {
+            let (num, den) = self.scaling_factors.get(sl).copied().unwrap_or((1, 1));
+            params.scaling_factor_num[sl] = num;
+            params.scaling_factor_den[sl] = den;
+        }
+
+        Ok(params)
+    }
+}
+
+/// Layer the next frames are encoded as (AV1E_SET_SVC_LAYER_ID), within the layout of
+/// the `SvcParams`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SvcLayerId {
+    pub spatial_layer_id: u32,
+    pub temporal_layer_id: u32,
+}
+
+impl SvcLayerId {
+    pub fn new(spatial_layer_id: u32, temporal_layer_id: u32) -> Self {
+        SvcLayerId {
+            spatial_layer_id,
+            temporal_layer_id,
+        }
+    }
+
+    /// Checks the ids against libaom's layer limits and fills the aom_svc_layer_id struct
+    pub(crate) fn to_aom(&self) -> Result<aom_svc_layer_id, AomError> {
+        if self.spatial_layer_id >= AOM_MAX_SS_LAYERS || self.temporal_layer_id >= AOM_MAX_TS_LAYERS
+        {
+            return Err(AomError::InvalidParam);
+        }
+
+        Ok(aom_svc_layer_id {
+            spatial_layer_id: self.spatial_layer_id as i32,
+            temporal_layer_id: self.temporal_layer_id as i32,
+        })
+    }
+}
+
+/// References a frame can predict from: LAST, LAST2, LAST3, GOLDEN, BWDREF, ALTREF2
+/// and ALTREF, in that order
+pub const REFS_PER_FRAME: usize = 7;
+/// Reference buffer slots of the decoder model
+pub const REF_FRAME_SLOTS: usize = 8;
+
+/// Which references a frame uses and which buffer slots it updates
+/// (AV1E_SET_SVC_REF_FRAME_CONFIG), for custom low latency GOP structures.
+/// References are indexed in the order of `REFS_PER_FRAME`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RefFrameConfig {
+    /// Whether the frame may predict from each reference
+    pub reference: [bool; REFS_PER_FRAME],
+    /// Buffer slot (0-7) each reference reads from
+    pub ref_idx: [u8; REFS_PER_FRAME],
+    /// Slots the frame is stored in once encoded
+    pub refresh: [bool; REF_FRAME_SLOTS],
+}
+
+impl RefFrameConfig {
+    /// Checks the slot indices and fills the aom_svc_ref_frame_config struct
+    pub(crate) fn to_aom(&self) -> Result<aom_svc_ref_frame_config, AomError> {
+        if self
+            .ref_idx
+            .iter()
+            .any(|idx| *idx as usize >= REF_FRAME_SLOTS)
+        {
+            return Err(AomError::InvalidParam);
+        }
+
+        Ok(aom_svc_ref_frame_config {
+            reference: self.reference.map(i32::from),
+            ref_idx: self.ref_idx.map(i32::from),
+            refresh: self.refresh.map(i32::from),
+        })
+    }
+}
+
+/// Compound prediction tools of the encoder. `None` keeps libaom's default, `Some`
+/// turns the tool on or off with its AV1E_SET_ENABLE_* control.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompoundToggles {
+    /// AV1E_SET_ENABLE_MASKED_COMP, wedge and difference weighted compound
+    pub masked: Option<bool>,
+    /// AV1E_SET_ENABLE_DIFF_WTD_COMP
+    pub diff_wtd: Option<bool>,
+    /// AV1E_SET_ENABLE_DIST_WTD_COMP, distance weighted compound
+    pub dist_wtd: Option<bool>,
+    /// AV1E_SET_ENABLE_ONESIDED_COMP, compound with both references on the same side
+    pub onesided: Option<bool>,
+    /// AV1E_SET_ENABLE_INTERINTRA_COMP
+    pub interintra: Option<bool>,
+    /// AV1E_SET_ENABLE_SMOOTH_INTERINTRA
+    pub smooth_interintra: Option<bool>,
+    /// AV1E_SET_ENABLE_INTERINTER_WEDGE
+    pub interinter_wedge: Option<bool>,
+    /// AV1E_SET_ENABLE_INTERINTRA_WEDGE
+    pub interintra_wedge: Option<bool>,
+}
+
+impl CompoundToggles {
+    /// (control id, value) of every toggle that is set
+    pub(crate) fn controls(&self) -> Vec<(aome_enc_control_id, bool)> {
+        [
+            (aome_enc_control_id_AV1E_SET_ENABLE_MASKED_COMP, self.masked),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_DIFF_WTD_COMP,
+                self.diff_wtd,
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_DIST_WTD_COMP,
+                self.dist_wtd,
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_ONESIDED_COMP,
+                self.onesided,
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_INTERINTRA_COMP,
+                self.interintra,
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_SMOOTH_INTERINTRA,
+                self.smooth_interintra,
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_INTERINTER_WEDGE,
+                self.interinter_wedge,
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_INTERINTRA_WEDGE,
+                self.interintra_wedge,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(id, value)| value.map(|value| (id, value)))
+        .collect()
+    }
+}
+
+/// AV1E_SET_LOOPFILTER_CONTROL values: which frames get deblocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopFilterControl {
+    /// No deblocking at all
+    Disabled = 0,
+    /// Deblock every frame (libaom's default)
+    AllFrames = 1,
+    /// Skip deblocking on frames no other frame references
+    ReferenceFrames = 2,
+    /// Skip deblocking on frames with little motion (realtime usage)
+    SkipLowMotion = 3,
+}
+
+/// In-loop filtering of the encoder, applied by `AV1Encoder::configure_loopfilter`.
+/// `None` keeps libaom's default. Turning filters off speeds up encoding, mostly in
+/// realtime mode, at some quality cost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoopFilterSettings {
+    /// AV1E_SET_LOOPFILTER_CONTROL, deblocking filter
+    pub deblocking: Option<LoopFilterControl>,
+    /// AV1E_SET_ENABLE_CDEF, constrained directional enhancement filter
+    pub cdef: Option<bool>,
+    /// AV1E_SET_ENABLE_RESTORATION, loop restoration (Wiener / self-guided) filter.
+    /// Not available in CONFIG_REALTIME_ONLY builds of libaom.
+    pub restoration: Option<bool>,
+    /// AV1E_SET_DELTALF_MODE, per superblock loop filter level deltas. Only has an
+    /// effect with delta q modulation (AV1E_SET_DELTAQ_MODE) enabled.
+    pub delta_lf: Option<bool>,
+}
+
+impl LoopFilterSettings {
+    /// (control id, value) of every setting that is set
+    pub(crate) fn controls(&self) -> Vec<(aome_enc_control_id, i32)> {
+        [
+            (
+                aome_enc_control_id_AV1E_SET_LOOPFILTER_CONTROL,
+                self.deblocking.map(|control| control as i32),
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_CDEF,
+                self.cdef.map(i32::from),
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_ENABLE_RESTORATION,
+                self.restoration.map(i32::from),
+            ),
+            (
+                aome_enc_control_id_AV1E_SET_DELTALF_MODE,
+                self.delta_lf.map(i32::from),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(id, value)| value.map(|value| (id, value)))
+        .collect()
+    }
+}
+
+/// Control ids shared by encoder and decoder whose argument is a pointer
+const COMMON_POINTER_CONTROLS: [u32; 5] = [
+    aom_com_control_id_AV1_GET_REFERENCE,
+    aom_com_control_id_AV1_SET_REFERENCE,
+    aom_com_control_id_AV1_COPY_REFERENCE,
+    aom_com_control_id_AV1_GET_NEW_FRAME_IMAGE,
+    aom_com_control_id_AV1_COPY_NEW_FRAME_IMAGE,
+];
+
+/// Encoder control ids whose argument is a pointer (struct, string or out parameter)
+const ENC_POINTER_CONTROLS: [aome_enc_control_id; 22] = [
+    aome_enc_control_id_AOME_SET_ROI_MAP,
+    aome_enc_control_id_AOME_SET_ACTIVEMAP,
+    aome_enc_control_id_AOME_SET_SCALEMODE,
+    aome_enc_control_id_AOME_GET_LAST_QUANTIZER,
+    aome_enc_control_id_AOME_GET_LAST_QUANTIZER_64,
+    aome_enc_control_id_AV1E_GET_ACTIVEMAP,
+    aome_enc_control_id_AV1E_SET_RENDER_SIZE,
+    aome_enc_control_id_AV1E_GET_SEQ_LEVEL_IDX,
+    aome_enc_control_id_AV1E_SET_FILM_GRAIN_TABLE,
+    aome_enc_control_id_AV1E_SET_SVC_LAYER_ID,
+    aome_enc_control_id_AV1E_SET_SVC_PARAMS,
+    aome_enc_control_id_AV1E_SET_SVC_REF_FRAME_CONFIG,
+    aome_enc_control_id_AV1E_SET_VMAF_MODEL_PATH,
+    aome_enc_control_id_AV1E_GET_BASELINE_GF_INTERVAL,
+    aome_enc_control_id_AV1E_SET_PARTITION_INFO_PATH,
+    aome_enc_control_id_AV1E_SET_EXTERNAL_PARTITION,
+    aome_enc_control_id_AV1E_SET_SVC_REF_FRAME_COMP_PRED,
+    aome_enc_control_id_AOME_GET_LOOPFILTER_LEVEL,
+    aome_enc_control_id_AV1E_GET_TARGET_SEQ_LEVEL_IDX,
+    aome_enc_control_id_AV1E_GET_NUM_OPERATING_POINTS,
+    aome_enc_control_id_AV1E_SET_RATE_DISTRIBUTION_INFO,
+    aome_enc_control_id_AV1E_GET_LUMA_CDEF_STRENGTH,
+];
+
+/// Decoder control ids whose argument is a pointer (mostly getters)
+const DEC_POINTER_CONTROLS: [aom_dec_control_id; 28] = [
+    aom_dec_control_id_AOMD_GET_LAST_REF_UPDATES,
+    aom_dec_control_id_AOMD_GET_FRAME_CORRUPTED,
+    aom_dec_control_id_AOMD_GET_LAST_REF_USED,
+    aom_dec_control_id_AV1D_GET_FRAME_SIZE,
+    aom_dec_control_id_AV1D_GET_DISPLAY_SIZE,
+    aom_dec_control_id_AV1D_GET_BIT_DEPTH,
+    aom_dec_control_id_AV1D_GET_IMG_FORMAT,
+    aom_dec_control_id_AV1D_GET_TILE_SIZE,
+    aom_dec_control_id_AV1D_GET_TILE_COUNT,
+    aom_dec_control_id_AV1_GET_ACCOUNTING,
+    aom_dec_control_id_AOMD_GET_LAST_QUANTIZER,
+    aom_dec_control_id_AV1D_GET_FRAME_HEADER_INFO,
+    aom_dec_control_id_AV1D_GET_TILE_DATA,
+    aom_dec_control_id_AV1D_SET_EXT_REF_PTR,
+    aom_dec_control_id_AV1_SET_INSPECTION_CALLBACK,
+    aom_dec_control_id_AOMD_GET_FWD_KF_PRESENT,
+    aom_dec_control_id_AOMD_GET_FRAME_FLAGS,
+    aom_dec_control_id_AOMD_GET_ALTREF_PRESENT,
+    aom_dec_control_id_AOMD_GET_TILE_INFO,
+    aom_dec_control_id_AOMD_GET_SCREEN_CONTENT_TOOLS_INFO,
+    aom_dec_control_id_AOMD_GET_STILL_PICTURE,
+    aom_dec_control_id_AOMD_GET_SB_SIZE,
+    aom_dec_control_id_AOMD_GET_SHOW_EXISTING_FRAME_FLAG,
+    aom_dec_control_id_AOMD_GET_S_FRAME_INFO,
+    aom_dec_control_id_AOMD_GET_SHOW_FRAME_FLAG,
+    aom_dec_control_id_AOMD_GET_BASE_Q_IDX,
+    aom_dec_control_id_AOMD_GET_ORDER_HINT,
+    aom_dec_control_id_AV1D_GET_MI_INFO,
+];
+
+/// Whether the encoder control `id` takes a pointer. Passing an int to such a control
+/// makes libaom dereference it, so the int control method rejects these ids.
+pub(crate) fn enc_control_takes_pointer(id: aome_enc_control_id) -> bool {
+    ENC_POINTER_CONTROLS.contains(&id) || COMMON_POINTER_CONTROLS.contains(&id)
+}
+
+/// Same as `enc_control_takes_pointer` for decoder controls
+pub(crate) fn dec_control_takes_pointer(id: aom_dec_control_id) -> bool {
+    DEC_POINTER_CONTROLS.contains(&id) || COMMON_POINTER_CONTROLS.contains(&id)
+}
@@ -0,0 +1,432 @@
+use super::{
+    controls::dec_control_takes_pointer,
+    errors::AomError,
+    obu::{annexb_temporal_units, temporal_units},
+};
+use crate::{
+    ffi::dec::{
+        aom_codec_av1_dx, aom_codec_control, aom_codec_ctx, aom_codec_ctx_t, aom_codec_dec_cfg,
+        aom_codec_dec_init_ver, aom_codec_decode, aom_codec_destroy, aom_codec_err_t_AOM_CODEC_OK,
+        aom_codec_get_frame, aom_codec_iter_t, aom_codec_peek_stream_info, aom_codec_stream_info,
+        aom_dec_control_id, aom_dec_control_id_AOMD_GET_FRAME_CORRUPTED,
+        aom_dec_control_id_AOMD_GET_LAST_QUANTIZER, aom_dec_control_id_AV1D_EXT_TILE_DEBUG,
+        aom_dec_control_id_AV1D_GET_BIT_DEPTH, aom_dec_control_id_AV1D_GET_DISPLAY_SIZE,
+        aom_dec_control_id_AV1D_GET_FRAME_HEADER_INFO, aom_dec_control_id_AV1D_GET_FRAME_SIZE,
+        aom_dec_control_id_AV1D_GET_IMG_FORMAT, aom_dec_control_id_AV1D_GET_MI_INFO,
+        aom_dec_control_id_AV1D_SET_IS_ANNEXB, aom_dec_control_id_AV1D_SET_OPERATING_POINT,
+        aom_dec_control_id_AV1D_SET_OUTPUT_ALL_LAYERS, aom_dec_control_id_AV1D_SET_ROW_MT,
+        aom_dec_control_id_AV1D_SET_SKIP_FILM_GRAIN, aom_dec_control_id_AV1_SET_SKIP_LOOP_FILTER,
+        aom_image, aom_img_fmt, aom_tile_data, AOM_DECODER_ABI_VERSION,
+    },
+    utils::img_to_frame,
+};
+use av_data::frame::Frame;
+use std::{
+    collections::VecDeque,
+    mem::{self, MaybeUninit},
+    ops::Deref,
+    os::raw::c_void,
+    ptr,
+};
+
+/// Decoder configuration, the aom_codec_dec_cfg passed to aom_codec_dec_init_ver.
+///
+/// `Default` lets libaom pick the thread count and takes the frame size from the
+/// stream. The setters can be chained:
+///
+/// ```rust,no_run
+/// # use aom_rs::core::decoder::{AV1Decoder, AV1DecoderConfig};
+/// # fn main() -> Result<(), aom_rs::core::AomError> {
+/// let mut config = AV1DecoderConfig::default();
+/// config.threads(4);
+/// let decoder = AV1Decoder::new(&config)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct AV1DecoderConfig {
+    pub(crate) dec_cfg: aom_codec_dec_cfg,
+}
+
+impl Default for AV1DecoderConfig {
+    fn default() -> Self {
+        AV1DecoderConfig {
+            dec_cfg: aom_codec_dec_cfg {
+                threads: 0,
+                w: 0,
+                h: 0,
+                allow_lowbitdepth: 1,
+            },
+        }
+    }
+}
+
+impl AV1DecoderConfig {
+    /// Maximum number of threads libaom may use for decoding, 0 for its default
+    pub fn threads(&mut self, value: u32) -> &mut Self {
+        self.dec_cfg.threads = value;
+        self
+    }
+
+    /// Frame width, if known ahead of the sequence header
+    pub fn w(&mut self, value: u32) -> &mut Self {
+        self.dec_cfg.w = value;
+        self
+    }
+
+    /// Frame height, if known ahead of the sequence header
+    pub fn h(&mut self, value: u32) -> &mut Self {
+        self.dec_cfg.h = value;
+        self
+    }
+}
+
+impl Deref for AV1DecoderConfig {
+    type Target = aom_codec_dec_cfg;
+
+    fn deref(&self) -> &Self::Target {
+        &self.dec_cfg
+    }
+}
+
+/// An AV1 decoder instance (aom_codec_av1_dx), the counterpart of `AV1Encoder`.
+///
+/// Created with `new`, which initializes the libaom context; the context is destroyed
+/// with aom_codec_destroy when the decoder is dropped.
+///
+/// Controls are marked (set) or (get) in their docs. Setters are applied before or
+/// between `decode` calls, getters report on the last decoded frame.
+pub struct AV1Decoder {
+    pub(crate) ctx: aom_codec_ctx_t,
+    pub(crate) iter: aom_codec_iter_t,
+    // pts of the last decode calls by the id passed as their user_priv, a pointer
+    // sized value can't carry an i64 on 32-bit targets
+    pts: VecDeque<(usize, i64)>,
+    next_id: usize,
+}
+
+// decode calls whose pts is kept, frames libaom holds back come out a few calls later
+// at most
+const PTS_HISTORY: usize = 16;
+
+impl AV1Decoder {
+    /// This calls the aom_codec_dec_init_ver function under the hood. libaom copies
+    /// the configuration, `cfg` can be dropped or changed afterwards.
+    pub fn new(cfg: &AV1DecoderConfig) -> Result<AV1Decoder, AomError> {
+        let mut ctx: MaybeUninit<aom_codec_ctx> = MaybeUninit::uninit();
+        let result: u32 = unsafe {
+            aom_codec_dec_init_ver(
+                ctx.as_mut_ptr(),
+                aom_codec_av1_dx(),
+                &cfg.dec_cfg,
+                0,
+                AOM_DECODER_ABI_VERSION as i32,
+            )
+        };
+
+        match result {
+            aom_codec_err_t_AOM_CODEC_OK => Ok(AV1Decoder {
+                ctx: unsafe { ctx.assume_init() },
+                iter: ptr::null(),
+                pts: VecDeque::with_capacity(PTS_HISTORY),
+                next_id: 0,
+            }),
+            _ => Err(result.into()),
+        }
+    }
+
+    /// Calls aom_codec_decode with one temporal unit of compressed data (e.g. the packet
+    /// data of an `AOMPacket::Frame`). The decoded images carry an id of the call as
+    /// their user_priv, by which `get_frame` gives the frames the timestamp of their
+    /// packet.
+    ///
+    /// An empty `data` flushes the decoder: libaom gets a null pointer and returns the
+    /// frames it still holds. Frames of earlier calls that were not fetched are dropped.
+    pub fn decode(&mut self, data: &[u8], pts: i64) -> Result<(), AomError> {
+        let buf: *const u8 = if data.is_empty() {
+            ptr::null()
+        } else {
+            data.as_ptr()
+        };
+
+        let id: usize = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        if self.pts.len() == PTS_HISTORY {
+            self.pts.pop_front();
+        }
+        self.pts.push_back((id, pts));
+
+        let result: u32 =
+            unsafe { aom_codec_decode(&mut self.ctx, buf, data.len(), id as *mut c_void) };
+        // Reset the iterator, the next get_frame starts at the frames of this call
+        self.iter = ptr::null();
+
+        match result {
+            aom_codec_err_t_AOM_CODEC_OK => Ok(()),
+            _ => Err(result.into()),
+        }
+    }
+
+    /// Calls aom_codec_get_frame and copies the next decoded image of the last `decode`
+    /// call into a new Frame, with the pts given to `decode`. Returns None once all
+    /// frames were returned. The pts is None for an image of a `decode` call more than
+    /// 16 calls back.
+    ///
+    /// An image in a format `img_to_frame` can't convert comes back as an error; the
+    /// iteration goes on, the next call returns the image after it.
+    pub fn get_frame(&mut self) -> Option<Result<Frame, AomError>> {
+        let img: *mut aom_image = unsafe { aom_codec_get_frame(&mut self.ctx, &mut self.iter) };
+        if img.is_null() {
+            return None;
+        }
+        let img: &aom_image = unsafe { &*img };
+
+        let id: usize = img.user_priv as usize;
+        let pts: Option<i64> = self.pts.iter().find(|(i, _)| *i == id).map(|(_, pts)| *pts);
+        let frame: Result<Frame, AomError> = img_to_frame(img).map(|mut frame| {
+            frame.t.pts = pts;
+            frame
+        });
+        Some(frame)
+    }
+
+    /// Calls aom_codec_control with an int parameter on the decoder instance.
+    /// Ids of controls that expect a pointer are rejected with AOM_CODEC_INVALID_PARAM.
+    pub fn aom_codec_control(&mut self, id: aom_dec_control_id, val: i32) -> Result<(), AomError> {
+        if dec_control_takes_pointer(id) {
+            return Err(AomError::InvalidParam);
+        }
+
+        let result: u32 = unsafe { aom_codec_control(&mut self.ctx, id as i32, val) };
+
+        match result {
+            aom_codec_err_t_AOM_CODEC_OK => Ok(()),
+            _ => Err(result.into()),
+        }
+    }
+
+    /// AV1D_SET_OUTPUT_ALL_LAYERS (set). Output every decoded layer of a temporal unit
+    /// instead of only the highest spatial layer.
+    pub fn set_output_all_layers(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(aom_dec_control_id_AV1D_SET_OUTPUT_ALL_LAYERS, enable as i32)
+    }
+
+    /// AV1D_SET_OPERATING_POINT (set). Decodes the operating point `op` (0-31) of a scalable
+    /// stream, i.e. only the layers that belong to it. Set it before the first decode.
+    pub fn set_operating_point(&mut self, op: u32) -> Result<(), AomError> {
+        if op > 31 {
+            return Err(AomError::InvalidParam);
+        }
+        self.aom_codec_control(aom_dec_control_id_AV1D_SET_OPERATING_POINT, op as i32)
+    }
+
+    /// AV1D_EXT_TILE_DEBUG (set). Debug output for large scale tile (tile list) decoding.
+    pub fn set_ext_tile_debug(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(aom_dec_control_id_AV1D_EXT_TILE_DEBUG, enable as i32)
+    }
+
+    /// AV1_SET_SKIP_LOOP_FILTER (set). Skips the loop filter, a quick way to make fast
+    /// (lower quality) previews.
+    pub fn set_skip_loop_filter(&mut self, skip: bool) -> Result<(), AomError> {
+        self.aom_codec_control(aom_dec_control_id_AV1_SET_SKIP_LOOP_FILTER, skip as i32)
+    }
+
+    /// AV1D_SET_SKIP_FILM_GRAIN (set). Outputs frames without the film grain the
+    /// stream signals, e.g. to compare against the encoder's input.
+    pub fn set_skip_film_grain(&mut self, skip: bool) -> Result<(), AomError> {
+        self.aom_codec_control(aom_dec_control_id_AV1D_SET_SKIP_FILM_GRAIN, skip as i32)
+    }
+
+    /// AV1D_SET_ROW_MT (set). Row based multi-threading, on by default. Only has an
+    /// effect with more than one thread (`AV1DecoderConfig::threads`).
+    pub fn set_row_mt(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(aom_dec_control_id_AV1D_SET_ROW_MT, enable as i32)
+    }
+
+    /// AV1D_GET_FRAME_SIZE (get). Size the last frame was decoded at, which can differ
+    /// from the display size with superres or reference scaling.
+    pub fn frame_size(&mut self) -> Result<(u32, u32), AomError> {
+        let mut size: [i32; 2] = [0; 2];
+        self.control_ptr(aom_dec_control_id_AV1D_GET_FRAME_SIZE, &mut size)?;
+        Ok((size[0] as u32, size[1] as u32))
+    }
+
+    /// AV1D_GET_DISPLAY_SIZE (get). Intended display size of the last frame.
+    pub fn display_size(&mut self) -> Result<(u32, u32), AomError> {
+        let mut size: [i32; 2] = [0; 2];
+        self.control_ptr(aom_dec_control_id_AV1D_GET_DISPLAY_SIZE, &mut size)?;
+        Ok((size[0] as u32, size[1] as u32))
+    }
+
+    /// AV1D_GET_BIT_DEPTH (get). Bit depth of the stream.
+    pub fn bit_depth(&mut self) -> Result<u32, AomError> {
+        let mut depth: u32 = 0;
+        self.control_ptr(aom_dec_control_id_AV1D_GET_BIT_DEPTH, &mut depth)?;
+        Ok(depth)
+    }
+
+    /// AV1D_GET_IMG_FORMAT (get). aom_img_fmt the decoded frames come in.
+    pub fn img_format(&mut self) -> Result<aom_img_fmt, AomError> {
+        let mut fmt: aom_img_fmt = 0;
+        self.control_ptr(aom_dec_control_id_AV1D_GET_IMG_FORMAT, &mut fmt)?;
+        Ok(fmt)
+    }
+
+    /// AOMD_GET_LAST_QUANTIZER (get). Quantizer of the last decoded frame, on libaom's
+    /// internal (qindex) scale.
+    pub fn last_quantizer(&mut self) -> Result<i32, AomError> {
+        let mut q: i32 = 0;
+        self.control_ptr(aom_dec_control_id_AOMD_GET_LAST_QUANTIZER, &mut q)?;
+        Ok(q)
+    }
+
+    /// AOMD_GET_FRAME_CORRUPTED (get). Whether the last decoded frame is corrupt.
+    pub fn frame_corrupted(&mut self) -> Result<bool, AomError> {
+        let mut corrupted: i32 = 0;
+        self.control_ptr(aom_dec_control_id_AOMD_GET_FRAME_CORRUPTED, &mut corrupted)?;
+        Ok(corrupted != 0)
+    }
+
+    /// AV1D_GET_FRAME_HEADER_INFO (get). Location of the frame header of the last
+    /// decoded frame, used for large scale tile decoding. The pointer in the returned
+    /// aom_tile_data points into the data given to the last `decode` call.
+    pub fn frame_header_info(&mut self) -> Result<aom_tile_data, AomError> {
+        let mut info: aom_tile_data = unsafe { mem::zeroed() };
+        self.control_ptr(aom_dec_control_id_AV1D_GET_FRAME_HEADER_INFO, &mut info)?;
+        Ok(info)
+    }
+
+    /// Calls aom_codec_control with a pointer argument, for getters and struct setters
+    /// without a typed method. Ids of int controls are rejected with
+    /// AOM_CODEC_INVALID_PARAM, as is AV1D_GET_MI_INFO, which takes three arguments.
+    ///
+    /// # Safety
+    /// `T` must be the type the control expects (see the aomdx.h documentation of the
+    /// id), libaom reads or writes through the pointer as that type.
+    pub unsafe fn aom_codec_control_ptr<T>(
+        &mut self,
+        id: aom_dec_control_id,
+        val: &mut T,
+    ) -> Result<(), AomError> {
+        if !dec_control_takes_pointer(id) || id == aom_dec_control_id_AV1D_GET_MI_INFO {
+            return Err(AomError::InvalidParam);
+        }
+        self.control_ptr(id, val)
+    }
+
+    // aom_codec_control for controls that take a pointer (struct or out parameter)
+    fn control_ptr<T>(&mut self, id: aom_dec_control_id, val: &mut T) -> Result<(), AomError> {
+        let result: u32 = unsafe { aom_codec_control(&mut self.ctx, id as i32, val as *mut T) };
+
+        match result {
+            aom_codec_err_t_AOM_CODEC_OK => Ok(()),
+            _ => Err(result.into()),
+        }
+    }
+}
+
+// When our AV1 Decoder goes out of scope, we need to call the aom_codec_destroy internally.
+impl Drop for AV1Decoder {
+    fn drop(&mut self) {
+        unsafe { aom_codec_destroy(&mut self.ctx) };
+    }
+}
+
+unsafe impl Send for AV1Decoder {}
+
+/// Stream properties read from the sequence header, see `peek_stream_info`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamInfo {
+    pub w: u32,
+    pub h: u32,
+    /// The first frame of the data is a keyframe
+    pub is_kf: bool,
+}
+
+/// Calls aom_codec_peek_stream_info. Reads the frame size from the start of a
+/// low-overhead (Section 5) stream without creating a decoder, e.g. to size a display
+/// surface before decoding. The data has to begin with a temporal unit that carries a
+/// sequence header; otherwise libaom's error is returned (AOM_CODEC_UNSUP_BITSTREAM
+/// when no sequence header is found, AOM_CODEC_INVALID_PARAM for empty data).
+pub fn peek_stream_info(data: &[u8]) -> Result<StreamInfo, AomError> {
+    if data.is_empty() {
+        return Err(AomError::InvalidParam);
+    }
+
+    let mut si: aom_codec_stream_info = unsafe { mem::zeroed() };
+    let result: u32 = unsafe {
+        aom_codec_peek_stream_info(aom_codec_av1_dx(), data.as_ptr(), data.len(), &mut si)
+    };
+
+    match result {
+        aom_codec_err_t_AOM_CODEC_OK => Ok(StreamInfo {
+            w: si.w,
+            h: si.h,
+            is_kf: si.is_kf != 0,
+        }),
+        _ => Err(result.into()),
+    }
+}
+
+/// Checks that an AV1 bitstream decodes without errors or corrupt frames.
+///
+/// Both low-overhead (Section 5, as in .obu files and most containers) and Annex B
+/// framing are accepted; the framing is detected from the data. The stream is decoded
+/// temporal unit by temporal unit and decoded frames are dropped without being
+/// converted. Returns the first error libaom reports.
+pub fn validate_bitstream(data: &[u8]) -> Result<(), AomError> {
+    let (mut decoder, units) = open_stream(data)?;
+
+    for unit in units {
+        decode_and_discard(&mut decoder, unit.as_ptr(), unit.len())?;
+    }
+    // flush
+    decode_and_discard(&mut decoder, ptr::null(), 0)
+}
+
+/// Splits a Section 5 or Annex B stream into temporal units and returns a decoder set
+/// up for its framing
+pub(crate) fn open_stream(data: &[u8]) -> Result<(AV1Decoder, Vec<&[u8]>), AomError> {
+    let (units, annexb): (Vec<&[u8]>, bool) = match temporal_units(data) {
+        Ok(units) => (units, false),
+        Err(_) => (annexb_temporal_units(data)?, true),
+    };
+    if units.is_empty() {
+        return Err(AomError::UnsupBitstream);
+    }
+
+    let mut decoder: AV1Decoder = AV1Decoder::new(&AV1DecoderConfig::default())?;
+    if annexb {
+        decoder.aom_codec_control(aom_dec_control_id_AV1D_SET_IS_ANNEXB, 1)?;
+    }
+
+    Ok((decoder, units))
+}
+
+fn decode_and_discard(
+    decoder: &mut AV1Decoder,
+    data: *const u8,
+    size: usize,
+) -> Result<(), AomError> {
+    let result: u32 = unsafe { aom_codec_decode(&mut decoder.ctx, data, size, ptr::null_mut()) };
+    if result != aom_codec_err_t_AOM_CODEC_OK {
+        return Err(result.into());
+    }
+
+    let mut corrupted: i32 = 0;
+    let result: u32 = unsafe {
+        aom_codec_control(
+            &mut decoder.ctx,
+            aom_dec_control_id_AOMD_GET_FRAME_CORRUPTED as i32,
+            &mut corrupted as *mut i32,
+        )
+    };
+    if result == aom_codec_err_t_AOM_CODEC_OK && corrupted != 0 {
+        return Err(AomError::CorruptFrame);
+    }
+
+    let mut iter: aom_codec_iter_t = ptr::null();
+    while !unsafe { aom_codec_get_frame(&mut decoder.ctx, &mut iter) }.is_null() {}
+
+    Ok(())
+}
@@ -1,19 +1,81 @@
-use super::config::AV1EncoderConfig;
+use super::{
+    aligned::AlignedVec,
+    config::{AV1EncoderConfig, FixedBuf},
+    controls::{
+        enc_control_takes_pointer, ActiveMap, AqMode, ColorMatrix, ColorPrimaries, ColorRange,
+        CompoundToggles, LoopFilterControl, LoopFilterSettings, MatrixCoefficients, RefFrameConfig,
+        SuperblockSize, SvcLayerId, SvcParams, Tier, TransferCharacteristics, TuneContent,
+        TuneMetric,
+    },
+    errors::AomError,
+    image::{ImageView, OwnedImage},
+    obu::{iter_obus, ObuExtension},
+    version::{require_version, SET_OPTION_VERSION},
+    AomCodecEncCfgTrait,
+};
 use crate::{
     aom::{
-        aom_codec_av1_cx, aom_codec_control, aom_codec_ctx, aom_codec_ctx_t, aom_codec_cx_pkt,
+        aom_active_map, aom_codec_av1_cx, aom_codec_build_config, aom_codec_caps_t,
+        aom_codec_control, aom_codec_ctx, aom_codec_ctx_t, aom_codec_cx_pkt,
         aom_codec_cx_pkt__bindgen_ty_1__bindgen_ty_1, aom_codec_cx_pkt__bindgen_ty_1_aom_psnr_pkt,
         aom_codec_cx_pkt_kind_AOM_CODEC_CUSTOM_PKT, aom_codec_cx_pkt_kind_AOM_CODEC_CX_FRAME_PKT,
         aom_codec_cx_pkt_kind_AOM_CODEC_FPMB_STATS_PKT, aom_codec_cx_pkt_kind_AOM_CODEC_PSNR_PKT,
-        aom_codec_cx_pkt_kind_AOM_CODEC_STATS_PKT, aom_codec_destroy, aom_codec_enc_init_ver,
-        aom_codec_encode, aom_codec_err_t, aom_codec_get_cx_data, aom_codec_iter_t, aom_image,
-        aome_enc_control_id, aome_enc_control_id_AOME_SET_CPUUSED, AOM_ENCODER_ABI_VERSION,
-        AOM_FRAME_IS_KEY,
+        aom_codec_cx_pkt_kind_AOM_CODEC_STATS_PKT, aom_codec_destroy, aom_codec_enc_cfg,
+        aom_codec_enc_config_set, aom_codec_enc_init_ver, aom_codec_encode, aom_codec_err_t,
+        aom_codec_err_t_AOM_CODEC_OK, aom_codec_error_detail, aom_codec_flags_t,
+        aom_codec_get_caps, aom_codec_get_cx_data, aom_codec_get_global_headers, aom_codec_iter_t,
+        aom_codec_set_option, aom_codec_version_str, aom_color_primaries, aom_color_range,
+        aom_com_control_id_AV1_GET_NEW_FRAME_IMAGE, aom_enc_frame_flags_t,
+        aom_enc_pass_AOM_RC_ONE_PASS, aom_fixed_buf_t, aom_image, aom_matrix_coefficients,
+        aom_rational, aom_superblock_size_t, aom_svc_layer_id, aom_svc_params,
+        aom_svc_ref_frame_config, aom_transfer_characteristics, aom_tune_content, aom_tune_metric,
+        aome_enc_control_id, aome_enc_control_id_AOME_GET_LAST_QUANTIZER_64,
+        aome_enc_control_id_AOME_GET_LOOPFILTER_LEVEL, aome_enc_control_id_AOME_SET_ACTIVEMAP,
+        aome_enc_control_id_AOME_SET_CPUUSED, aome_enc_control_id_AOME_SET_CQ_LEVEL,
+        aome_enc_control_id_AOME_SET_SHARPNESS, aome_enc_control_id_AOME_SET_STATIC_THRESHOLD,
+        aome_enc_control_id_AOME_SET_TUNING, aome_enc_control_id_AV1E_GET_ACTIVEMAP,
+        aome_enc_control_id_AV1E_SET_AQ_MODE, aome_enc_control_id_AV1E_SET_COLOR_PRIMARIES,
+        aome_enc_control_id_AV1E_SET_COLOR_RANGE, aome_enc_control_id_AV1E_SET_DELTAQ_MODE,
+        aome_enc_control_id_AV1E_SET_DENOISE_NOISE_LEVEL, aome_enc_control_id_AV1E_SET_ENABLE_CDEF,
+        aome_enc_control_id_AV1E_SET_ENABLE_CHROMA_DELTAQ,
+        aome_enc_control_id_AV1E_SET_ENABLE_GLOBAL_MOTION,
+        aome_enc_control_id_AV1E_SET_ENABLE_RESTORATION,
+        aome_enc_control_id_AV1E_SET_ENABLE_WARPED_MOTION,
+        aome_enc_control_id_AV1E_SET_FILM_GRAIN_TABLE,
+        aome_enc_control_id_AV1E_SET_LOOPFILTER_CONTROL, aome_enc_control_id_AV1E_SET_LOSSLESS,
+        aome_enc_control_id_AV1E_SET_MATRIX_COEFFICIENTS,
+        aome_enc_control_id_AV1E_SET_QUANTIZER_ONE_PASS, aome_enc_control_id_AV1E_SET_ROW_MT,
+        aome_enc_control_id_AV1E_SET_SUPERBLOCK_SIZE, aome_enc_control_id_AV1E_SET_SVC_LAYER_ID,
+        aome_enc_control_id_AV1E_SET_SVC_PARAMS, aome_enc_control_id_AV1E_SET_SVC_REF_FRAME_CONFIG,
+        aome_enc_control_id_AV1E_SET_TARGET_SEQ_LEVEL_IDX, aome_enc_control_id_AV1E_SET_TIER_MASK,
+        aome_enc_control_id_AV1E_SET_TILE_COLUMNS, aome_enc_control_id_AV1E_SET_TILE_ROWS,
+        aome_enc_control_id_AV1E_SET_TRANSFER_CHARACTERISTICS,
+        aome_enc_control_id_AV1E_SET_TUNE_CONTENT, AOM_CODEC_CAP_ENCODER,
+        AOM_CODEC_CAP_HIGHBITDEPTH, AOM_CODEC_CAP_PSNR, AOM_CODEC_USE_HIGHBITDEPTH,
+        AOM_CODEC_USE_PSNR, AOM_EFLAG_ERROR_RESILIENT, AOM_EFLAG_FORCE_KF, AOM_EFLAG_NO_REF_ARF,
+        AOM_EFLAG_NO_REF_ARF2, AOM_EFLAG_NO_REF_BWD, AOM_EFLAG_NO_REF_FRAME_MVS,
+        AOM_EFLAG_NO_REF_GF, AOM_EFLAG_NO_REF_LAST, AOM_EFLAG_NO_REF_LAST2, AOM_EFLAG_NO_REF_LAST3,
+        AOM_EFLAG_NO_UPD_ARF, AOM_EFLAG_NO_UPD_ENTROPY, AOM_EFLAG_NO_UPD_GF, AOM_EFLAG_NO_UPD_LAST,
+        AOM_EFLAG_SET_PRIMARY_REF_NONE, AOM_EFLAG_SET_S_FRAME, AOM_ENCODER_ABI_VERSION,
+        AOM_FRAME_IS_KEY, AOM_IMG_FMT_HIGHBITDEPTH, AOM_USAGE_REALTIME,
     },
-    utils::{img_from_frame, to_buffer},
+    utils::{
+        frame_bit_depth, img_to_frame, range_from_formaton, rgb_to_i420, to_buffer, I420Planes,
+    },
+};
+use av_data::{frame::Frame, packet::Packet, pixel::Formaton};
+use std::{
+    collections::BTreeMap,
+    ffi::{CStr, CString},
+    fs::File,
+    mem::MaybeUninit,
+    os::raw::c_char,
+    path::Path,
+    ptr, slice,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
-use av_data::{frame::Frame, packet::Packet};
-use std::{mem::MaybeUninit, ptr};
 
 /// aom_codec_cx_pkt__bindgen_ty_1_aom_psnr_pkt struct of C
 /// hbd -> high_bit_depth_data
@@ -30,11 +92,35 @@ pub struct PSNR {
 pub enum AOMPacket {
     Frame(Packet),
     TwoPassStats(Vec<u8>),
+    /// Only emitted by libaom builds with first pass MB stats support, see `MbStatsBuffer`
     FirstPassMBStats(Vec<u8>),
     PSNR(PSNR),
     Raw(Vec<u8>),
 }
 
+/// Metadata of a frame packet returned by `get_packet_into` or `get_packet_ref`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    pub pts: i64,
+    pub duration: u64,
+    pub is_key: bool,
+}
+
+/// Packet returned by `get_packet_into`. Frame data lands in the caller's `AlignedVec`,
+/// every other kind is returned as usual.
+pub enum AlignedPacket {
+    Frame(FrameInfo),
+    Other(AOMPacket),
+}
+
+/// Packet returned by `get_packet_ref`. Frame data is borrowed from libaom's output
+/// buffer, which is reused by the next `encode`/`get_packet` call on the encoder, hence
+/// the borrow of the encoder for `'a`. Every other kind is returned as usual.
+pub enum BorrowedPacket<'a> {
+    Frame { data: &'a [u8], info: FrameInfo },
+    Other(AOMPacket),
+}
+
 impl AOMPacket {
     fn new(pkt: aom_codec_cx_pkt) -> Self {
         match pkt.kind {
@@ -47,6 +133,7 @@ impl AOMPacket {
                     p.data.set_len(f.sz);
                 }
                 p.t.pts = Some(f.pts);
+                p.t.duration = Some(f.duration as u64);
                 p.is_key = (f.flags & AOM_FRAME_IS_KEY) != 0;
 
                 AOMPacket::Frame(p)
@@ -81,25 +168,593 @@ impl AOMPacket {
             _ => panic!("Invalid aom packet kind detected"),
         }
     }
+
+    /// Temporal/spatial ids from the OBU extension header of a frame packet.
+    /// None for other packets and for frames of non-scalable streams.
+    pub fn layer_ids(&self) -> Option<ObuExtension> {
+        match self {
+            AOMPacket::Frame(p) => iter_obus(&p.data).find_map(|obu| obu.extension),
+            _ => None,
+        }
+    }
+}
+
+// err with the message at detail attached, if there is one
+fn with_detail(err: aom_codec_err_t, detail: *const c_char) -> AomError {
+    if detail.is_null() {
+        return err.into();
+    }
+
+    AomError::WithDetail {
+        error: Box::new(err.into()),
+        detail: unsafe { CStr::from_ptr(detail) }
+            .to_string_lossy()
+            .into_owned(),
+    }
+}
+
+/// Init-time flags passed to aom_codec_enc_init_ver as an aom_codec_flags_t bitset
+///
+/// ```rust,no_run
+/// # use aom_rs::core::{config::AV1EncoderConfig, encoder::{AV1Encoder, InitFlags}};
+/// # fn example(mut cfg: AV1EncoderConfig) -> Result<(), aom_rs::core::AomError> {
+/// let flags = InitFlags::new().psnr(true).high_bit_depth(true);
+/// let encoder = AV1Encoder::new_with_flags(&mut cfg, flags)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InitFlags(aom_codec_flags_t);
+
+impl InitFlags {
+    pub fn new() -> Self {
+        InitFlags(0)
+    }
+
+    /// AOM_CODEC_USE_PSNR. The encoder emits an AOMPacket::PSNR after every frame
+    pub fn psnr(self, enable: bool) -> Self {
+        self.set(AOM_CODEC_USE_PSNR, enable)
+    }
+
+    /// AOM_CODEC_USE_HIGHBITDEPTH. Required to feed 10/12-bit images to the encoder
+    pub fn high_bit_depth(self, enable: bool) -> Self {
+        self.set(AOM_CODEC_USE_HIGHBITDEPTH, enable)
+    }
+
+    /// Raw bitset as passed to libaom
+    pub fn bits(&self) -> aom_codec_flags_t {
+        self.0
+    }
+
+    fn set(self, flag: u32, enable: bool) -> Self {
+        let flag = flag as aom_codec_flags_t;
+        if enable {
+            InitFlags(self.0 | flag)
+        } else {
+            InitFlags(self.0 & !flag)
+        }
+    }
+}
+
+impl From<InitFlags> for aom_codec_flags_t {
+    fn from(flags: InitFlags) -> Self {
+        flags.0
+    }
+}
+
+/// Per-frame reference flags passed to aom_codec_encode as an aom_enc_frame_flags_t
+/// bitset (see `AV1Encoder::encode_with`). They control which reference buffers a frame
+/// may predict from and which ones it refreshes, for application driven error recovery.
+///
+/// ```rust,no_run
+/// # use aom_rs::core::encoder::{AV1Encoder, FrameRefFlags};
+/// # use av_data::frame::Frame;
+/// # fn example(encoder: &mut AV1Encoder, frame: &Frame) -> Result<(), aom_rs::core::AomError> {
+/// // Loss on the wire: predict only from the golden frame, which the receiver
+/// // acknowledged, and keep it untouched so it stays a known good reference.
+/// let flags = FrameRefFlags::new()
+///     .no_ref_last(true)
+///     .no_ref_last2(true)
+///     .no_ref_last3(true)
+///     .no_ref_bwd(true)
+///     .no_ref_altref(true)
+///     .no_ref_altref2(true)
+///     .no_upd_golden(true);
+/// encoder.encode_with(&frame, 1, flags.into())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameRefFlags(aom_enc_frame_flags_t);
+
+impl FrameRefFlags {
+    pub fn new() -> Self {
+        FrameRefFlags(0)
+    }
+
+    /// AOM_EFLAG_FORCE_KF. Code the frame as a keyframe
+    pub fn force_keyframe(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_FORCE_KF, enable)
+    }
+
+    /// Makes the frame refresh GOLDEN_FRAME (and LAST_FRAME), e.g. for a clean long
+    /// term reference after a scene transition that doesn't warrant a keyframe.
+    ///
+    /// libaom has no AOM_EFLAG_FORCE_GF (it was VP8 only). But as soon as any
+    /// AOM_EFLAG_NO_UPD_* flag is given, libaom refreshes exactly the buffers that are
+    /// not excluded, so this sets AOM_EFLAG_NO_UPD_ARF and clears AOM_EFLAG_NO_UPD_GF
+    /// and AOM_EFLAG_NO_UPD_LAST. The frame's own GOP position is overridden, which
+    /// works best with realtime usage or g_lag_in_frames = 0.
+    pub fn force_golden(self, enable: bool) -> Self {
+        if enable {
+            self.set(AOM_EFLAG_NO_UPD_ARF, true)
+                .set(AOM_EFLAG_NO_UPD_GF, false)
+                .set(AOM_EFLAG_NO_UPD_LAST, false)
+        } else {
+            self.set(AOM_EFLAG_NO_UPD_ARF, false)
+        }
+    }
+
+    /// AOM_EFLAG_NO_REF_LAST. Don't predict from LAST_FRAME
+    pub fn no_ref_last(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_REF_LAST, enable)
+    }
+
+    /// AOM_EFLAG_NO_REF_LAST2. Don't predict from LAST2_FRAME
+    pub fn no_ref_last2(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_REF_LAST2, enable)
+    }
+
+    /// AOM_EFLAG_NO_REF_LAST3. Don't predict from LAST3_FRAME
+    pub fn no_ref_last3(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_REF_LAST3, enable)
+    }
+
+    /// AOM_EFLAG_NO_REF_GF. Don't predict from GOLDEN_FRAME
+    pub fn no_ref_golden(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_REF_GF, enable)
+    }
+
+    /// AOM_EFLAG_NO_REF_ARF. Don't predict from ALTREF_FRAME
+    pub fn no_ref_altref(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_REF_ARF, enable)
+    }
+
+    /// AOM_EFLAG_NO_REF_BWD. Don't predict from BWDREF_FRAME
+    pub fn no_ref_bwd(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_REF_BWD, enable)
+    }
+
+    /// AOM_EFLAG_NO_REF_ARF2. Don't predict from ALTREF2_FRAME
+    pub fn no_ref_altref2(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_REF_ARF2, enable)
+    }
+
+    /// AOM_EFLAG_NO_UPD_LAST. Don't refresh LAST_FRAME with this frame
+    pub fn no_upd_last(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_UPD_LAST, enable)
+    }
+
+    /// AOM_EFLAG_NO_UPD_GF. Don't refresh GOLDEN_FRAME with this frame
+    pub fn no_upd_golden(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_UPD_GF, enable)
+    }
+
+    /// AOM_EFLAG_NO_UPD_ARF. Don't refresh ALTREF_FRAME with this frame
+    pub fn no_upd_altref(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_UPD_ARF, enable)
+    }
+
+    /// AOM_EFLAG_NO_UPD_ENTROPY. Don't carry this frame's entropy context forward
+    pub fn no_upd_entropy(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_UPD_ENTROPY, enable)
+    }
+
+    /// AOM_EFLAG_NO_REF_FRAME_MVS. Don't use motion vectors of reference frames
+    pub fn no_ref_frame_mvs(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_NO_REF_FRAME_MVS, enable)
+    }
+
+    /// AOM_EFLAG_ERROR_RESILIENT. Code the frame in error resilient mode
+    pub fn error_resilient(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_ERROR_RESILIENT, enable)
+    }
+
+    /// AOM_EFLAG_SET_S_FRAME. Code the frame as a switch frame
+    pub fn switch_frame(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_SET_S_FRAME, enable)
+    }
+
+    /// AOM_EFLAG_SET_PRIMARY_REF_NONE. Don't inherit probabilities from a reference
+    pub fn primary_ref_none(self, enable: bool) -> Self {
+        self.set(AOM_EFLAG_SET_PRIMARY_REF_NONE, enable)
+    }
+
+    /// Raw bitset as passed to libaom
+    pub fn bits(&self) -> aom_enc_frame_flags_t {
+        self.0
+    }
+
+    fn set(self, flag: u32, enable: bool) -> Self {
+        let flag = flag as aom_enc_frame_flags_t;
+        if enable {
+            FrameRefFlags(self.0 | flag)
+        } else {
+            FrameRefFlags(self.0 & !flag)
+        }
+    }
+}
+
+impl From<FrameRefFlags> for aom_enc_frame_flags_t {
+    fn from(flags: FrameRefFlags) -> Self {
+        flags.0
+    }
+}
+
+/// Rate control diagnostics for the last frame packet returned by `get_packet`.
+///
+/// The `model_*` fields come from the crate's own leaky bucket model of the buffer
+/// described by rc_target_bitrate, rc_buf_sz and rc_buf_initial_sz, fed with the size
+/// and duration of each packet. They are not libaom's internal rate control state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameRcInfo {
+    /// Actual encoded size of the frame in bytes (the packet `sz`)
+    pub size: usize,
+    /// Bytes the model budgets for the frame's duration at the target bitrate
+    pub model_target_size: usize,
+    /// Quantizer (0-63) used for the frame, from AOME_GET_LAST_QUANTIZER_64. libaom
+    /// only reports the last frame it coded, which is the packet's frame in encodes
+    /// with g_lag_in_frames 0 only; None with a lookahead or when libaom can't tell.
+    pub quantizer: Option<i32>,
+    /// Model buffer fullness in bits after the frame was removed from the buffer
+    pub model_buffer_level: i64,
+    /// The frame was larger than what the model buffer held
+    pub model_underflow: bool,
+    /// The model buffer filled past rc_buf_sz (the encoder is undershooting)
+    pub model_overflow: bool,
+}
+
+/// Seek index of keyframe positions in the concatenated frame packet output.
+///
+/// Every frame packet advances the byte offset by its size, keyframes are recorded
+/// with the offset at which they start. The encoder keeps one up to date for the
+/// packets returned by `get_packet` (see `AV1Encoder::keyframe_index`), which matches
+/// the output as long as the frame packets are written back to back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyframeIndex {
+    entries: Vec<(u64, i64)>,
+    offset: u64,
+}
+
+impl KeyframeIndex {
+    pub fn new() -> Self {
+        KeyframeIndex {
+            entries: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Accounts for a packet, only frame packets move the offset
+    pub fn record(&mut self, pkt: &AOMPacket) {
+        if let AOMPacket::Frame(p) = pkt {
+            self.record_frame(p.is_key, p.t.pts.unwrap_or(0), p.data.len());
+        }
+    }
+
+    fn record_frame(&mut self, is_key: bool, pts: i64, size: usize) {
+        if is_key {
+            self.entries.push((self.offset, pts));
+        }
+        self.offset += size as u64;
+    }
+
+    /// (byte offset, pts) of every keyframe so far
+    pub fn entries(&self) -> &[(u64, i64)] {
+        &self.entries
+    }
+
+    /// Bytes of frame data accounted for so far
+    pub fn total_bytes(&self) -> u64 {
+        self.offset
+    }
+}
+
+/// Leaky bucket model of the rate control buffer described by rc_target_bitrate,
+/// rc_buf_sz and rc_buf_initial_sz. Bits drain in at the target bitrate and every
+/// frame takes its size out.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RcBufferModel {
+    bitrate: i64,
+    timebase: aom_rational,
+    max_level: i64,
+    level: i64,
+}
+
+impl RcBufferModel {
+    pub(crate) fn new(cfg: &aom_codec_enc_cfg) -> Self {
+        // bitrate is in kbps and the buffer sizes in ms, so bits = kbps * ms
+        let bitrate: i64 = cfg.rc_target_bitrate as i64 * 1000;
+        RcBufferModel {
+            bitrate,
+            timebase: cfg.g_timebase,
+            max_level: cfg.rc_buf_sz as i64 * bitrate / 1000,
+            level: cfg.rc_buf_initial_sz as i64 * bitrate / 1000,
+        }
+    }
+
+    /// Takes the bitrate and buffer size of a new configuration, keeping the current
+    /// level (clamped to the new buffer size)
+    fn reconfigure(&mut self, cfg: &aom_codec_enc_cfg) {
+        let level: i64 = self.level;
+        *self = RcBufferModel::new(cfg);
+        self.level = if self.max_level > 0 {
+            level.min(self.max_level)
+        } else {
+            level
+        };
+    }
+
+    /// Bits that flow into the buffer while a frame of `duration` timebase ticks is shown
+    fn bits_for(&self, duration: u64) -> i64 {
+        if self.timebase.den == 0 {
+            return 0;
+        }
+        self.bitrate * duration as i64 * self.timebase.num as i64 / self.timebase.den as i64
+    }
+
+    /// Accounts for one frame and returns (target bytes, level, underflow, overflow)
+    fn update(&mut self, size: usize, duration: u64) -> (usize, i64, bool, bool) {
+        let budget: i64 = self.bits_for(duration);
+        self.level += budget - size as i64 * 8;
+
+        let underflow: bool = self.level < 0;
+        let overflow: bool = self.max_level > 0 && self.level > self.max_level;
+        if overflow {
+            self.level = self.max_level;
+        }
+
+        ((budget / 8) as usize, self.level, underflow, overflow)
+    }
+}
+
+/// What the linked libaom supports, see `encoder_caps`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncoderCaps {
+    /// Raw aom_codec_caps_t of the AV1 encoder interface
+    pub raw: aom_codec_caps_t,
+    /// The library was built with the encoder (AOM_CODEC_CAP_ENCODER)
+    pub encoder: bool,
+    /// AOM_CODEC_CAP_PSNR, InitFlags::psnr can be used
+    pub psnr: bool,
+    /// AOM_CODEC_CAP_HIGHBITDEPTH, 10/12-bit input and InitFlags::high_bit_depth work
+    pub high_bit_depth: bool,
+    /// Built with CONFIG_REALTIME_ONLY, only AOM_USAGE_REALTIME and one pass are available
+    pub realtime_only: bool,
+    /// AV1 profiles the encoder can produce (0 = main, 1 = high, 2 = professional), the
+    /// ones libaom initializes an 8-bit encoder for
+    pub profiles: Vec<u32>,
+    /// Highest bit depth libaom initializes an encoder for: 12 (profile 2), 10 or 8
+    pub max_bit_depth: u32,
+    /// Threads available to the process, an upper bound for g_threads
+    pub threads: usize,
+    /// aom_codec_version_str, e.g. "3.9.1"
+    pub version: String,
+    /// aom_codec_build_config, the cmake flags libaom was built with
+    pub build_config: String,
+}
+
+/// Probes the capabilities of the linked libaom (aom_codec_get_caps on the AV1 encoder
+/// interface plus the build configuration), so applications can adapt at runtime.
+/// Profiles and bit depths are probed by initializing small realtime encoders, which
+/// takes a few milliseconds.
+pub fn encoder_caps() -> EncoderCaps {
+    let raw: aom_codec_caps_t = unsafe { aom_codec_get_caps(aom_codec_av1_cx()) };
+    let has = |cap: u32| raw & cap as aom_codec_caps_t != 0;
+
+    let to_string = |s: *const c_char| -> String {
+        if s.is_null() {
+            String::new()
+        } else {
+            unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+        }
+    };
+    let version: String = to_string(unsafe { aom_codec_version_str() });
+    let build_config: String = to_string(unsafe { aom_codec_build_config() });
+
+    let high_bit_depth: bool = has(AOM_CODEC_CAP_HIGHBITDEPTH);
+
+    EncoderCaps {
+        raw,
+        encoder: has(AOM_CODEC_CAP_ENCODER),
+        psnr: has(AOM_CODEC_CAP_PSNR),
+        high_bit_depth,
+        realtime_only: build_config.contains("CONFIG_REALTIME_ONLY=1"),
+        profiles: (0..=2)
+            .filter(|profile| init_succeeds(*profile, 8))
+            .collect(),
+        max_bit_depth: [(2, 12), (0, 10)]
+            .into_iter()
+            .find(|(profile, depth)| init_succeeds(*profile, *depth))
+            .map_or(8, |(_, depth)| depth),
+        threads: std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1),
+        version,
+        build_config,
+    }
+}
+
+// Whether libaom accepts a 16x16 realtime configuration (available in every build) for
+// `profile` at `bit_depth`; the encoder is destroyed right away
+fn init_succeeds(profile: u32, bit_depth: u32) -> bool {
+    let mut cfg: AV1EncoderConfig = match AV1EncoderConfig::for_realtime() {
+        Ok(cfg) => cfg,
+        Err(_) => return false,
+    };
+    cfg.g_w(16)
+        .g_h(16)
+        .g_profile(profile)
+        .g_bit_depth(bit_depth)
+        .g_input_bit_depth(bit_depth);
+    let flags: aom_codec_flags_t = if bit_depth > 8 {
+        AOM_CODEC_USE_HIGHBITDEPTH as aom_codec_flags_t
+    } else {
+        0
+    };
+
+    let mut ctx: MaybeUninit<aom_codec_ctx> = MaybeUninit::zeroed();
+    let result: u32 = unsafe {
+        aom_codec_enc_init_ver(
+            ctx.as_mut_ptr(),
+            aom_codec_av1_cx(),
+            &cfg.enc_cfg,
+            flags,
+            AOM_ENCODER_ABI_VERSION as i32,
+        )
+    };
+    if result != aom_codec_err_t_AOM_CODEC_OK {
+        return false;
+    }
+
+    unsafe { aom_codec_destroy(ctx.as_mut_ptr()) };
+    true
+}
+
+/// Outcome of `AV1Encoder::encode_with_deadline`
+pub enum DeadlineResult {
+    /// The encode finished within the deadline, the encoder is handed back
+    Done(AV1Encoder, Result<(), AomError>),
+    /// The deadline passed. The encode keeps running on the worker thread.
+    Late(PendingEncode),
+}
+
+/// An encode that missed its deadline. libaom can't be interrupted in the middle of a
+/// frame, so the encoder comes back once the frame is done. Dropping this detaches the
+/// worker, which then drops the encoder when it finishes.
+pub struct PendingEncode {
+    handle: JoinHandle<(AV1Encoder, Result<(), AomError>)>,
+    started: Instant,
+}
+
+impl PendingEncode {
+    /// The frame is done and `wait` won't block
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Time since the encode started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Blocks until the frame is done and hands the encoder back
+    pub fn wait(self) -> (AV1Encoder, Result<(), AomError>) {
+        match self.handle.join() {
+            Ok(done) => done,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
 }
 
+const MAX_OPERATING_POINTS: usize = 32;
+// seq_level_idx 31, "maximum parameters" level without level constraints (libaom default)
+const LEVEL_MAX_PARAMETERS: u32 = 31;
+// seq_level_idx of level 4.0, the first level that has a high tier
+const LEVEL_4_0: u32 = 8;
+// qindex delta libaom applies to the chroma DC/AC quantizers with
+// AV1E_SET_ENABLE_CHROMA_DELTAQ, it has no control for other values
+const CHROMA_DELTA_Q: i32 = 2;
+
+// AV1E_SET_TILE_COLUMNS / AV1E_SET_TILE_ROWS take log2 counts, at most 64 tiles
+const MAX_TILE_LOG2: u32 = 6;
+
+// AOME_SET_CQ_LEVEL is on the 0..=63 quantizer scale
+const MAX_CQ_LEVEL: u32 = 63;
+
+// DELTA_Q_HDR, the last mode of AV1E_SET_DELTAQ_MODE
+const MAX_DELTAQ_MODE: u32 = 5;
+
+/// Dropping an encoder discards the frames it still buffers (g_lag_in_frames > 0)
+/// without notice; with the `tracing` feature a warning is emitted. End a stream with
+/// `finish()`, or `flush()` and drain `get_packet()` until it returns None.
+///
+/// The encoder is `Send` but intentionally not `Sync`: it can move to another thread,
+/// and a `Mutex<AV1Encoder>` can be shared between the workers of a pool, but there is
+/// no concurrent access through `&AV1Encoder`.
 pub struct AV1Encoder {
     pub(crate) ctx: aom_codec_ctx_t,
     pub(crate) iter: aom_codec_iter_t,
+    // configuration the encoder currently runs with
+    pub(crate) cfg: aom_codec_enc_cfg,
+    pub(crate) rc_model: RcBufferModel,
+    pub(crate) last_rc_info: Option<FrameRcInfo>,
+    pub(crate) keyframes: KeyframeIndex,
+    pub(crate) lag_in_frames: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    // AV1E_SET_TIER_MASK and AV1E_SET_TARGET_SEQ_LEVEL_IDX state per operating point
+    pub(crate) tier_mask: u32,
+    pub(crate) target_levels: [u32; MAX_OPERATING_POINTS],
+    // frames were sent since the last flush
+    pub(crate) pending: bool,
+    // the first frame at or after this pts is forced to be a keyframe
+    pub(crate) segment_start: Option<i64>,
+    // the next frame is forced to be a keyframe, after a size change. libaom only forces
+    // one itself when a dimension grows past the size the encoder was created with;
+    // smaller sizes are coded as inter frames predicted from scaled references
+    pub(crate) keyframe_pending: bool,
+    pub(crate) chroma_qp_offset: i32,
+    // two-pass buffers the configuration points libaom at, read during the whole encode
+    pub(crate) stats_in: [Option<FixedBuf>; 2],
+    pub(crate) monochrome_fallback: bool,
+    // AV1E_SET_FILM_GRAIN_TABLE keeps the pointer, not a copy of the path
+    pub(crate) film_grain_table: Option<CString>,
+    // map handed to AOME_SET_ACTIVEMAP, libaom reads it through the pointer
+    pub(crate) active_map: Option<ActiveMap>,
 }
 
 impl AV1Encoder {
-    /// This calls the aom_codec_enc_init_ver function under the hood
-    pub fn new(cfg: &mut AV1EncoderConfig) -> Result<AV1Encoder, aom_codec_err_t> {
-        let mut ctx: MaybeUninit<aom_codec_ctx> = MaybeUninit::uninit();
+    /// This calls the aom_codec_enc_init_ver function under the hood.
+    /// No controls are set, the speed is libaom's default until `set_cpu_used`.
+    ///
+    /// The configuration is checked with `AV1EncoderConfig::validate` first. One libaom
+    /// rejects fails with `AomError::WithDetail`, carrying the message of the check that
+    /// failed (e.g. "g_w out of range [..1..65536]").
+    pub fn new(cfg: &mut AV1EncoderConfig) -> Result<AV1Encoder, AomError> {
+        Self::new_with_flags(cfg, InitFlags::new())
+    }
+
+    /// Same as `new`, but passes the given init flags (PSNR, high bit depth, ...) to libaom.
+    ///
+    /// High bit depth is turned on by itself when g_bit_depth or g_input_bit_depth is
+    /// above 8. A g_input_bit_depth above g_bit_depth is rejected with
+    /// `AomError::InvalidParam`.
+    pub fn new_with_flags(
+        cfg: &mut AV1EncoderConfig,
+        flags: InitFlags,
+    ) -> Result<AV1Encoder, AomError> {
+        cfg.validate()?;
+        let enc_cfg: &aom_codec_enc_cfg = &cfg.enc_cfg;
+        // the input is never downshifted, a 10-bit input needs a 10 or 12-bit stream
+        if enc_cfg.g_input_bit_depth > enc_cfg.g_bit_depth as u32 {
+            return Err(AomError::InvalidParam);
+        }
+        // libaom refuses high bit depth streams without the flag
+        let flags: InitFlags = if enc_cfg.g_bit_depth as u32 > 8 || enc_cfg.g_input_bit_depth > 8 {
+            flags.high_bit_depth(true)
+        } else {
+            flags
+        };
+
+        // zeroed, so err_detail reads as null when init bails out before setting it
+        let mut ctx: MaybeUninit<aom_codec_ctx> = MaybeUninit::zeroed();
         // If result is 0, it passed, otherwise failed
-        // TODO - Add custom error class
         let result: u32 = unsafe {
             aom_codec_enc_init_ver(
                 ctx.as_mut_ptr(),
                 aom_codec_av1_cx(),
                 &cfg.enc_cfg,
-                0,
+                flags.bits(),
                 AOM_ENCODER_ABI_VERSION as i32,
             )
         };
@@ -110,76 +765,1559 @@ impl AV1Encoder {
                 let mut enc: AV1Encoder = AV1Encoder {
                     ctx,
                     iter: ptr::null(),
+                    cfg: cfg.enc_cfg,
+                    rc_model: RcBufferModel::new(&cfg.enc_cfg),
+                    last_rc_info: None,
+                    keyframes: KeyframeIndex::new(),
+                    lag_in_frames: cfg.enc_cfg.g_lag_in_frames,
+                    width: cfg.enc_cfg.g_w,
+                    height: cfg.enc_cfg.g_h,
+                    tier_mask: 0,
+                    target_levels: [LEVEL_MAX_PARAMETERS; MAX_OPERATING_POINTS],
+                    pending: false,
+                    segment_start: None,
+                    keyframe_pending: false,
+                    chroma_qp_offset: 0,
+                    stats_in: [cfg.twopass_stats.clone(), cfg.mb_stats.clone()],
+                    monochrome_fallback: false,
+                    film_grain_table: None,
+                    active_map: None,
                 };
-
-                // check about this
-                // CPU usage level (which balances encoding speed and quality),
-                enc.aom_codec_control(aome_enc_control_id_AOME_SET_CPUUSED, 2)
-                    .expect("Cannot set CPUUSED");
+                if let Some(level) = cfg.cq_level {
+                    enc.set_cq_level(level)?;
+                }
 
                 Ok(enc)
             }
-            _ => Err(result),
+            _ => {
+                // the context is destroyed by now, but err_detail is left pointing at
+                // the static message of the failed check
+                let detail: *const c_char = unsafe { ctx.assume_init_ref() }.err_detail;
+                Err(with_detail(result, detail))
+            }
         }
     }
 
+    /// aom_codec_error_detail. Message libaom recorded for the last failed call on this
+    /// encoder, None if there is none.
+    pub fn error_detail(&self) -> Option<String> {
+        let detail: *const c_char = unsafe { aom_codec_error_detail(&self.ctx) };
+        if detail.is_null() {
+            None
+        } else {
+            Some(
+                unsafe { CStr::from_ptr(detail) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    // err as returned by the last call on this encoder, with the detail message
+    // libaom left for it; the next call on the encoder replaces the detail
+    fn error(&self, err: aom_codec_err_t) -> AomError {
+        with_detail(err, unsafe { aom_codec_error_detail(&self.ctx) })
+    }
+
     /// Calls aom_codec_control. Changes the codec configuration for an existing Av1Encoder Instance
-    pub fn aom_codec_control(
-        &mut self,
-        id: aome_enc_control_id,
-        val: i32,
-    ) -> Result<(), aom_codec_err_t> {
+    ///
+    /// Only for controls that take an int. Ids of controls that expect a pointer (active
+    /// map, SVC params, getters, ...) are rejected with AOM_CODEC_INVALID_PARAM, those
+    /// are reachable through their typed methods.
+    pub fn aom_codec_control(&mut self, id: aome_enc_control_id, val: i32) -> Result<(), AomError> {
+        if enc_control_takes_pointer(id) {
+            return Err(AomError::InvalidParam);
+        }
+
         let result: u32 = unsafe { aom_codec_control(&mut self.ctx, id as i32, val) };
 
+        #[cfg(feature = "tracing")]
+        tracing::trace!(id, val, result, "aom_codec_control");
+
         match result {
             aom_codec_err_t_AOM_CODEC_OK => Ok(()),
-            _ => Err(result),
+            _ => Err(self.error(result)),
         }
     }
 
-    // calls aom_codec_encode internally with Frame objects.
-    pub fn aom_codec_encode(&mut self, frame: &Frame) -> Result<(), aom_codec_err_t> {
-        let img: aom_image = img_from_frame(frame);
+    // aom_codec_control for controls that take a pointer (struct or out parameter)
+    fn control_ptr<T>(&mut self, id: aome_enc_control_id, val: &mut T) -> Result<(), AomError> {
+        let result: u32 = unsafe { aom_codec_control(&mut self.ctx, id as i32, val as *mut T) };
 
-        let ret = unsafe { aom_codec_encode(&mut self.ctx, &img, frame.t.pts.unwrap(), 1, 0) };
-        self.iter = ptr::null();
+        #[cfg(feature = "tracing")]
+        tracing::trace!(id, result, "aom_codec_control (pointer)");
 
-        match ret {
+        match result {
             aom_codec_err_t_AOM_CODEC_OK => Ok(()),
-            _ => Err(ret),
+            _ => Err(self.error(result)),
         }
     }
 
-    // calls aom_codec_encode internally. It clears out all the frames from the pointer
-    pub fn flush(&mut self) -> Result<(), aom_codec_err_t> {
-        let ret: u32 = unsafe { aom_codec_encode(&mut self.ctx, ptr::null_mut(), 0, 1, 0) };
+    /// AV1E_SET_COLOR_PRIMARIES. Signaled in the sequence header, set it before the
+    /// first frame. HDR10 is `Bt2020` with `Smpte2084` transfer and `Bt2020Ncl` matrix.
+    pub fn set_color_primaries(&mut self, primaries: ColorPrimaries) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_COLOR_PRIMARIES,
+            aom_color_primaries::from(primaries) as i32,
+        )
+    }
 
-        self.iter = ptr::null();
+    /// AV1E_SET_TRANSFER_CHARACTERISTICS, see `set_color_primaries`
+    pub fn set_transfer_characteristics(
+        &mut self,
+        transfer: TransferCharacteristics,
+    ) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_TRANSFER_CHARACTERISTICS,
+            aom_transfer_characteristics::from(transfer) as i32,
+        )
+    }
 
-        match ret {
-            aom_codec_err_t_AOM_CODEC_OK => Ok(()),
-            _ => Err(ret),
+    /// AV1E_SET_MATRIX_COEFFICIENTS, see `set_color_primaries`
+    pub fn set_matrix_coefficients(&mut self, matrix: MatrixCoefficients) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_MATRIX_COEFFICIENTS,
+            aom_matrix_coefficients::from(matrix) as i32,
+        )
+    }
+
+    /// Signals the color description of `fmt` (primaries, transfer, matrix and range
+    /// when the model has one) in the sequence header, so it matches the frames. Call it
+    /// before the first frame with the Formaton of the input.
+    pub fn set_color_description(&mut self, fmt: &Formaton) -> Result<(), AomError> {
+        self.set_color_primaries(fmt.get_primaries().into())?;
+        self.set_transfer_characteristics(fmt.get_xfer().into())?;
+        self.set_matrix_coefficients(fmt.get_matrix().into())?;
+        if let Some(range) = range_from_formaton(fmt) {
+            self.set_color_range(range)?;
         }
+        Ok(())
     }
 
-    // calls aom_codec_get_cx_data internally. Returns packet information
-    pub fn get_packet(&mut self) -> Option<AOMPacket> {
-        let pkt: *const crate::aom::aom_codec_cx_pkt =
-            unsafe { aom_codec_get_cx_data(&mut self.ctx, &mut self.iter) };
+    /// AV1E_SET_COLOR_RANGE. Signals whether samples use the full or the limited (studio)
+    /// range. It has to match the range of the frames' Formaton, which is also what ends up
+    /// on the aom_image. Set it before the first frame, it lands in the sequence header.
+    pub fn set_color_range(&mut self, range: ColorRange) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_COLOR_RANGE,
+            aom_color_range::from(range) as i32,
+        )
+    }
 
-        if pkt.is_null() {
-            None
+    /// AOME_SET_ACTIVEMAP. Marks 16x16 blocks of the following frames as active or
+    /// skipped. The map has to be built for the current g_w x g_h, i.e. have
+    /// `(g_h + 15) / 16` rows and `(g_w + 15) / 16` columns, otherwise
+    /// AOM_CODEC_INVALID_PARAM is returned. The encoder keeps its own copy of the map
+    /// for libaom to read from, so `map` only has to live for the duration of this call.
+    pub fn set_active_map(&mut self, map: &ActiveMap) -> Result<(), AomError> {
+        if map.rows() != (self.cfg.g_h + 15) / 16 || map.cols() != (self.cfg.g_w + 15) / 16 {
+            return Err(AomError::InvalidParam);
+        }
+
+        let map: &ActiveMap = self.active_map.insert(map.clone());
+        let mut aom_map: aom_active_map = map.to_aom();
+        self.control_ptr(aome_enc_control_id_AOME_SET_ACTIVEMAP, &mut aom_map)
+    }
+
+    /// AV1E_GET_ACTIVEMAP. The active map the encoder currently uses.
+    pub fn active_map(&mut self) -> Result<ActiveMap, AomError> {
+        let mut map: ActiveMap = ActiveMap::new(self.cfg.g_w, self.cfg.g_h);
+        let mut aom_map: aom_active_map = map.to_aom_mut();
+        self.control_ptr(aome_enc_control_id_AV1E_GET_ACTIVEMAP, &mut aom_map)?;
+
+        Ok(map)
+    }
+
+    /// AV1E_SET_TARGET_SEQ_LEVEL_IDX. Targets the seq_level_idx `level` (0-27 for levels
+    /// 2.0-8.3, i.e. `(major - 2) * 4 + minor`, 31 for no level constraints, 32 to only
+    /// keep level stats) on the operating point `op` (0-31). Fails with
+    /// AOM_CODEC_INVALID_PARAM when the operating point was set to the high tier and the
+    /// level is below 4.0, which has no high tier.
+    pub fn set_target_level(&mut self, op: u32, level: u32) -> Result<(), AomError> {
+        if op as usize >= MAX_OPERATING_POINTS || !(level <= 27 || level == 31 || level == 32) {
+            return Err(AomError::InvalidParam);
+        }
+        if self.tier_mask & (1 << op) != 0 && level < LEVEL_4_0 {
+            return Err(AomError::InvalidParam);
+        }
+
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_TARGET_SEQ_LEVEL_IDX,
+            (op * 100 + level) as i32,
+        )?;
+        self.target_levels[op as usize] = level;
+
+        Ok(())
+    }
+
+    /// AV1E_SET_TIER_MASK. Sets the tier of the operating point `op` (0-31), the other
+    /// operating points keep theirs. High bitrate content (e.g. 4K at level 5.x) needs the
+    /// high tier to stay within the level limits. Can be called before or after
+    /// `set_target_level`, both check that the high tier is only used with level 4.0+.
+    pub fn set_tier(&mut self, op: u32, tier: Tier) -> Result<(), AomError> {
+        if op as usize >= MAX_OPERATING_POINTS {
+            return Err(AomError::InvalidParam);
+        }
+
+        let mask: u32 = match tier {
+            Tier::Main => self.tier_mask & !(1 << op),
+            Tier::High => {
+                if self.target_levels[op as usize] < LEVEL_4_0 {
+                    return Err(AomError::InvalidParam);
+                }
+                self.tier_mask | (1 << op)
+            }
+        };
+
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_TIER_MASK, mask as i32)?;
+        self.tier_mask = mask;
+
+        Ok(())
+    }
+
+    /// AV1E_SET_SVC_PARAMS. Sets the spatial/temporal layer layout and the per-layer
+    /// bitrates, frame rate factors and quantizer ranges. The params are validated first,
+    /// mismatched array lengths fail with AOM_CODEC_INVALID_PARAM.
+    pub fn set_svc_params(&mut self, params: &SvcParams) -> Result<(), AomError> {
+        let mut aom_params: aom_svc_params = params.to_aom()?;
+        self.control_ptr(aome_enc_control_id_AV1E_SET_SVC_PARAMS, &mut aom_params)
+    }
+
+    /// AV1E_SET_SVC_LAYER_ID. The next frames are encoded as the given spatial/temporal
+    /// layer, which is what ends up in the OBU extension headers of their packets
+    /// (see `AOMPacket::layer_ids`). Needs the layout set with `set_svc_params` first,
+    /// ids beyond libaom's layer limits fail with AOM_CODEC_INVALID_PARAM.
+    pub fn set_svc_layer_id(&mut self, layer_id: &SvcLayerId) -> Result<(), AomError> {
+        let mut aom_layer_id: aom_svc_layer_id = layer_id.to_aom()?;
+        self.control_ptr(aome_enc_control_id_AV1E_SET_SVC_LAYER_ID, &mut aom_layer_id)
+    }
+
+    /// AV1E_SET_SVC_REF_FRAME_CONFIG. The next frames predict from and refresh the
+    /// reference slots of `config` instead of libaom's own choice, until it is set
+    /// again. Meant for realtime usage, typically set before each frame together with
+    /// `set_svc_layer_id`. Slot indices above 7 fail with AOM_CODEC_INVALID_PARAM.
+    pub fn set_ref_frame_config(&mut self, config: &RefFrameConfig) -> Result<(), AomError> {
+        let mut aom_config: aom_svc_ref_frame_config = config.to_aom()?;
+        self.control_ptr(
+            aome_enc_control_id_AV1E_SET_SVC_REF_FRAME_CONFIG,
+            &mut aom_config,
+        )
+    }
+
+    /// AOME_SET_CPUUSED. Speed preset, higher is faster and lower quality: 0-9 for good
+    /// quality and all intra usage, 0-11 for realtime usage. It can be changed between
+    /// encode calls (e.g. raised when the machine can't keep up) and takes effect on the
+    /// frames encoded after the call.
+    pub fn set_cpu_used(&mut self, cpu_used: i32) -> Result<(), AomError> {
+        let max: i32 = if self.cfg.g_usage == AOM_USAGE_REALTIME {
+            11
         } else {
-            Some(AOMPacket::new(unsafe { *pkt }))
+            9
+        };
+        if !(0..=max).contains(&cpu_used) {
+            return Err(AomError::InvalidParam);
         }
+
+        self.aom_codec_control(aome_enc_control_id_AOME_SET_CPUUSED, cpu_used)
     }
-}
 
-// When our AV1 Encoder goes out of scope, we need to call the aom_codec_destroy internally.
-impl Drop for AV1Encoder {
-    fn drop(&mut self) {
-        unsafe { aom_codec_destroy(&mut self.ctx) };
+    /// Applies the compound prediction toggles that are set, in one go. Meant to be
+    /// called before the first frame.
+    pub fn apply_compound_toggles(&mut self, toggles: &CompoundToggles) -> Result<(), AomError> {
+        for (id, enable) in toggles.controls() {
+            self.aom_codec_control(id, enable as i32)?;
+        }
+        Ok(())
     }
-}
 
-unsafe impl Send for AV1Encoder {}
+    /// AOME_SET_TUNING (aomenc --tune). Metric the rate-distortion decisions optimize
+    /// for, PSNR by default.
+    pub fn set_tune(&mut self, metric: TuneMetric) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AOME_SET_TUNING,
+            aom_tune_metric::from(metric) as i32,
+        )
+    }
+
+    /// AV1E_SET_TUNE_CONTENT (aomenc --tune-content). Set it before the first frame,
+    /// the screen content tools are decided per sequence.
+    pub fn set_tune_content(&mut self, content: TuneContent) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_TUNE_CONTENT,
+            aom_tune_content::from(content) as i32,
+        )
+    }
+
+    /// AV1E_SET_SUPERBLOCK_SIZE (aomenc --sb-size). The size is part of the sequence
+    /// header, set it before the first frame.
+    pub fn set_superblock_size(&mut self, size: SuperblockSize) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_SUPERBLOCK_SIZE,
+            aom_superblock_size_t::from(size) as i32,
+        )
+    }
+
+    /// AOME_SET_SHARPNESS (aomenc --sharpness). Trades loop filtering and quantization
+    /// for detail retention, 0 (default) to 7 (sharpest).
+    pub fn set_sharpness(&mut self, sharpness: i32) -> Result<(), AomError> {
+        if !(0..=7).contains(&sharpness) {
+            return Err(AomError::InvalidParam);
+        }
+        self.aom_codec_control(aome_enc_control_id_AOME_SET_SHARPNESS, sharpness)
+    }
+
+    /// Applies the in-loop filter settings that are set, in the order deblocking, CDEF,
+    /// restoration, delta LF. Stops at the first control libaom rejects.
+    pub fn configure_loopfilter(&mut self, settings: &LoopFilterSettings) -> Result<(), AomError> {
+        for (id, value) in settings.controls() {
+            self.aom_codec_control(id, value)?;
+        }
+        Ok(())
+    }
+
+    /// AV1E_SET_ENABLE_CDEF. On by default. Disabling it speeds up encoding and
+    /// decoding, at a quality cost on most content.
+    pub fn set_enable_cdef(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_ENABLE_CDEF, enable as i32)
+    }
+
+    /// AV1E_SET_ENABLE_RESTORATION. Loop restoration, on by default outside realtime.
+    /// Not available in CONFIG_REALTIME_ONLY builds of libaom.
+    pub fn set_enable_restoration(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_ENABLE_RESTORATION,
+            enable as i32,
+        )
+    }
+
+    /// AV1E_SET_LOOPFILTER_CONTROL with `LoopFilterControl::AllFrames` or `Disabled`.
+    /// Deblocking is on by default; use `configure_loopfilter` for the other modes.
+    pub fn set_enable_loopfilter(&mut self, enable: bool) -> Result<(), AomError> {
+        let control: LoopFilterControl = if enable {
+            LoopFilterControl::AllFrames
+        } else {
+            LoopFilterControl::Disabled
+        };
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_LOOPFILTER_CONTROL,
+            control as i32,
+        )
+    }
+
+    /// AOME_GET_LOOPFILTER_LEVEL. Luma deblocking level libaom picked for the last
+    /// coded frame, 0 when it was not filtered.
+    pub fn loopfilter_level(&mut self) -> Result<i32, AomError> {
+        let mut level: i32 = 0;
+        self.control_ptr(aome_enc_control_id_AOME_GET_LOOPFILTER_LEVEL, &mut level)?;
+        Ok(level)
+    }
+
+    /// AV1E_SET_ENABLE_GLOBAL_MOTION. Global motion models camera pans and zooms at the
+    /// frame level. Helps motion heavy content, costs encode time; disabling it is a
+    /// common realtime speedup.
+    pub fn set_enable_global_motion(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_ENABLE_GLOBAL_MOTION,
+            enable as i32,
+        )
+    }
+
+    /// AV1E_SET_ENABLE_WARPED_MOTION. Local warped motion per block, same tradeoff as
+    /// global motion.
+    pub fn set_enable_warped_motion(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_ENABLE_WARPED_MOTION,
+            enable as i32,
+        )
+    }
+
+    /// Chroma quantizer offset in qindex steps, added to the luma qindex for the U and V
+    /// planes. Positive values spend fewer bits on chroma.
+    ///
+    /// libaom only exposes this as AV1E_SET_ENABLE_CHROMA_DELTAQ, which switches a fixed
+    /// offset of +2 on or off. So only 0 and 2 are accepted, anything else fails with
+    /// AOM_CODEC_UNSUP_FEATURE. A negative offset (more chroma detail) is not reachable
+    /// through libaom's API.
+    pub fn set_chroma_qp_offset(&mut self, offset: i32) -> Result<(), AomError> {
+        if offset != 0 && offset != CHROMA_DELTA_Q {
+            return Err(AomError::UnsupFeature);
+        }
+
+        self.aom_codec_control(
+            aome_enc_control_id_AV1E_SET_ENABLE_CHROMA_DELTAQ,
+            (offset != 0) as i32,
+        )?;
+        self.chroma_qp_offset = offset;
+        Ok(())
+    }
+
+    /// Chroma quantizer offset set with `set_chroma_qp_offset`, 0 by default
+    pub fn chroma_qp_offset(&self) -> i32 {
+        self.chroma_qp_offset
+    }
+
+    /// AOME_SET_STATIC_THRESHOLD. Blocks whose difference to the reference is below the
+    /// threshold are coded as static (skipped). 0 disables it. The control takes an int,
+    /// thresholds above `i32::MAX` fail with AOM_CODEC_INVALID_PARAM.
+    pub fn set_static_threshold(&mut self, threshold: u32) -> Result<(), AomError> {
+        let threshold: i32 = i32::try_from(threshold).map_err(|_| AomError::InvalidParam)?;
+        self.aom_codec_control(aome_enc_control_id_AOME_SET_STATIC_THRESHOLD, threshold)
+    }
+
+    /// AV1E_SET_QUANTIZER_ONE_PASS. Encodes every following frame with the fixed
+    /// quantizer `q` (0-63). This is the simplest way to get a fixed quality in one-pass
+    /// all-intra mode (e.g. AVIF stills), without going through rc_end_usage/CQ level.
+    /// Fails with AOM_CODEC_INVALID_PARAM outside 0-63.
+    pub fn set_quantizer(&mut self, q: i32) -> Result<(), AomError> {
+        if !(0..=63).contains(&q) {
+            return Err(AomError::InvalidParam);
+        }
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_QUANTIZER_ONE_PASS, q)
+    }
+
+    /// AV1E_SET_TILE_COLUMNS. Splits frames into 2^`log2` tile columns (0..=6), which
+    /// can be encoded and decoded in parallel. libaom caps the count to what the frame
+    /// width allows (tiles are at least 256 pixels wide). Ignored when tile_widths is
+    /// set in the configuration.
+    pub fn set_tile_columns(&mut self, log2: u32) -> Result<(), AomError> {
+        if log2 > MAX_TILE_LOG2 {
+            return Err(AomError::InvalidParam);
+        }
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_TILE_COLUMNS, log2 as i32)
+    }
+
+    /// AV1E_SET_TILE_ROWS. 2^`log2` tile rows (0..=6), see `set_tile_columns`.
+    pub fn set_tile_rows(&mut self, log2: u32) -> Result<(), AomError> {
+        if log2 > MAX_TILE_LOG2 {
+            return Err(AomError::InvalidParam);
+        }
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_TILE_ROWS, log2 as i32)
+    }
+
+    /// AV1E_SET_ROW_MT. Row based multi-threading within tiles, on by default. Only has
+    /// an effect with g_threads > 1.
+    pub fn set_row_mt(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_ROW_MT, enable as i32)
+    }
+
+    /// AV1E_SET_FILM_GRAIN_TABLE. Reads film grain parameters from a table file (the
+    /// format written by aomenc --film-grain-table or noise_model) and signals them in
+    /// the stream instead of estimating grain; the decoder synthesizes the grain. Set it
+    /// before the first frame.
+    ///
+    /// A file that can't be opened is rejected with AOM_CODEC_INVALID_PARAM and the I/O
+    /// error as detail, libaom itself would go on without grain. A malformed table only
+    /// shows up as an encode error. A path that isn't valid UTF-8 or contains NUL bytes
+    /// is rejected with AOM_CODEC_INVALID_PARAM.
+    pub fn set_film_grain_table(&mut self, path: &Path) -> Result<(), AomError> {
+        if let Err(err) = File::open(path) {
+            return Err(AomError::WithDetail {
+                error: Box::new(AomError::InvalidParam),
+                detail: format!("can't open {}: {}", path.display(), err),
+            });
+        }
+        let path: &str = path.to_str().ok_or(AomError::InvalidParam)?;
+        let path: CString = CString::new(path).map_err(|_| AomError::InvalidParam)?;
+
+        // the string itself is the argument, not a pointer to it
+        let result: u32 = unsafe {
+            aom_codec_control(
+                &mut self.ctx,
+                aome_enc_control_id_AV1E_SET_FILM_GRAIN_TABLE as i32,
+                path.as_ptr(),
+            )
+        };
+        match result {
+            aom_codec_err_t_AOM_CODEC_OK => {
+                // the previous path is only dropped once libaom stopped pointing at it
+                self.film_grain_table = Some(path);
+                Ok(())
+            }
+            _ => Err(self.error(result)),
+        }
+    }
+
+    /// AV1E_SET_DENOISE_NOISE_LEVEL. Denoises the input at this noise level before
+    /// encoding and signals film grain matching the removed noise, so grainy sources
+    /// keep their look at a fraction of the bits. 0 (default) disables it, useful
+    /// values are around 5 to 50.
+    pub fn set_denoise_noise_level(&mut self, level: i32) -> Result<(), AomError> {
+        if level < 0 {
+            return Err(AomError::InvalidParam);
+        }
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_DENOISE_NOISE_LEVEL, level)
+    }
+
+    /// AOME_SET_CQ_LEVEL (aomenc --cq-level). Quality level of constant quality (AOM_CQ)
+    /// and quality (AOM_Q) rate control, 0..=63, lower is better. Other values fail with
+    /// AOM_CODEC_INVALID_PARAM. See also `AV1EncoderConfig::constant_quality`.
+    pub fn set_cq_level(&mut self, q: u32) -> Result<(), AomError> {
+        if q > MAX_CQ_LEVEL {
+            return Err(AomError::InvalidParam);
+        }
+        self.aom_codec_control(aome_enc_control_id_AOME_SET_CQ_LEVEL, q as i32)
+    }
+
+    /// AV1E_SET_AQ_MODE (aomenc --aq-mode)
+    pub fn set_aq_mode(&mut self, mode: AqMode) -> Result<(), AomError> {
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_AQ_MODE, mode as i32)
+    }
+
+    /// AV1E_SET_DELTAQ_MODE (aomenc --deltaq-mode). Varies the quantizer per superblock:
+    /// 0 off, 1 objective (the default in good quality), 2 perceptual, 3 perceptual for
+    /// all intra, 4 user rating based, 5 HDR. Other values fail with
+    /// AOM_CODEC_INVALID_PARAM. Some modes only work with a matching usage (3 needs all
+    /// intra), libaom rejects them otherwise.
+    pub fn set_deltaq_mode(&mut self, mode: u32) -> Result<(), AomError> {
+        if mode > MAX_DELTAQ_MODE {
+            return Err(AomError::InvalidParam);
+        }
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_DELTAQ_MODE, mode as i32)
+    }
+
+    /// AV1E_SET_LOSSLESS. Codes frames losslessly (qindex 0, no quantization or
+    /// in-loop filtering), for archival. The bitrate is many times that of lossy
+    /// coding. Best used with a configuration from `AV1EncoderConfig::lossless`, so
+    /// rate control doesn't fight it.
+    pub fn set_lossless(&mut self, enable: bool) -> Result<(), AomError> {
+        self.aom_codec_control(aome_enc_control_id_AV1E_SET_LOSSLESS, enable as i32)
+    }
+
+    /// Applies aomenc style options (`"enable-cdef" => "0"`, `"tune" => "ssim"`, ...)
+    /// through aom_codec_set_option, in key order. Stops at the first option libaom
+    /// rejects and returns `AomError::BadOption` naming it; the options before it stay
+    /// applied. Needs libaom 3.2.0 or newer at runtime (`AomError::VersionTooOld`).
+    pub fn apply_options(&mut self, opts: &BTreeMap<String, String>) -> Result<(), AomError> {
+        require_version(SET_OPTION_VERSION)?;
+
+        for (key, value) in opts {
+            let bad_option = |err: AomError| AomError::BadOption {
+                key: key.clone(),
+                error: Box::new(err),
+            };
+
+            // interior NULs can't cross the C boundary
+            let name: CString =
+                CString::new(key.as_str()).map_err(|_| bad_option(AomError::InvalidParam))?;
+            let value: CString =
+                CString::new(value.as_str()).map_err(|_| bad_option(AomError::InvalidParam))?;
+
+            let ret: aom_codec_err_t =
+                unsafe { aom_codec_set_option(&mut self.ctx, name.as_ptr(), value.as_ptr()) };
+            if ret != 0 {
+                return Err(bad_option(self.error(ret)));
+            }
+        }
+
+        Ok(())
+    }
+
+    // calls aom_codec_encode internally with Frame objects.
+    // The frame's TimeInfo duration is used when set, one time base tick otherwise.
+    pub fn aom_codec_encode(&mut self, frame: &Frame) -> Result<(), AomError> {
+        self.encode_with(frame, frame.t.duration.unwrap_or(1), 0)
+    }
+
+    /// Encodes a frame shown for `duration` ticks of g_timebase, with the given
+    /// aom_enc_frame_flags_t (AOM_EFLAG_FORCE_KF to force a keyframe, see also
+    /// `FrameRefFlags`). The duration goes to aom_codec_encode as is, so rate control
+    /// budgets bits for the real display time of the frame. This is what variable frame
+    /// rate input (e.g. screen recordings with idle periods) needs to hit its target
+    /// bitrate. `aom_codec_encode` is this call with the frame's own duration and no
+    /// flags.
+    ///
+    /// The frame needs a pts; AOM_CODEC_INVALID_PARAM without one or with a zero
+    /// duration.
+    pub fn encode_with(
+        &mut self,
+        frame: &Frame,
+        duration: u64,
+        flags: aom_enc_frame_flags_t,
+    ) -> Result<(), AomError> {
+        let view: ImageView<'_> = self.frame_view(frame)?;
+
+        let pts: i64 = frame.t.pts.ok_or(AomError::InvalidParam)?;
+        self.encode_view(&view, pts, duration, flags)
+    }
+
+    // The image every Frame based encode call hands to libaom, after the bit depth
+    // check and with the monochrome fallback applied
+    fn frame_view<'a>(&self, frame: &'a Frame) -> Result<ImageView<'a>, AomError> {
+        // before building the view, which may reject high bit depth formats for other
+        // reasons
+        if let Some(depth) = frame_bit_depth(frame) {
+            self.check_bit_depth(depth)?;
+        }
+        if self.monochrome_fallback {
+            ImageView::with_neutral_chroma(frame)
+        } else {
+            ImageView::new(frame)
+        }
+    }
+
+    /// `encode_with` and AOM_EFLAG_FORCE_KF: the frame is coded as a keyframe, whatever
+    /// kf_max_dist and libaom's own keyframe placement would have chosen. For on demand
+    /// IDRs, e.g. when a viewer joins a live stream or at a scene cut.
+    pub fn encode_keyframe(&mut self, frame: &Frame, duration: u64) -> Result<(), AomError> {
+        self.encode_with(frame, duration, AOM_EFLAG_FORCE_KF as aom_enc_frame_flags_t)
+    }
+
+    /// Encodes a frame and returns the packets that are ready right after it
+    pub fn encode_frame(&mut self, frame: &Frame) -> Result<Vec<AOMPacket>, AomError> {
+        self.aom_codec_encode(frame)?;
+
+        let mut packets: Vec<AOMPacket> = Vec::new();
+        while let Some(pkt) = self.get_packet() {
+            packets.push(pkt);
+        }
+
+        Ok(packets)
+    }
+
+    /// Same as `encode_frame`, with the wall time spent in aom_codec_encode attached to
+    /// every packet. With g_lag_in_frames > 0 the packets of a call can belong to earlier
+    /// frames, the time is the one of the call that produced them.
+    pub fn encode_frame_timed(
+        &mut self,
+        frame: &Frame,
+    ) -> Result<Vec<(AOMPacket, Duration)>, AomError> {
+        let start: Instant = Instant::now();
+        self.aom_codec_encode(frame)?;
+        let elapsed: Duration = start.elapsed();
+
+        let mut packets: Vec<(AOMPacket, Duration)> = Vec::new();
+        while let Some(pkt) = self.get_packet() {
+            packets.push((pkt, elapsed));
+        }
+
+        Ok(packets)
+    }
+
+    /// Encodes a frame on a worker thread and waits at most `timeout` for it.
+    ///
+    /// A single aom_codec_encode call can't be preempted, so this is a watchdog rather
+    /// than a cancellation: when the deadline passes, `DeadlineResult::Late` is returned
+    /// right away while the frame keeps encoding, and the encoder is given back by
+    /// `PendingEncode::wait`. Realtime callers can use the late signal to drop upcoming
+    /// frames or raise cpu_used. The encoder moves into the call, so nothing else can
+    /// touch it while the worker runs.
+    ///
+    /// The frame goes through the same checks as in `encode_with` (bit depth, monochrome
+    /// fallback), their errors come back in `DeadlineResult::Done`.
+    pub fn encode_with_deadline(
+        mut self,
+        frame: Frame,
+        pts: i64,
+        timeout: Duration,
+    ) -> DeadlineResult {
+        let started: Instant = Instant::now();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+
+        let handle = thread::spawn(move || {
+            let ret: Result<(), AomError> = self.frame_view(&frame).and_then(|view| {
+                let duration: u64 = frame.t.duration.unwrap_or(1);
+                self.encode_view(&view, pts, duration, 0)
+            });
+            // the receiver is gone when the caller stopped waiting
+            let _ = done_tx.send(());
+            (self, ret)
+        });
+
+        let pending: PendingEncode = PendingEncode { handle, started };
+        match done_rx.recv_timeout(timeout) {
+            Err(RecvTimeoutError::Timeout) => DeadlineResult::Late(pending),
+            // finished, or the worker panicked and wait() passes the panic on
+            _ => {
+                let (enc, ret) = pending.wait();
+                DeadlineResult::Done(enc, ret)
+            }
+        }
+    }
+
+    /// Encodes an image that borrows its planes, e.g. a view built ahead of time.
+    /// `duration` and `flags` work like in `encode_with`.
+    pub fn encode_view(
+        &mut self,
+        view: &ImageView<'_>,
+        pts: i64,
+        duration: u64,
+        flags: aom_enc_frame_flags_t,
+    ) -> Result<(), AomError> {
+        if duration == 0 {
+            return Err(AomError::InvalidParam);
+        }
+
+        self.encode_img(view.as_raw(), pts, duration, flags)
+    }
+
+    /// Encodes an image allocated with `OwnedImage::new`. `duration` and `flags` work
+    /// like in `encode_with`. The image can be reused for the next frame once the call
+    /// returns, libaom copies what it keeps.
+    pub fn encode_image(
+        &mut self,
+        img: &OwnedImage,
+        pts: i64,
+        duration: u64,
+        flags: aom_enc_frame_flags_t,
+    ) -> Result<(), AomError> {
+        if duration == 0 {
+            return Err(AomError::InvalidParam);
+        }
+
+        self.encode_img(img.as_raw(), pts, duration, flags)
+    }
+
+    /// Encodes a packed 8-bit RGB buffer (`width * height * 3` bytes, no row padding).
+    /// The pixels are converted to limited range I420 with the given matrix before
+    /// being handed to libaom, the matrix is also signaled on the image.
+    pub fn encode_rgb(
+        &mut self,
+        rgb: &[u8],
+        width: u32,
+        height: u32,
+        pts: i64,
+        matrix: ColorMatrix,
+    ) -> Result<(), AomError> {
+        let (w, h) = (width as usize, height as usize);
+        let len: Option<usize> = w.checked_mul(h).and_then(|n| n.checked_mul(3));
+        if w == 0 || h == 0 || len.map_or(true, |len| rgb.len() < len) {
+            return Err(AomError::InvalidParam);
+        }
+
+        let planes: I420Planes = rgb_to_i420(rgb, w, h, matrix);
+        let img: aom_image = planes.to_img();
+
+        // planes stays alive until the end of this function, after libaom copied the image
+        self.encode_img(&img, pts, 1, 0)
+    }
+
+    pub(crate) fn encode_img(
+        &mut self,
+        img: &aom_image,
+        pts: i64,
+        duration: u64,
+        mut flags: aom_enc_frame_flags_t,
+    ) -> Result<(), AomError> {
+        self.check_bit_depth(img.bit_depth)?;
+        // a monochrome stream only codes luma, the chroma planes of the input are
+        // dropped here rather than handed to libaom; luma only images need such a stream
+        let luma_only: aom_image;
+        let img: &aom_image = match (self.cfg.monochrome != 0, img.monochrome != 0) {
+            (true, false) => {
+                luma_only = aom_image {
+                    monochrome: 1,
+                    planes: [img.planes[0], ptr::null_mut(), ptr::null_mut()],
+                    stride: [img.stride[0], 0, 0],
+                    ..*img
+                };
+                &luma_only
+            }
+            (false, true) => return Err(AomError::InvalidParam),
+            _ => img,
+        };
+        // 16-bit samples go with the high bit depth flag, 8-bit ones without it
+        let high_bit_depth: bool =
+            self.ctx.init_flags & AOM_CODEC_USE_HIGHBITDEPTH as aom_codec_flags_t != 0;
+        if (img.fmt & AOM_IMG_FMT_HIGHBITDEPTH != 0) != high_bit_depth {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(fmt = img.fmt, high_bit_depth, "image sample size mismatch");
+            return Err(AomError::InvalidParam);
+        }
+
+        if self.segment_start.map_or(false, |start| pts >= start) {
+            flags |= AOM_EFLAG_FORCE_KF as aom_enc_frame_flags_t;
+            self.segment_start = None;
+        }
+        if self.keyframe_pending {
+            flags |= AOM_EFLAG_FORCE_KF as aom_enc_frame_flags_t;
+            self.keyframe_pending = false;
+        }
+
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "aom_codec_encode",
+            pts,
+            duration,
+            flags,
+            width = img.d_w,
+            height = img.d_h
+        )
+        .entered();
+
+        let ret = unsafe { aom_codec_encode(&mut self.ctx, img, pts, duration as _, flags) };
+        self.iter = ptr::null();
+        self.pending = true;
+
+        #[cfg(feature = "tracing")]
+        if ret != 0 {
+            tracing::warn!(error = ret, detail = ?self.error_detail(), "aom_codec_encode failed");
+        }
+
+        match ret {
+            aom_codec_err_t_AOM_CODEC_OK => Ok(()),
+            _ => Err(self.error(ret)),
+        }
+    }
+
+    /// When enabled, single plane (grayscale) frames passed to `aom_codec_encode`,
+    /// `encode_with` and friends get neutral chroma synthesized, see
+    /// `ImageView::with_neutral_chroma`. Off by default: such frames are then rejected
+    /// with AOM_CODEC_INVALID_PARAM, unless `monochrome` is set in the configuration.
+    pub fn set_monochrome_fallback(&mut self, enable: bool) {
+        self.monochrome_fallback = enable;
+    }
+
+    /// g_input_bit_depth the encoder was configured with, the bit depth every input
+    /// frame must have
+    pub fn input_bit_depth(&self) -> u32 {
+        self.cfg.g_input_bit_depth
+    }
+
+    // Feeding 8-bit frames to an encoder configured for 10-bit (or the other way
+    // around) makes libaom misread the planes and silently corrupts the stream.
+    fn check_bit_depth(&self, depth: u32) -> Result<(), AomError> {
+        if depth != self.input_bit_depth() {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                frame = depth,
+                configured = self.input_bit_depth(),
+                "input bit depth mismatch"
+            );
+            return Err(AomError::InvalidParam);
+        }
+        Ok(())
+    }
+
+    // calls aom_codec_encode internally. It clears out all the frames from the pointer
+    pub fn flush(&mut self) -> Result<(), AomError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("aom_codec_encode (flush)").entered();
+
+        let ret: u32 = unsafe { aom_codec_encode(&mut self.ctx, ptr::null_mut(), 0, 1, 0) };
+
+        self.iter = ptr::null();
+        self.pending = false;
+
+        match ret {
+            aom_codec_err_t_AOM_CODEC_OK => Ok(()),
+            _ => Err(self.error(ret)),
+        }
+    }
+
+    /// Starts a new segment (HLS/DASH) at `pts` without ending the stream.
+    ///
+    /// libaom has no partial flush: `flush` ends the sequence, and frames held back by
+    /// lag_in_frames only come out as later frames are fed. So the boundary is made with
+    /// a keyframe instead. The first frame encoded with a pts at or after `pts` gets
+    /// AOM_EFLAG_FORCE_KF, and since AV1 never predicts across it, everything before it
+    /// is a closed segment.
+    ///
+    /// Returns the packets that are ready now. Packets of frames still in the lookahead
+    /// arrive from later `get_packet` calls; they are the ones with a pts below the
+    /// boundary, the segment starts at the first keyframe packet with a pts at or after
+    /// it.
+    pub fn segment_boundary(&mut self, pts: i64) -> Vec<AOMPacket> {
+        self.segment_start = Some(pts);
+
+        let mut packets: Vec<AOMPacket> = Vec::new();
+        while let Some(pkt) = self.get_packet() {
+            packets.push(pkt);
+        }
+
+        packets
+    }
+
+    /// aom_codec_enc_config_set. Applies a modified configuration to the running
+    /// encoder, e.g. a new rc_target_bitrate for adaptive streaming. The change applies
+    /// to the frames encoded after the call.
+    ///
+    /// Rate control (rc_*), keyframe (kf_*), quantizer and tile fields can change, and
+    /// g_lag_in_frames can be lowered. Fields libaom sets up at init can't:
+    /// g_usage, g_pass, g_bit_depth, g_input_bit_depth and monochrome; changing one
+    /// fails with `AomError::InvalidConfig` naming it, as does raising g_lag_in_frames
+    /// or a configuration `AV1EncoderConfig::validate` rejects.
+    ///
+    /// g_w and g_h can change for resolution switching, in one pass encodes with
+    /// g_lag_in_frames 0 or 1 only (libaom can't resize frames already in the
+    /// lookahead). Frames passed after the call must have the new size. The next frame
+    /// is forced to be a keyframe, so a decoder or a player switching renditions can
+    /// start from it without references of the old size; libaom alone would only do so
+    /// when the size grows past the initial one. An active map is built for a
+    /// size, so it is dropped and turned off in libaom; set a new one for the new size.
+    /// Anything else libaom refuses comes back as its error with the detail message.
+    /// The `AV1EncoderConfig::cq_level` is not applied, use `set_cq_level`.
+    pub fn update_config(&mut self, cfg: &AV1EncoderConfig) -> Result<(), AomError> {
+        cfg.validate()?;
+
+        let new: &aom_codec_enc_cfg = &cfg.enc_cfg;
+        let fixed: [(&'static str, bool); 5] = [
+            ("g_usage", new.g_usage != self.cfg.g_usage),
+            ("g_pass", new.g_pass != self.cfg.g_pass),
+            ("g_bit_depth", new.g_bit_depth != self.cfg.g_bit_depth),
+            (
+                "g_input_bit_depth",
+                new.g_input_bit_depth != self.cfg.g_input_bit_depth,
+            ),
+            ("monochrome", new.monochrome != self.cfg.monochrome),
+        ];
+        if let Some(&(field, _)) = fixed.iter().find(|(_, changed)| *changed) {
+            return Err(AomError::InvalidConfig {
+                field,
+                reason: "can't change after the encoder was created".into(),
+            });
+        }
+        if new.g_lag_in_frames > self.cfg.g_lag_in_frames {
+            return Err(AomError::InvalidConfig {
+                field: "g_lag_in_frames",
+                reason: format!("can't be raised above {}", self.cfg.g_lag_in_frames),
+            });
+        }
+
+        let resized: bool = new.g_w != self.cfg.g_w || new.g_h != self.cfg.g_h;
+        if resized && (new.g_pass != aom_enc_pass_AOM_RC_ONE_PASS || new.g_lag_in_frames > 1) {
+            return Err(AomError::InvalidConfig {
+                field: if new.g_w != self.cfg.g_w {
+                    "g_w"
+                } else {
+                    "g_h"
+                },
+                reason: "can only change in one pass encodes with g_lag_in_frames <= 1".into(),
+            });
+        }
+
+        let ret: u32 = unsafe { aom_codec_enc_config_set(&mut self.ctx, new) };
+        if ret != aom_codec_err_t_AOM_CODEC_OK {
+            return Err(self.error(ret));
+        }
+
+        self.cfg = *new;
+        // the new configuration may point libaom at other two-pass buffers
+        self.stats_in = [cfg.twopass_stats.clone(), cfg.mb_stats.clone()];
+        self.lag_in_frames = new.g_lag_in_frames;
+        self.rc_model.reconfigure(&self.cfg);
+        if resized {
+            self.width = new.g_w;
+            self.height = new.g_h;
+            self.keyframe_pending = true;
+            if self.active_map.take().is_some() {
+                // a null map with the current dimensions disables the active map
+                let mut off: aom_active_map = aom_active_map {
+                    active_map: ptr::null_mut(),
+                    rows: (new.g_h + 15) / 16,
+                    cols: (new.g_w + 15) / 16,
+                };
+                self.control_ptr(aome_enc_control_id_AOME_SET_ACTIVEMAP, &mut off)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Brings the rate control buffer back to its initial fullness at a content boundary
+    /// (e.g. between spliced clips) without recreating the encoder.
+    ///
+    /// libaom has no control for this. The only place that touches the buffer level is
+    /// aom_codec_enc_config_set, which clamps it to rc_buf_sz. So the configuration is
+    /// applied once with rc_buf_sz = rc_buf_initial_sz, which drains an over-full buffer
+    /// down to the initial level, and then again with the real configuration. A buffer
+    /// that is below its initial level (after an overshoot) can't be refilled this way,
+    /// that needs a new encoder. The crate side model behind `last_frame_rc_info` is reset.
+    pub fn reset_rate_control(&mut self) -> Result<(), AomError> {
+        let mut clamped: aom_codec_enc_cfg = self.cfg;
+        clamped.rc_buf_sz = clamped.rc_buf_initial_sz;
+
+        let ret: u32 = unsafe { aom_codec_enc_config_set(&mut self.ctx, &clamped) };
+        if ret != 0 {
+            return Err(self.error(ret));
+        }
+        let ret: u32 = unsafe { aom_codec_enc_config_set(&mut self.ctx, &self.cfg) };
+        if ret != 0 {
+            return Err(self.error(ret));
+        }
+
+        self.rc_model = RcBufferModel::new(&self.cfg);
+        self.last_rc_info = None;
+
+        Ok(())
+    }
+
+    /// Terminal call of a stream. Flushes the encoder until libaom stops producing
+    /// packets and returns everything that was still buffered, then destroys the encoder.
+    pub fn finish(mut self) -> Result<Vec<AOMPacket>, AomError> {
+        let mut packets: Vec<AOMPacket> = Vec::new();
+
+        loop {
+            self.flush()?;
+
+            let before: usize = packets.len();
+            packets.extend(self.packets());
+
+            if packets.len() == before {
+                break;
+            }
+        }
+
+        Ok(packets)
+    }
+
+    /// Lazy counterpart of `finish`. Flushes the encoder and yields the remaining
+    /// packets one at a time, flushing again until libaom has nothing left. The encoder
+    /// is destroyed when the iterator is dropped. A failing flush ends the iteration.
+    pub fn into_packets(mut self) -> impl Iterator<Item = AOMPacket> {
+        let mut flushed: bool = false;
+        // packets came out since the last flush
+        let mut drained: bool = false;
+
+        std::iter::from_fn(move || loop {
+            if let Some(pkt) = self.get_packet() {
+                drained = true;
+                return Some(pkt);
+            }
+            if flushed && !drained {
+                return None;
+            }
+            if self.flush().is_err() {
+                // make the next call end the iteration as well
+                flushed = true;
+                drained = false;
+                return None;
+            }
+            flushed = true;
+            drained = false;
+        })
+    }
+
+    // calls aom_codec_get_cx_data internally. Returns packet information
+    pub fn get_packet(&mut self) -> Option<AOMPacket> {
+        let pkt: aom_codec_cx_pkt = self.next_cx_pkt()?;
+        let pkt: AOMPacket = AOMPacket::new(pkt);
+        self.keyframes.record(&pkt);
+        Some(pkt)
+    }
+
+    /// Packets that are ready, as an iterator over `get_packet`. Ends when libaom has
+    /// nothing more until the next `encode` or `flush`:
+    ///
+    /// ```rust,no_run
+    /// # use aom_rs::core::encoder::AV1Encoder;
+    /// # use av_data::frame::Frame;
+    /// # fn example(encoder: &mut AV1Encoder, frame: &Frame) -> Result<(), aom_rs::core::AomError> {
+    /// encoder.aom_codec_encode(&frame)?;
+    /// for pkt in encoder.packets() { /* ... */ }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn packets(&mut self) -> impl Iterator<Item = AOMPacket> + '_ {
+        std::iter::from_fn(move || self.get_packet())
+    }
+
+    /// Like `get_packet`, but copies the bytes of a frame packet into `buf` (replacing
+    /// its contents) instead of a freshly allocated `Packet`, so the data starts at the
+    /// buffer's alignment. `buf` keeps its allocation, reusing it across calls avoids
+    /// reallocating per frame. Non-frame packets leave `buf` untouched.
+    ///
+    /// Fails with `AomError::MemError` if `buf` can't grow; the packet is dropped then.
+    pub fn get_packet_into(
+        &mut self,
+        buf: &mut AlignedVec,
+    ) -> Option<Result<AlignedPacket, AomError>> {
+        let pkt: aom_codec_cx_pkt = self.next_cx_pkt()?;
+        if pkt.kind != aom_codec_cx_pkt_kind_AOM_CODEC_CX_FRAME_PKT {
+            return Some(Ok(AlignedPacket::Other(AOMPacket::new(pkt))));
+        }
+
+        let f: aom_codec_cx_pkt__bindgen_ty_1__bindgen_ty_1 = unsafe { pkt.data.frame };
+        let data: &[u8] = unsafe { slice::from_raw_parts(f.buf as *const u8, f.sz) };
+        buf.clear();
+        if let Err(e) = buf.extend_from_slice(data) {
+            return Some(Err(e));
+        }
+
+        let info: FrameInfo = FrameInfo {
+            pts: f.pts,
+            duration: f.duration as u64,
+            is_key: (f.flags & AOM_FRAME_IS_KEY) != 0,
+        };
+        self.keyframes.record_frame(info.is_key, info.pts, f.sz);
+        Some(Ok(AlignedPacket::Frame(info)))
+    }
+
+    /// Like `get_packet`, but without copying the bytes of a frame packet: they are
+    /// borrowed from libaom's buffer, valid until the encoder is used again. Meant for
+    /// writing frames straight to a muxer; copy the data to keep it around.
+    pub fn get_packet_ref(&mut self) -> Option<BorrowedPacket<'_>> {
+        let pkt: aom_codec_cx_pkt = self.next_cx_pkt()?;
+        if pkt.kind != aom_codec_cx_pkt_kind_AOM_CODEC_CX_FRAME_PKT {
+            return Some(BorrowedPacket::Other(AOMPacket::new(pkt)));
+        }
+
+        let f: aom_codec_cx_pkt__bindgen_ty_1__bindgen_ty_1 = unsafe { pkt.data.frame };
+        let info: FrameInfo = FrameInfo {
+            pts: f.pts,
+            duration: f.duration as u64,
+            is_key: (f.flags & AOM_FRAME_IS_KEY) != 0,
+        };
+        self.keyframes.record_frame(info.is_key, info.pts, f.sz);
+
+        // owned by the codec context until the next encode or get_cx_data call, both
+        // of which need &mut self
+        let data: &[u8] = if f.sz == 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(f.buf as *const u8, f.sz) }
+        };
+        Some(BorrowedPacket::Frame { data, info })
+    }
+
+    // Next packet from aom_codec_get_cx_data, with rate control tracking done for frames
+    fn next_cx_pkt(&mut self) -> Option<aom_codec_cx_pkt> {
+        let pkt: *const crate::aom::aom_codec_cx_pkt =
+            unsafe { aom_codec_get_cx_data(&mut self.ctx, &mut self.iter) };
+
+        if pkt.is_null() {
+            return None;
+        }
+
+        let pkt: aom_codec_cx_pkt = unsafe { *pkt };
+        if pkt.kind == aom_codec_cx_pkt_kind_AOM_CODEC_CX_FRAME_PKT {
+            let frame: aom_codec_cx_pkt__bindgen_ty_1__bindgen_ty_1 = unsafe { pkt.data.frame };
+            self.track_rc(frame);
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                pts = frame.pts,
+                bytes = frame.sz,
+                key = (frame.flags & AOM_FRAME_IS_KEY) != 0,
+                quantizer = self.last_rc_info.and_then(|info| info.quantizer),
+                "frame packet"
+            );
+        } else {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(kind = pkt.kind, "packet");
+        }
+        Some(pkt)
+    }
+
+    /// Keyframe positions of the frame packets returned by `get_packet` so far
+    pub fn keyframe_index(&self) -> &KeyframeIndex {
+        &self.keyframes
+    }
+
+    /// Rate control diagnostics (size vs. the model's budget, quantizer, model buffer
+    /// state) of the last frame packet returned by `get_packet`
+    pub fn last_frame_rc_info(&self) -> Option<FrameRcInfo> {
+        self.last_rc_info
+    }
+
+    /// Copy of the encoder's reconstruction of the last shown frame, i.e. what a decoder
+    /// outputs for it (AV1_GET_NEW_FRAME_IMAGE). Compare it with the source for PSNR or
+    /// other quality checks. With g_lag_in_frames > 0 this is the last frame that came
+    /// out as a packet, not the last one passed in. None before the first frame or when
+    /// `img_to_frame` can't convert the image.
+    pub fn get_reconstructed_frame(&mut self) -> Option<Frame> {
+        let img: aom_image = self.new_frame_image()?;
+        // the planes belong to the encoder and are reused by the next encode call
+        img_to_frame(&img).ok()
+    }
+
+    /// aom_codec_get_global_headers. The sequence header OBU the encoder produces for its
+    /// configuration, i.e. the configOBUs of an AVIF/MP4 av1C box. Available right after
+    /// init, before any frame. None when libaom has nothing to return.
+    pub fn get_global_headers(&mut self) -> Option<Vec<u8>> {
+        let headers: *mut aom_fixed_buf_t = unsafe { aom_codec_get_global_headers(&mut self.ctx) };
+        if headers.is_null() {
+            return None;
+        }
+
+        // both the struct and its buffer are malloc'd by libaom and owned by the caller
+        unsafe {
+            let data: Option<Vec<u8>> = if (*headers).buf.is_null() || (*headers).sz == 0 {
+                None
+            } else {
+                Some(to_buffer(*headers))
+            };
+            libc::free((*headers).buf);
+            libc::free(headers as *mut libc::c_void);
+            data
+        }
+    }
+
+    /// Size (width, height) the most recently coded frame was coded at, from
+    /// AV1_GET_NEW_FRAME_IMAGE. It differs from the configured g_w/g_h when internal
+    /// resize (`rc_resize_mode`) kicks in; with superres it is the upscaled size, only the
+    /// width is coded smaller. Falls back to the configured size if libaom can't tell.
+    ///
+    /// This is encoder state after the last encode call, not a property of a packet:
+    /// with g_lag_in_frames > 0, or when one call puts out several frame packets, the
+    /// frame coded last is not necessarily the one of the packet just drained.
+    pub fn coded_size(&mut self) -> (u32, u32) {
+        match self.new_frame_image() {
+            Some(img) if img.d_w != 0 && img.d_h != 0 => (img.d_w, img.d_h),
+            _ => (self.width, self.height),
+        }
+    }
+
+    // AV1_GET_NEW_FRAME_IMAGE, an image pointing into the encoder's frame buffers
+    fn new_frame_image(&mut self) -> Option<aom_image> {
+        let mut img: MaybeUninit<aom_image> = MaybeUninit::zeroed();
+        let ret = unsafe {
+            aom_codec_control(
+                &mut self.ctx,
+                aom_com_control_id_AV1_GET_NEW_FRAME_IMAGE as i32,
+                img.as_mut_ptr(),
+            )
+        };
+
+        if ret != 0 {
+            return None;
+        }
+        Some(unsafe { img.assume_init() })
+    }
+
+    fn track_rc(&mut self, f: aom_codec_cx_pkt__bindgen_ty_1__bindgen_ty_1) {
+        // the getter answers for the frame coded last, which with a lookahead can be
+        // ahead of the packet being drained
+        let mut quantizer: Option<i32> = None;
+        if self.lag_in_frames == 0 {
+            let mut q: i32 = -1;
+            // The getter writes through the pointer; on failure we keep -1
+            unsafe {
+                aom_codec_control(
+                    &mut self.ctx,
+                    aome_enc_control_id_AOME_GET_LAST_QUANTIZER_64 as i32,
+                    &mut q as *mut i32,
+                )
+            };
+            quantizer = Some(q).filter(|&q| q >= 0);
+        }
+
+        let (model_target_size, model_buffer_level, model_underflow, model_overflow) =
+            self.rc_model.update(f.sz, f.duration as u64);
+
+        self.last_rc_info = Some(FrameRcInfo {
+            size: f.sz,
+            model_target_size,
+            quantizer,
+            model_buffer_level,
+            model_underflow,
+            model_overflow,
+        });
+    }
+}
+
+// When our AV1 Encoder goes out of scope, we need to call the aom_codec_destroy internally.
+impl Drop for AV1Encoder {
+    fn drop(&mut self) {
+        #[cfg(feature = "tracing")]
+        if self.pending && self.lag_in_frames > 0 {
+            tracing::warn!(
+                lag_in_frames = self.lag_in_frames,
+                "encoder dropped without flushing, buffered frames are lost"
+            );
+        }
+
+        unsafe { aom_codec_destroy(&mut self.ctx) };
+    }
+}
+
+// The context and the buffers it points to (two-pass stats, maps, film grain table) are
+// owned by the encoder and libaom keeps no thread-local state for them, so the encoder
+// can be used from another thread. It is not Sync: aom_codec_control and the encode
+// calls, control getters included, take a mutable context and update its error state,
+// which is why they need &mut self. Sharing through a Mutex only needs Send.
+unsafe impl Send for AV1Encoder {}
+
+const _: fn() = || {
+    fn shareable<T: Send + Sync>() {}
+    shareable::<std::sync::Mutex<AV1Encoder>>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        aom::{
+            aom_dec_control_id_AOMD_GET_SB_SIZE,
+            aom_dec_control_id_AOMD_GET_SCREEN_CONTENT_TOOLS_INFO,
+            aom_dec_control_id_AOMD_GET_TILE_INFO, aom_screen_content_tools_info,
+            aom_superblock_size_AOM_SUPERBLOCK_SIZE_128X128, aom_tile_info,
+        },
+        core::decoder::{AV1Decoder, AV1DecoderConfig},
+    };
+    use av_data::{
+        frame::{FrameBufferConv, FrameType, VideoInfo},
+        pixel::formats::YUV420,
+        timeinfo::TimeInfo,
+    };
+    use std::{env, fs, mem, path::PathBuf, sync::Arc};
+
+    // I420 frame with a diagonal gradient moving with `index` and flat chroma
+    fn frame(width: usize, height: usize, index: usize) -> Frame {
+        let info: VideoInfo =
+            VideoInfo::new(width, height, false, FrameType::OTHER, Arc::new(*YUV420));
+        let t: TimeInfo = TimeInfo {
+            pts: Some(index as i64),
+            duration: Some(1),
+            ..Default::default()
+        };
+        let mut frame: Frame = Frame::new_default_frame(info, Some(t));
+
+        let stride: usize = frame.buf.linesize(0).unwrap();
+        let luma: &mut [u8] = frame.buf.as_mut_slice(0).unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                luma[y * stride + x] = ((x + y + index * 4) % 256) as u8;
+            }
+        }
+        for plane in 1..3 {
+            let chroma: &mut [u8] = frame.buf.as_mut_slice(plane).unwrap();
+            chroma.fill(128 + plane as u8);
+        }
+
+        frame
+    }
+
+    fn realtime_config(width: u32, height: u32) -> AV1EncoderConfig {
+        let mut cfg: AV1EncoderConfig = AV1EncoderConfig::for_realtime().unwrap();
+        cfg.g_w(width)
+            .g_h(height)
+            .g_timebase(aom_rational { num: 1, den: 30 })
+            .g_lag_in_frames(0);
+        cfg
+    }
+
+    // encodes `count` frames and returns every packet, the buffered ones included
+    fn encode(mut encoder: AV1Encoder, width: usize, height: usize, count: usize) -> Vec<Packet> {
+        let mut packets: Vec<AOMPacket> = Vec::new();
+        for i in 0..count {
+            packets.extend(encoder.encode_frame(&frame(width, height, i)).unwrap());
+        }
+        packets.extend(encoder.finish().unwrap());
+
+        frame_packets(packets)
+    }
+
+    fn frame_packets(packets: Vec<AOMPacket>) -> Vec<Packet> {
+        packets
+            .into_iter()
+            .filter_map(|pkt| match pkt {
+                AOMPacket::Frame(packet) => Some(packet),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // decoder holding the first frame of `packets`
+    fn decode_first(packets: &[Packet]) -> AV1Decoder {
+        let mut decoder: AV1Decoder = AV1Decoder::new(&AV1DecoderConfig::default()).unwrap();
+        decoder.decode(&packets[0].data, 0).unwrap();
+        decoder
+    }
+
+    // decodes every packet in order and checks the size of each frame
+    fn assert_decodes(packets: &[Packet], width: u32, height: u32) {
+        let mut decoder: AV1Decoder = AV1Decoder::new(&AV1DecoderConfig::default()).unwrap();
+        for (i, packet) in packets.iter().enumerate() {
+            decoder.decode(&packet.data, i as i64).unwrap();
+            assert_eq!(decoder.frame_size().unwrap(), (width, height));
+        }
+    }
+
+    #[test]
+    fn encode_4k_with_two_tile_columns() {
+        let mut encoder: AV1Encoder = AV1Encoder::new(&mut realtime_config(3840, 2160)).unwrap();
+        encoder.set_cpu_used(10).unwrap();
+        encoder.set_tile_columns(1).unwrap();
+
+        let packets: Vec<Packet> = encode(encoder, 3840, 2160, 2);
+        assert_eq!(packets.len(), 2);
+
+        let mut decoder: AV1Decoder = decode_first(&packets);
+        let mut info: aom_tile_info = unsafe { mem::zeroed() };
+        unsafe { decoder.aom_codec_control_ptr(aom_dec_control_id_AOMD_GET_TILE_INFO, &mut info) }
+            .unwrap();
+        assert_eq!(info.tile_columns, 2);
+    }
+
+    #[test]
+    fn film_grain_table() {
+        let path: PathBuf =
+            env::temp_dir().join(format!("aom-rs-grain-{}.tbl", std::process::id()));
+        fs::write(
+            &path,
+            "filmgrn1\n\
+             E 0 9223372036854775807 1 1234 1\n\
+             \tp 0 6 0 8 0 1 128 192 256 128 192 256\n\
+             \tsY 2  0 20 255 20\n\
+             \tsCb 0\n\
+             \tsCr 0\n\
+             \tcY\n\
+             \tcCb 0\n\
+             \tcCr 0\n",
+        )
+        .unwrap();
+
+        let mut encoder: AV1Encoder = AV1Encoder::new(&mut realtime_config(320, 240)).unwrap();
+        let ret: Result<(), AomError> = encoder.set_film_grain_table(&path);
+        fs::remove_file(&path).unwrap();
+        ret.unwrap();
+
+        let packets: Vec<Packet> = encode(encoder, 320, 240, 3);
+        assert_eq!(packets.len(), 3);
+
+        // the grain is synthesized by the decoder, so skipping it has to change the output
+        let grain: Frame = decode_first(&packets).get_frame().unwrap().unwrap();
+        let mut decoder: AV1Decoder = AV1Decoder::new(&AV1DecoderConfig::default()).unwrap();
+        decoder.set_skip_film_grain(true).unwrap();
+        decoder.decode(&packets[0].data, 0).unwrap();
+        let clean: Frame = decoder.get_frame().unwrap().unwrap();
+        let a: &[u8] = grain.buf.as_slice(0).unwrap();
+        let b: &[u8] = clean.buf.as_slice(0).unwrap();
+        assert_ne!(a, b);
+
+        let mut encoder: AV1Encoder = AV1Encoder::new(&mut realtime_config(320, 240)).unwrap();
+        let missing: PathBuf = env::temp_dir().join("aom-rs-missing-grain-table.tbl");
+        let err: AomError = encoder.set_film_grain_table(&missing).unwrap_err();
+        assert_eq!(err.code(), AomError::InvalidParam.code());
+    }
+
+    #[test]
+    fn screen_content_all_intra() {
+        let mut cfg: AV1EncoderConfig = AV1EncoderConfig::for_all_intra().unwrap();
+        cfg.g_w(320)
+            .g_h(240)
+            .g_timebase(aom_rational { num: 1, den: 30 });
+        let mut encoder: AV1Encoder = AV1Encoder::new(&mut cfg).unwrap();
+        encoder.set_cpu_used(6).unwrap();
+        encoder.set_tune_content(TuneContent::Screen).unwrap();
+
+        let packets: Vec<Packet> = encode(encoder, 320, 240, 3);
+        assert_eq!(packets.len(), 3);
+        assert!(packets.iter().all(|packet| packet.is_key));
+
+        let mut decoder: AV1Decoder = decode_first(&packets);
+        let mut info: aom_screen_content_tools_info = unsafe { mem::zeroed() };
+        unsafe {
+            decoder.aom_codec_control_ptr(
+                aom_dec_control_id_AOMD_GET_SCREEN_CONTENT_TOOLS_INFO,
+                &mut info,
+            )
+        }
+        .unwrap();
+        assert_eq!(info.allow_screen_content_tools, 1);
+    }
+
+    #[test]
+    fn lossless_round_trip() {
+        let mut cfg: AV1EncoderConfig = AV1EncoderConfig::for_good_quality().unwrap();
+        cfg.g_w(64)
+            .g_h(48)
+            .g_timebase(aom_rational { num: 1, den: 30 })
+            .g_lag_in_frames(0)
+            .lossless();
+        let mut encoder: AV1Encoder = AV1Encoder::new(&mut cfg).unwrap();
+        encoder.set_cpu_used(6).unwrap();
+        encoder.set_lossless(true).unwrap();
+
+        let packets: Vec<Packet> = encode(encoder, 64, 48, 3);
+        assert_eq!(packets.len(), 3);
+
+        let mut decoder: AV1Decoder = AV1Decoder::new(&AV1DecoderConfig::default()).unwrap();
+        for (i, packet) in packets.iter().enumerate() {
+            decoder.decode(&packet.data, i as i64).unwrap();
+            let decoded: Frame = decoder.get_frame().unwrap().unwrap();
+            let input: Frame = frame(64, 48, i);
+
+            for (plane, (w, h)) in [(64, 48), (32, 24), (32, 24)].into_iter().enumerate() {
+                let a: &[u8] = input.buf.as_slice(plane).unwrap();
+                let b: &[u8] = decoded.buf.as_slice(plane).unwrap();
+                let a_stride: usize = input.buf.linesize(plane).unwrap();
+                let b_stride: usize = decoded.buf.linesize(plane).unwrap();
+                for y in 0..h {
+                    assert_eq!(a[y * a_stride..][..w], b[y * b_stride..][..w]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn encode_with_every_aq_mode() {
+        let mut streams: Vec<Vec<u8>> = Vec::new();
+        for mode in [
+            AqMode::None,
+            AqMode::Variance,
+            AqMode::Complexity,
+            AqMode::CyclicRefresh,
+        ] {
+            let mut encoder: AV1Encoder = AV1Encoder::new(&mut realtime_config(320, 240)).unwrap();
+            encoder.set_cpu_used(10).unwrap();
+            encoder.set_aq_mode(mode).unwrap();
+
+            let packets: Vec<Packet> = encode(encoder, 320, 240, 3);
+            assert_eq!(packets.len(), 3, "{:?}", mode);
+            assert_decodes(&packets, 320, 240);
+            streams.push(packets.into_iter().flat_map(|packet| packet.data).collect());
+        }
+
+        // cyclic refresh codes a rotating set of blocks at a lower quantizer, so its stream
+        // has to differ from the plain one
+        assert!(streams[3] != streams[0]);
+    }
+
+    #[test]
+    fn encode_1080p_with_128x128_superblocks() {
+        let mut encoder: AV1Encoder = AV1Encoder::new(&mut realtime_config(1920, 1080)).unwrap();
+        encoder.set_cpu_used(10).unwrap();
+        encoder.set_superblock_size(SuperblockSize::Sb128).unwrap();
+
+        let packets: Vec<Packet> = encode(encoder, 1920, 1080, 2);
+        assert_eq!(packets.len(), 2);
+
+        let mut decoder: AV1Decoder = decode_first(&packets);
+        let mut size: aom_superblock_size_t = 0;
+        unsafe { decoder.aom_codec_control_ptr(aom_dec_control_id_AOMD_GET_SB_SIZE, &mut size) }
+            .unwrap();
+        assert_eq!(size, aom_superblock_size_AOM_SUPERBLOCK_SIZE_128X128);
+    }
+
+    #[test]
+    fn caps_are_probed() {
+        let caps: EncoderCaps = encoder_caps();
+        assert!(caps.encoder);
+        assert_eq!(caps.profiles, [0, 1, 2]);
+        // 10 and 12-bit streams need a high bit depth build
+        assert_eq!(caps.max_bit_depth > 8, caps.high_bit_depth);
+        assert!(!caps.version.is_empty());
+    }
+
+    #[test]
+    fn resize_forces_a_keyframe() {
+        let mut cfg: AV1EncoderConfig = realtime_config(1280, 720);
+        let mut encoder: AV1Encoder = AV1Encoder::new(&mut cfg).unwrap();
+        encoder.set_cpu_used(10).unwrap();
+        for i in 0..3 {
+            encoder.encode_frame(&frame(1280, 720, i)).unwrap();
+        }
+
+        cfg.g_w(640).g_h(360);
+        encoder.update_config(&cfg).unwrap();
+        let packets: Vec<Packet> =
+            frame_packets(encoder.encode_frame(&frame(640, 360, 3)).unwrap());
+        // libaom alone codes a downscaled frame as an inter frame
+        assert!(packets[0].is_key);
+        assert_eq!(packets[0].t.pts, Some(3));
+
+        let mut decoder: AV1Decoder = decode_first(&packets);
+        assert_eq!(decoder.frame_size().unwrap(), (640, 360));
+    }
+
+    #[test]
+    fn encode_on_another_thread() {
+        let encoder: AV1Encoder = AV1Encoder::new(&mut realtime_config(320, 240)).unwrap();
+
+        let worker = std::thread::spawn(move || encode(encoder, 320, 240, 3));
+        let packets: Vec<Packet> = worker.join().unwrap();
+        assert_eq!(packets.len(), 3);
+        assert_decodes(&packets, 320, 240);
+    }
+}
@@ -1,15 +1,119 @@
+use crate::aom::{
+    aom_codec_err_t, aom_codec_err_t_AOM_CODEC_ABI_MISMATCH,
+    aom_codec_err_t_AOM_CODEC_CORRUPT_FRAME, aom_codec_err_t_AOM_CODEC_ERROR,
+    aom_codec_err_t_AOM_CODEC_INCAPABLE, aom_codec_err_t_AOM_CODEC_INVALID_PARAM,
+    aom_codec_err_t_AOM_CODEC_LIST_END, aom_codec_err_t_AOM_CODEC_MEM_ERROR,
+    aom_codec_err_t_AOM_CODEC_UNSUP_BITSTREAM, aom_codec_err_t_AOM_CODEC_UNSUP_FEATURE,
+    aom_codec_err_to_string,
+};
 use core::fmt;
-use std::error::Error;
+use std::{error::Error, ffi::CStr};
 
-#[derive(Debug)]
-pub enum Av1Errors {
-    ConfigError,
+/// aom_codec_err_t as a Rust error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AomError {
+    /// AOM_CODEC_ERROR, unspecified error
+    Error,
+    /// AOM_CODEC_MEM_ERROR
+    MemError,
+    /// AOM_CODEC_ABI_MISMATCH
+    AbiMismatch,
+    /// AOM_CODEC_INCAPABLE, the codec lacks a required capability
+    Incapable,
+    /// AOM_CODEC_UNSUP_BITSTREAM, the bitstream is not supported or not AV1
+    UnsupBitstream,
+    /// AOM_CODEC_UNSUP_FEATURE, the stream uses a feature the codec doesn't support
+    UnsupFeature,
+    /// AOM_CODEC_CORRUPT_FRAME
+    CorruptFrame,
+    /// AOM_CODEC_INVALID_PARAM
+    InvalidParam,
+    /// AOM_CODEC_LIST_END
+    ListEnd,
+    /// A code libaom didn't have when the bindings were generated
+    Unknown(aom_codec_err_t),
+    /// A libaom error with the detail message the codec context recorded for it
+    /// (aom_codec_error_detail), e.g. which configuration field failed validation
+    WithDetail {
+        error: Box<AomError>,
+        detail: String,
+    },
+    /// `AV1EncoderConfig::validate` rejected a field of the configuration
+    InvalidConfig { field: &'static str, reason: String },
+    /// A string option (aom_codec_set_option) was rejected
+    BadOption { key: String, error: Box<AomError> },
+    /// The libaom loaded at runtime lacks an API, (major, minor, patch) versions
+    VersionTooOld {
+        required: (u32, u32, u32),
+        found: (u32, u32, u32),
+    },
 }
 
-impl fmt::Display for Av1Errors {
+impl From<aom_codec_err_t> for AomError {
+    fn from(err: aom_codec_err_t) -> Self {
+        match err {
+            aom_codec_err_t_AOM_CODEC_ERROR => AomError::Error,
+            aom_codec_err_t_AOM_CODEC_MEM_ERROR => AomError::MemError,
+            aom_codec_err_t_AOM_CODEC_ABI_MISMATCH => AomError::AbiMismatch,
+            aom_codec_err_t_AOM_CODEC_INCAPABLE => AomError::Incapable,
+            aom_codec_err_t_AOM_CODEC_UNSUP_BITSTREAM => AomError::UnsupBitstream,
+            aom_codec_err_t_AOM_CODEC_UNSUP_FEATURE => AomError::UnsupFeature,
+            aom_codec_err_t_AOM_CODEC_CORRUPT_FRAME => AomError::CorruptFrame,
+            aom_codec_err_t_AOM_CODEC_INVALID_PARAM => AomError::InvalidParam,
+            aom_codec_err_t_AOM_CODEC_LIST_END => AomError::ListEnd,
+            other => AomError::Unknown(other),
+        }
+    }
+}
+
+impl AomError {
+    /// The aom_codec_err_t this error stands for, None for the errors of the wrapper
+    /// itself (`InvalidConfig`, `BadOption`, `VersionTooOld`)
+    pub fn code(&self) -> Option<aom_codec_err_t> {
+        match self {
+            AomError::Error => Some(aom_codec_err_t_AOM_CODEC_ERROR),
+            AomError::MemError => Some(aom_codec_err_t_AOM_CODEC_MEM_ERROR),
+            AomError::AbiMismatch => Some(aom_codec_err_t_AOM_CODEC_ABI_MISMATCH),
+            AomError::Incapable => Some(aom_codec_err_t_AOM_CODEC_INCAPABLE),
+            AomError::UnsupBitstream => Some(aom_codec_err_t_AOM_CODEC_UNSUP_BITSTREAM),
+            AomError::UnsupFeature => Some(aom_codec_err_t_AOM_CODEC_UNSUP_FEATURE),
+            AomError::CorruptFrame => Some(aom_codec_err_t_AOM_CODEC_CORRUPT_FRAME),
+            AomError::InvalidParam => Some(aom_codec_err_t_AOM_CODEC_INVALID_PARAM),
+            AomError::ListEnd => Some(aom_codec_err_t_AOM_CODEC_LIST_END),
+            AomError::Unknown(code) => Some(*code),
+            AomError::WithDetail { error, .. } => error.code(),
+            AomError::InvalidConfig { .. }
+            | AomError::BadOption { .. }
+            | AomError::VersionTooOld { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for AomError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "")
+        if let AomError::WithDetail { error, detail } = self {
+            return write!(f, "{}: {}", error, detail);
+        }
+        if let Some(code) = self.code() {
+            // static strings, unknown codes get "Unrecognized error code"
+            let msg: &CStr = unsafe { CStr::from_ptr(aom_codec_err_to_string(code)) };
+            return write!(f, "{}", msg.to_string_lossy());
+        }
+
+        match self {
+            AomError::InvalidConfig { field, reason } => {
+                write!(f, "Invalid configuration, {}: {}", field, reason)
+            }
+            AomError::BadOption { key, error } => write!(f, "Option \"{}\": {}", key, error),
+            AomError::VersionTooOld { required, found } => write!(
+                f,
+                "libaom {}.{}.{} is too old, {}.{}.{} or newer is required",
+                found.0, found.1, found.2, required.0, required.1, required.2
+            ),
+            // handled through code() above
+            _ => unreachable!(),
+        }
     }
 }
 
-impl Error for Av1Errors {}
+impl Error for AomError {}
@@ -0,0 +1,96 @@
+use super::errors::AomError;
+use crate::{
+    aom::aom_image,
+    utils::{depack_422, img_from_frame, neutral_chroma_img, packed_422_offsets, I422Planes},
+};
+use av_data::frame::{Frame, FrameBufferConv, MediaKind};
+use std::marker::PhantomData;
+
+pub use crate::utils::OwnedImage;
+
+/// aom_image whose planes point into a borrowed Frame.
+///
+/// The view borrows the frame for `'a`, so the borrow checker makes sure the plane
+/// buffers outlive every use of the image. It can be built once and encoded later
+/// with `AV1Encoder::encode_view`, or handed to several encoders. Every encode path
+/// of the crate goes through a view, raw images are never taken from frames.
+///
+/// Packed 4:2:2 frames (YUYV/UYVY, as produced by most webcams) are depacked to planar
+/// I422 into a buffer owned by the view, since libaom only takes planar input.
+pub struct ImageView<'a> {
+    img: aom_image,
+    // planes that don't come from the frame, `img` points into them
+    _owned: Option<Owned>,
+    _frame: PhantomData<&'a Frame>,
+}
+
+enum Owned {
+    Depacked(I422Planes),
+    NeutralChroma(Vec<u8>),
+}
+
+// mid grey for 8-bit chroma
+const NEUTRAL_CHROMA: u8 = 128;
+
+impl<'a> ImageView<'a> {
+    pub fn new(frame: &'a Frame) -> Result<Self, AomError> {
+        if let MediaKind::Video(ref v) = frame.kind {
+            if let Some(offsets) = packed_422_offsets(&v.format) {
+                let src: &[u8] = frame.buf.as_slice(0).unwrap();
+                let stride: usize = frame.buf.linesize(0).unwrap();
+                let planes: I422Planes = depack_422(src, stride, v.width, v.height, offsets)?;
+
+                return Ok(ImageView {
+                    img: planes.to_img(&v.format)?,
+                    _owned: Some(Owned::Depacked(planes)),
+                    _frame: PhantomData,
+                });
+            }
+        }
+
+        Ok(ImageView {
+            img: img_from_frame(frame)?,
+            _owned: None,
+            _frame: PhantomData,
+        })
+    }
+
+    /// Like `new`, but a single plane (grayscale) 8-bit frame gets neutral (128) chroma
+    /// planes owned by the view, so it encodes as regular I420. This works with every
+    /// libaom build and profile, at the cost of a few bits for the flat chroma. With
+    /// `monochrome` set in the configuration the chroma planes are dropped anyway, `new`
+    /// is enough there.
+    /// Other frames are handled as in `new`.
+    pub fn with_neutral_chroma(frame: &'a Frame) -> Result<Self, AomError> {
+        let (width, height) = match frame.kind {
+            MediaKind::Video(ref v) if v.format.get_num_comp() == 1 => {
+                match v.format.get_chromaton(0) {
+                    Some(c) if c.get_depth() == 8 => {}
+                    _ => return Err(AomError::UnsupFeature),
+                }
+                (v.width, v.height)
+            }
+            _ => return Self::new(frame),
+        };
+
+        let chroma: Vec<u8> = vec![NEUTRAL_CHROMA; ((width + 1) / 2) * ((height + 1) / 2)];
+        Ok(ImageView {
+            img: neutral_chroma_img(frame, &chroma)?,
+            _owned: Some(Owned::NeutralChroma(chroma)),
+            _frame: PhantomData,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.img.d_w
+    }
+
+    pub fn height(&self) -> u32 {
+        self.img.d_h
+    }
+
+    /// The underlying aom_image, valid as long as the view
+    pub fn as_raw(&self) -> &aom_image {
+        &self.img
+    }
+}
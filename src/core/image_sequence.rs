@@ -0,0 +1,193 @@
+use super::{
+    config::{AV1EncoderConfig, RateControl},
+    encoder::{AOMPacket, AV1Encoder},
+    errors::AomError,
+    image::ImageView,
+    obu::{iter_obus, ObuType},
+    AomCodecEncCfgTrait,
+};
+use crate::{
+    aom::{aom_bit_depth_t, AOM_USAGE_ALL_INTRA},
+    utils::frame_bit_depth,
+};
+use av_data::frame::{Frame, MediaKind};
+use std::ops::Range;
+
+/// Encodes `frame` as a single still image, for the item data (`mdat`) of an AVIF file.
+///
+/// The encoder runs with an AOM_USAGE_ALL_INTRA configuration limited to one frame
+/// (g_limit 1), which gets libaom to write a reduced still picture sequence header, and
+/// constant quality rate control at `cq` (0..=63, lower is better). The frame's size,
+/// bit depth and monochrome-ness carry over to the configuration. The returned
+/// bitstream holds the sequence header and frame OBUs, without temporal delimiter.
+///
+/// # Example
+/// ```rust,no_run
+/// # use aom_rs::core::image_sequence::encode_still_image;
+/// # use av_data::frame::Frame;
+/// # fn example(frame: Frame) -> Result<(), aom_rs::core::AomError> {
+/// let obus = encode_still_image(&frame, 24)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode_still_image(frame: &Frame, cq: u32) -> Result<Vec<u8>, AomError> {
+    let info = match &frame.kind {
+        MediaKind::Video(info) => info,
+        _ => return Err(AomError::InvalidParam),
+    };
+    let depth: u32 = frame_bit_depth(frame).ok_or(AomError::InvalidParam)?;
+    let monochrome: bool = info.format.get_num_comp() == 1;
+
+    let mut cfg: AV1EncoderConfig = AV1EncoderConfig::for_all_intra()?;
+    cfg.g_w(info.width as u32)
+        .g_h(info.height as u32)
+        .g_limit(1)
+        .g_bit_depth(depth as aom_bit_depth_t)
+        .g_input_bit_depth(depth)
+        .monochrome(monochrome as u32);
+    // AOM_Q rather than AOM_CQ, there is no bitrate to cap
+    cfg.constant_quality(cq)?.rate_control(RateControl::Q);
+
+    let mut encoder: AV1Encoder = AV1Encoder::new(&mut cfg)?;
+    let view: ImageView<'_> = ImageView::new(frame)?;
+    encoder.encode_view(&view, 0, 1, 0)?;
+
+    let mut data: Vec<u8> = Vec::new();
+    for pkt in encoder.finish()? {
+        if let AOMPacket::Frame(packet) = pkt {
+            for obu in iter_obus(&packet.data) {
+                if obu.obu_type != ObuType::TemporalDelimiter {
+                    data.extend_from_slice(obu.data);
+                }
+            }
+        }
+    }
+
+    if data.is_empty() {
+        return Err(AomError::Error);
+    }
+    Ok(data)
+}
+
+/// Output of `ImageSequenceEncoder`, laid out for an AVIF image sequence track
+pub struct ImageSequence {
+    /// The sequence_header_obu() shared by all frames, for the configOBUs of the av1C box
+    pub sequence_header: Vec<u8>,
+    /// Frame samples back to back, without temporal delimiters. Every sample is a
+    /// keyframe and carries its own copy of the sequence header, as sync samples must.
+    pub data: Vec<u8>,
+    /// Byte range of each frame's sample in `data`, in display order
+    pub frames: Vec<Range<usize>>,
+    /// Duration of each frame in g_timebase ticks
+    pub durations: Vec<u64>,
+}
+
+/// Encodes frames as a sequence of independent intra frames (animated AVIF).
+///
+/// Uses an AOM_USAGE_ALL_INTRA configuration, so there is no inter prediction and
+/// no lag: each pushed frame comes out as one keyframe. Frames are timed by their
+/// position (pts is the index) and their TimeInfo duration, one tick if unset.
+///
+/// # Example
+/// ```rust,no_run
+/// # use aom_rs::core::{config::AV1EncoderConfig, image_sequence::ImageSequenceEncoder, AomCodecEncCfgTrait};
+/// # use av_data::frame::Frame;
+/// # fn example(frames: Vec<Frame>, width: u32, height: u32) -> Result<(), aom_rs::core::AomError> {
+/// let mut config = AV1EncoderConfig::for_all_intra()?;
+/// config.g_w(width).g_h(height);
+/// let sequence = ImageSequenceEncoder::new(&mut config)?.encode(&frames)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ImageSequenceEncoder {
+    encoder: AV1Encoder,
+    sequence: ImageSequence,
+    pts: i64,
+}
+
+impl ImageSequenceEncoder {
+    /// `cfg` must use AOM_USAGE_ALL_INTRA with keyframes only (kf_max_dist 0) and
+    /// low-overhead framing (save_as_annexb off); `AomError::InvalidParam` otherwise.
+    pub fn new(cfg: &mut AV1EncoderConfig) -> Result<Self, AomError> {
+        if cfg.g_usage != AOM_USAGE_ALL_INTRA || cfg.kf_max_dist != 0 || cfg.save_as_annexb != 0 {
+            return Err(AomError::InvalidParam);
+        }
+
+        Ok(ImageSequenceEncoder {
+            encoder: AV1Encoder::new(cfg)?,
+            sequence: ImageSequence {
+                sequence_header: Vec::new(),
+                data: Vec::new(),
+                frames: Vec::new(),
+                durations: Vec::new(),
+            },
+            pts: 0,
+        })
+    }
+
+    /// Encodes the next frame of the sequence
+    pub fn push(&mut self, frame: &Frame) -> Result<(), AomError> {
+        let duration: u64 = frame.t.duration.unwrap_or(1);
+        let view: ImageView<'_> = ImageView::new(frame)?;
+        self.encoder.encode_view(&view, self.pts, duration, 0)?;
+        self.pts += 1;
+        self.sequence.durations.push(duration);
+
+        while let Some(pkt) = self.encoder.get_packet() {
+            self.sequence.add_packet(pkt)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the encoder and returns the sequence
+    pub fn finish(self) -> Result<ImageSequence, AomError> {
+        let ImageSequenceEncoder {
+            encoder,
+            mut sequence,
+            ..
+        } = self;
+        for pkt in encoder.finish()? {
+            sequence.add_packet(pkt)?;
+        }
+
+        // one keyframe per pushed frame, anything else means libaom held frames back
+        if sequence.frames.len() != sequence.durations.len() {
+            return Err(AomError::Error);
+        }
+        Ok(sequence)
+    }
+
+    /// `push` for every frame, then `finish`
+    pub fn encode(mut self, frames: &[Frame]) -> Result<ImageSequence, AomError> {
+        for frame in frames {
+            self.push(frame)?;
+        }
+        self.finish()
+    }
+}
+
+impl ImageSequence {
+    fn add_packet(&mut self, pkt: AOMPacket) -> Result<(), AomError> {
+        let packet = match pkt {
+            AOMPacket::Frame(packet) => packet,
+            _ => return Ok(()),
+        };
+        if !packet.is_key {
+            return Err(AomError::Error);
+        }
+
+        let start: usize = self.data.len();
+        for obu in iter_obus(&packet.data) {
+            match obu.obu_type {
+                ObuType::TemporalDelimiter => continue,
+                ObuType::SequenceHeader if self.sequence_header.is_empty() => {
+                    self.sequence_header = obu.data.to_vec();
+                }
+                _ => {}
+            }
+            self.data.extend_from_slice(obu.data);
+        }
+        self.frames.push(start..self.data.len());
+        Ok(())
+    }
+}
@@ -1,11 +1,28 @@
 use crate::aom::{
-    aom_bit_depth_t, aom_codec_er_flags_t, aom_enc_pass, aom_fixed_buf_t, aom_kf_mode,
-    aom_rational, aom_rc_mode, aom_superres_mode, cfg_options_t,
+    aom_bit_depth_t, aom_codec_er_flags_t, aom_enc_pass, aom_kf_mode, aom_rational, aom_rc_mode,
+    aom_superres_mode, cfg_options_t,
 };
+use config::FixedBuf;
 
+pub mod aligned;
 pub mod config;
+pub mod controls;
+pub mod decoder;
 pub mod encoder;
 mod errors;
+pub mod image;
+pub mod image_sequence;
+#[cfg(feature = "mp4")]
+pub mod mp4;
+pub mod obu;
+pub mod quality;
+pub mod scenecut;
+pub mod tile_list;
+pub mod transcode;
+pub mod twopass;
+pub mod version;
+
+pub use errors::AomError;
 
 /// A trait for configuring the AV1 Encoder with builder-style methods.
 ///
@@ -21,7 +38,7 @@ mod errors;
 /// structs representing an AV1 encoder, enabling customization and flexible encoding configurations.
 ///
 /// Example usage:
-/// ```rust
+/// ```rust,ignore
 /// struct MyEncoder;
 ///
 /// impl AomCodecEncCfgTrait for MyEncoder {
@@ -58,8 +75,8 @@ pub trait AomCodecEncCfgTrait {
     fn rc_superres_qthresh(&mut self, value: u32) -> &mut Self;
     fn rc_superres_kf_qthresh(&mut self, value: u32) -> &mut Self;
     fn rc_end_usage(&mut self, value: aom_rc_mode) -> &mut Self;
-    fn rc_twopass_stats_in(&mut self, value: aom_fixed_buf_t) -> &mut Self;
-    fn rc_firstpass_mb_stats_in(&mut self, value: aom_fixed_buf_t) -> &mut Self;
+    fn rc_twopass_stats_in(&mut self, value: FixedBuf) -> &mut Self;
+    fn rc_firstpass_mb_stats_in(&mut self, value: FixedBuf) -> &mut Self;
     fn rc_target_bitrate(&mut self, value: u32) -> &mut Self;
     fn rc_min_quantizer(&mut self, value: u32) -> &mut Self;
     fn rc_max_quantizer(&mut self, value: u32) -> &mut Self;
@@ -0,0 +1,541 @@
+use super::{
+    encoder::AOMPacket,
+    obu::{iter_obus, ObuType, SequenceHeader},
+};
+use crate::aom::aom_rational;
+use std::io::{self, Seek, SeekFrom, Write};
+
+struct Sample {
+    size: u32,
+    pts: i64,
+    duration: u64,
+    is_key: bool,
+}
+
+/// Writes the frame packets of an encoder into a progressive MP4 with a single AV1 track.
+///
+/// The file starts with `ftyp` and `mdat`, the samples are appended to `mdat` as they
+/// come in and `finish` writes the `moov` box (sample tables and the av1C
+/// configuration record built from the first sequence header). Temporal delimiters are
+/// dropped from the samples as required by the AV1 ISOBMFF binding.
+///
+/// ```rust,no_run
+/// # use aom_rs::core::{config::AV1EncoderConfig, encoder::AV1Encoder, mp4::Mp4Av1Writer};
+/// # use std::fs::File;
+/// # fn example(encoder: &mut AV1Encoder, cfg: &AV1EncoderConfig) -> std::io::Result<()> {
+/// # let (width, height) = (cfg.g_w, cfg.g_h);
+/// let file = File::create("out.mp4")?;
+/// let mut writer = Mp4Av1Writer::new(file, width, height, cfg.g_timebase)?;
+/// while let Some(pkt) = encoder.get_packet() {
+///     writer.write_packet(&pkt)?;
+/// }
+/// writer.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Mp4Av1Writer<W: Write + Seek> {
+    out: W,
+    width: u32,
+    height: u32,
+    timebase: aom_rational,
+    mdat_start: u64,
+    mdat_size: u64,
+    samples: Vec<Sample>,
+    // sequence header OBU (with its header) and its parsed fields
+    sequence_header: Option<(Vec<u8>, SequenceHeader)>,
+}
+
+impl<W: Write + Seek> Mp4Av1Writer<W> {
+    /// Starts the file. `timebase` is the g_timebase of the encoder, pts and durations of
+    /// the packets are in its units.
+    pub fn new(mut out: W, width: u32, height: u32, timebase: aom_rational) -> io::Result<Self> {
+        if timebase.num <= 0 || timebase.den <= 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid time base",
+            ));
+        }
+
+        let mut ftyp: Vec<u8> = Vec::new();
+        ftyp.extend_from_slice(b"isom");
+        ftyp.extend_from_slice(&0x200u32.to_be_bytes());
+        for brand in [b"isom", b"iso6", b"av01", b"mp41"] {
+            ftyp.extend_from_slice(brand);
+        }
+        out.write_all(&mp4_box(b"ftyp", &ftyp))?;
+
+        // mdat with a 64-bit size, patched in finish
+        let mdat_start: u64 = out.stream_position()?;
+        out.write_all(&1u32.to_be_bytes())?;
+        out.write_all(b"mdat")?;
+        out.write_all(&0u64.to_be_bytes())?;
+
+        Ok(Mp4Av1Writer {
+            out,
+            width,
+            height,
+            timebase,
+            mdat_start,
+            mdat_size: 16,
+            samples: Vec::new(),
+            sequence_header: None,
+        })
+    }
+
+    /// Appends a frame packet as a sample, other packets are ignored
+    pub fn write_packet(&mut self, pkt: &AOMPacket) -> io::Result<()> {
+        let packet = match pkt {
+            AOMPacket::Frame(p) => p,
+            _ => return Ok(()),
+        };
+
+        let mut sample: Vec<u8> = Vec::with_capacity(packet.data.len());
+        for obu in iter_obus(&packet.data) {
+            match obu.obu_type {
+                ObuType::TemporalDelimiter => continue,
+                ObuType::SequenceHeader if self.sequence_header.is_none() => {
+                    let header: SequenceHeader = SequenceHeader::parse(obu.payload)
+                        .map_err(|_| invalid_data("cannot parse the sequence header"))?;
+                    self.sequence_header = Some((obu.data.to_vec(), header));
+                }
+                _ => {}
+            }
+            sample.extend_from_slice(obu.data);
+        }
+
+        self.out.write_all(&sample)?;
+        self.mdat_size += sample.len() as u64;
+        self.samples.push(Sample {
+            size: sample.len() as u32,
+            pts: packet.t.pts.unwrap_or(0),
+            duration: packet.t.duration.unwrap_or(1),
+            is_key: packet.is_key,
+        });
+
+        Ok(())
+    }
+
+    /// Finalizes `mdat`, writes `moov` and returns the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let (seq_obu, seq) = self
+            .sequence_header
+            .take()
+            .ok_or_else(|| invalid_data("no sequence header was written"))?;
+
+        let end: u64 = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(self.mdat_start + 8))?;
+        self.out.write_all(&self.mdat_size.to_be_bytes())?;
+        self.out.seek(SeekFrom::Start(end))?;
+
+        let moov: Vec<u8> = self.moov(&seq_obu, &seq)?;
+        self.out.write_all(&moov)?;
+        self.out.flush()?;
+
+        Ok(self.out)
+    }
+
+    /// Sample durations in timescale (time base denominator) units. A sample lasts until
+    /// the next one starts, the last one uses its packet duration.
+    fn durations(&self) -> Vec<u32> {
+        let num: u64 = self.timebase.num as u64;
+        (0..self.samples.len())
+            .map(|i| {
+                let ticks: u64 = match self.samples.get(i + 1) {
+                    Some(next) if next.pts > self.samples[i].pts => {
+                        (next.pts - self.samples[i].pts) as u64
+                    }
+                    _ => self.samples[i].duration.max(1),
+                };
+                (ticks * num).min(u32::MAX as u64) as u32
+            })
+            .collect()
+    }
+
+    fn moov(&self, seq_obu: &[u8], seq: &SequenceHeader) -> io::Result<Vec<u8>> {
+        let timescale: u32 = self.timebase.den as u32;
+        let durations: Vec<u32> = self.durations();
+        let total: u32 = durations
+            .iter()
+            .fold(0u64, |acc, d| acc + *d as u64)
+            .min(u32::MAX as u64) as u32;
+
+        // sample data starts right after the 16 byte mdat header
+        let chunk_offset: u64 = self.mdat_start + 16;
+        if chunk_offset > u32::MAX as u64 {
+            return Err(invalid_data("mdat starts past 4 GiB"));
+        }
+
+        let mut mvhd: Vec<u8> = full_box_header(0, 0);
+        mvhd.extend_from_slice(&[0; 8]); // creation/modification time
+        mvhd.extend_from_slice(&timescale.to_be_bytes());
+        mvhd.extend_from_slice(&total.to_be_bytes());
+        mvhd.extend_from_slice(&0x0001_0000u32.to_be_bytes()); // rate 1.0
+        mvhd.extend_from_slice(&0x0100u16.to_be_bytes()); // volume 1.0
+        mvhd.extend_from_slice(&[0; 10]);
+        mvhd.extend_from_slice(&UNITY_MATRIX);
+        mvhd.extend_from_slice(&[0; 24]);
+        mvhd.extend_from_slice(&2u32.to_be_bytes()); // next_track_ID
+
+        // enabled | in_movie
+        let mut tkhd: Vec<u8> = full_box_header(0, 3);
+        tkhd.extend_from_slice(&[0; 8]);
+        tkhd.extend_from_slice(&1u32.to_be_bytes()); // track_ID
+        tkhd.extend_from_slice(&[0; 4]);
+        tkhd.extend_from_slice(&total.to_be_bytes());
+        tkhd.extend_from_slice(&[0; 16]); // reserved, layer, alternate_group, volume, reserved
+        tkhd.extend_from_slice(&UNITY_MATRIX);
+        tkhd.extend_from_slice(&(self.width << 16).to_be_bytes());
+        tkhd.extend_from_slice(&(self.height << 16).to_be_bytes());
+
+        let mut mdhd: Vec<u8> = full_box_header(0, 0);
+        mdhd.extend_from_slice(&[0; 8]);
+        mdhd.extend_from_slice(&timescale.to_be_bytes());
+        mdhd.extend_from_slice(&total.to_be_bytes());
+        mdhd.extend_from_slice(&0x55c4u16.to_be_bytes()); // "und"
+        mdhd.extend_from_slice(&[0; 2]);
+
+        let mut hdlr: Vec<u8> = full_box_header(0, 0);
+        hdlr.extend_from_slice(&[0; 4]);
+        hdlr.extend_from_slice(b"vide");
+        hdlr.extend_from_slice(&[0; 12]);
+        hdlr.extend_from_slice(b"VideoHandler\0");
+
+        let mut vmhd: Vec<u8> = full_box_header(0, 1);
+        vmhd.extend_from_slice(&[0; 8]);
+
+        let mut dref: Vec<u8> = full_box_header(0, 0);
+        dref.extend_from_slice(&1u32.to_be_bytes());
+        // self contained
+        dref.extend_from_slice(&mp4_box(b"url ", &full_box_header(0, 1)));
+
+        let stbl: Vec<u8> = [
+            mp4_box(b"stsd", &self.stsd(seq_obu, seq)),
+            mp4_box(b"stts", &stts(&durations)),
+            mp4_box(b"stss", &self.stss()),
+            mp4_box(b"stsc", &self.stsc()),
+            mp4_box(b"stsz", &self.stsz()),
+            mp4_box(b"stco", &stco(chunk_offset as u32)),
+        ]
+        .concat();
+
+        let minf: Vec<u8> = [
+            mp4_box(b"vmhd", &vmhd),
+            mp4_box(b"dinf", &mp4_box(b"dref", &dref)),
+            mp4_box(b"stbl", &stbl),
+        ]
+        .concat();
+
+        let mdia: Vec<u8> = [
+            mp4_box(b"mdhd", &mdhd),
+            mp4_box(b"hdlr", &hdlr),
+            mp4_box(b"minf", &minf),
+        ]
+        .concat();
+
+        let trak: Vec<u8> = [mp4_box(b"tkhd", &tkhd), mp4_box(b"mdia", &mdia)].concat();
+
+        Ok(mp4_box(
+            b"moov",
+            &[mp4_box(b"mvhd", &mvhd), mp4_box(b"trak", &trak)].concat(),
+        ))
+    }
+
+    /// Sample description with the av01 sample entry and its av1C box
+    fn stsd(&self, seq_obu: &[u8], seq: &SequenceHeader) -> Vec<u8> {
+        let mut entry: Vec<u8> = Vec::new();
+        entry.extend_from_slice(&[0; 6]);
+        entry.extend_from_slice(&1u16.to_be_bytes()); // data_reference_index
+        entry.extend_from_slice(&[0; 16]);
+        entry.extend_from_slice(&(self.width as u16).to_be_bytes());
+        entry.extend_from_slice(&(self.height as u16).to_be_bytes());
+        entry.extend_from_slice(&0x0048_0000u32.to_be_bytes()); // 72 dpi
+        entry.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+        entry.extend_from_slice(&[0; 4]);
+        entry.extend_from_slice(&1u16.to_be_bytes()); // frame_count
+        let mut compressor: [u8; 32] = [0; 32];
+        let name: &[u8] = b"AOM Coding";
+        compressor[0] = name.len() as u8;
+        compressor[1..=name.len()].copy_from_slice(name);
+        entry.extend_from_slice(&compressor);
+        entry.extend_from_slice(&0x0018u16.to_be_bytes()); // depth
+        entry.extend_from_slice(&(-1i16).to_be_bytes());
+        entry.extend_from_slice(&mp4_box(b"av1C", &av1c(seq_obu, seq)));
+
+        let mut stsd: Vec<u8> = full_box_header(0, 0);
+        stsd.extend_from_slice(&1u32.to_be_bytes());
+        stsd.extend_from_slice(&mp4_box(b"av01", &entry));
+        stsd
+    }
+
+    fn stss(&self) -> Vec<u8> {
+        let sync: Vec<u32> = self
+            .samples
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.is_key)
+            .map(|(i, _)| i as u32 + 1)
+            .collect();
+
+        let mut stss: Vec<u8> = full_box_header(0, 0);
+        stss.extend_from_slice(&(sync.len() as u32).to_be_bytes());
+        for index in sync {
+            stss.extend_from_slice(&index.to_be_bytes());
+        }
+        stss
+    }
+
+    /// All samples live in a single chunk
+    fn stsc(&self) -> Vec<u8> {
+        let mut stsc: Vec<u8> = full_box_header(0, 0);
+        stsc.extend_from_slice(&1u32.to_be_bytes());
+        stsc.extend_from_slice(&1u32.to_be_bytes()); // first_chunk
+        stsc.extend_from_slice(&(self.samples.len() as u32).to_be_bytes());
+        stsc.extend_from_slice(&1u32.to_be_bytes()); // sample_description_index
+        stsc
+    }
+
+    fn stsz(&self) -> Vec<u8> {
+        let mut stsz: Vec<u8> = full_box_header(0, 0);
+        stsz.extend_from_slice(&0u32.to_be_bytes());
+        stsz.extend_from_slice(&(self.samples.len() as u32).to_be_bytes());
+        for sample in &self.samples {
+            stsz.extend_from_slice(&sample.size.to_be_bytes());
+        }
+        stsz
+    }
+}
+
+const UNITY_MATRIX: [u8; 36] = [
+    0x00, 0x01, 0x00, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, //
+    0, 0, 0, 0, 0x00, 0x01, 0x00, 0x00, 0, 0, 0, 0, //
+    0, 0, 0, 0, 0, 0, 0, 0, 0x40, 0x00, 0x00, 0x00,
+];
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(payload.len() + 8);
+    out.extend_from_slice(&(payload.len() as u32 + 8).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(payload);
+    out
+}
+
+fn full_box_header(version: u8, flags: u32) -> Vec<u8> {
+    let mut out: Vec<u8> = flags.to_be_bytes().to_vec();
+    out[0] = version;
+    out
+}
+
+/// Run length coded sample durations
+fn stts(durations: &[u32]) -> Vec<u8> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for duration in durations {
+        match runs.last_mut() {
+            Some((count, delta)) if delta == duration => *count += 1,
+            _ => runs.push((1, *duration)),
+        }
+    }
+
+    let mut stts: Vec<u8> = full_box_header(0, 0);
+    stts.extend_from_slice(&(runs.len() as u32).to_be_bytes());
+    for (count, delta) in runs {
+        stts.extend_from_slice(&count.to_be_bytes());
+        stts.extend_from_slice(&delta.to_be_bytes());
+    }
+    stts
+}
+
+fn stco(offset: u32) -> Vec<u8> {
+    let mut stco: Vec<u8> = full_box_header(0, 0);
+    stco.extend_from_slice(&1u32.to_be_bytes());
+    stco.extend_from_slice(&offset.to_be_bytes());
+    stco
+}
+
+/// AV1CodecConfigurationRecord
+fn av1c(seq_obu: &[u8], seq: &SequenceHeader) -> Vec<u8> {
+    let op = seq.operating_points.first();
+    let level: u8 = op.map(|op| op.seq_level_idx).unwrap_or(31);
+    let tier: u8 = op.map(|op| op.seq_tier).unwrap_or(0);
+
+    let mut out: Vec<u8> = vec![
+        // marker, version 1
+        0x81,
+        (seq.seq_profile << 5) | (level & 0x1f),
+        (tier << 7)
+            | ((seq.bit_depth > 8) as u8) << 6
+            | ((seq.bit_depth == 12) as u8) << 5
+            | (seq.mono_chrome as u8) << 4
+            | (seq.subsampling_x as u8) << 3
+            | (seq.subsampling_y as u8) << 2
+            | (seq.chroma_sample_position & 0x03),
+        // no initial_presentation_delay
+        0,
+    ];
+    out.extend_from_slice(seq_obu);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use av_data::packet::Packet;
+    use std::io::Cursor;
+
+    // reduced still picture sequence header: profile 0, level 8, 64x64, 8-bit 4:2:0 with
+    // vertical chroma sample position
+    const SEQUENCE_HEADER: [u8; 6] = [0x1a, 0x15, 0x7f, 0xfc, 0x00, 0x48];
+
+    fn sequence_header_obu() -> Vec<u8> {
+        [&[0x0a, SEQUENCE_HEADER.len() as u8][..], &SEQUENCE_HEADER].concat()
+    }
+
+    fn packet(obus: &[&[u8]], pts: i64, is_key: bool) -> AOMPacket {
+        let mut p: Packet = Packet::with_capacity(64);
+        // every packet starts with a temporal delimiter
+        p.data.extend_from_slice(&[0x12, 0x00]);
+        for obu in obus {
+            p.data.extend_from_slice(obu);
+        }
+        p.t.pts = Some(pts);
+        p.t.duration = Some(1);
+        p.is_key = is_key;
+        AOMPacket::Frame(p)
+    }
+
+    /// Boxes of `data` as (type, payload), 64-bit sizes included
+    fn boxes(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        let mut out: Vec<([u8; 4], &[u8])> = Vec::new();
+        while !data.is_empty() {
+            let mut size: usize = u32::from_be_bytes(data[..4].try_into().unwrap()) as usize;
+            let kind: [u8; 4] = data[4..8].try_into().unwrap();
+            let mut header: usize = 8;
+            if size == 1 {
+                size = u64::from_be_bytes(data[8..16].try_into().unwrap()) as usize;
+                header = 16;
+            }
+            out.push((kind, &data[header..size]));
+            data = &data[size..];
+        }
+        out
+    }
+
+    fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> &'a [u8] {
+        boxes(data)
+            .into_iter()
+            .find(|(k, _)| k == kind)
+            .map(|(_, payload)| payload)
+            .unwrap()
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn av1c_record() {
+        let seq_obu: Vec<u8> = sequence_header_obu();
+        let mut seq: SequenceHeader = SequenceHeader::parse(&SEQUENCE_HEADER).unwrap();
+        assert_eq!((seq.max_frame_width, seq.max_frame_height), (64, 64));
+
+        // level 8, 4:2:0, chroma sample position 1, then the sequence header OBU
+        let record: Vec<u8> = av1c(&seq_obu, &seq);
+        assert_eq!(record[..4], [0x81, 0x08, 0x0d, 0x00]);
+        assert_eq!(record[4..], seq_obu[..]);
+
+        // profile 2, 12-bit 4:2:2 on the high tier
+        seq.seq_profile = 2;
+        seq.bit_depth = 12;
+        seq.subsampling_y = false;
+        seq.chroma_sample_position = 0;
+        seq.operating_points[0].seq_level_idx = 13;
+        seq.operating_points[0].seq_tier = 1;
+        let record: Vec<u8> = av1c(&seq_obu, &seq);
+        assert_eq!(record[..4], [0x81, 0x4d, 0xe8, 0x00]);
+    }
+
+    #[test]
+    fn stts_runs() {
+        let table: Vec<u8> = stts(&[3, 3, 3, 5, 3]);
+        let entries: Vec<u32> = (0..7).map(|i| u32_at(&table, 4 + i * 4)).collect();
+        assert_eq!(entries, [3, 3, 3, 1, 5, 1, 3]);
+    }
+
+    #[test]
+    fn writes_the_box_layout() {
+        let timebase: aom_rational = aom_rational { num: 1, den: 30 };
+        let mut writer = Mp4Av1Writer::new(Cursor::new(Vec::new()), 64, 64, timebase).unwrap();
+
+        let seq_obu: Vec<u8> = sequence_header_obu();
+        writer
+            .write_packet(&packet(&[&seq_obu, &[0x32, 0x01, 0xaa]], 0, true))
+            .unwrap();
+        writer
+            .write_packet(&packet(&[&[0x32, 0x01, 0xbb]], 2, false))
+            .unwrap();
+        // non frame packets are ignored
+        writer
+            .write_packet(&AOMPacket::TwoPassStats(vec![0; 8]))
+            .unwrap();
+        let file: Vec<u8> = writer.finish().unwrap().into_inner();
+
+        let top: Vec<([u8; 4], &[u8])> = boxes(&file);
+        let kinds: Vec<[u8; 4]> = top.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(kinds, [*b"ftyp", *b"mdat", *b"moov"]);
+
+        // the samples without their temporal delimiters
+        let mdat: &[u8] = top[1].1;
+        let first: Vec<u8> = [&seq_obu[..], &[0x32, 0x01, 0xaa]].concat();
+        assert_eq!(mdat, [&first[..], &[0x32, 0x01, 0xbb]].concat());
+
+        let stbl: &[u8] = child(
+            child(child(child(top[2].1, b"trak"), b"mdia"), b"minf"),
+            b"stbl",
+        );
+
+        // sizes of both samples, only the first is a sync sample
+        let stsz: &[u8] = child(stbl, b"stsz");
+        assert_eq!(
+            (u32_at(stsz, 8), u32_at(stsz, 12), u32_at(stsz, 16)),
+            (2, first.len() as u32, 3)
+        );
+        let stss: &[u8] = child(stbl, b"stss");
+        assert_eq!((u32_at(stss, 4), u32_at(stss, 8)), (1, 1));
+
+        // first sample lasts until the second, the last one its own duration
+        let stts: &[u8] = child(stbl, b"stts");
+        assert_eq!(u32_at(stts, 4), 2);
+        assert_eq!((u32_at(stts, 8), u32_at(stts, 12)), (1, 2));
+        assert_eq!((u32_at(stts, 16), u32_at(stts, 20)), (1, 1));
+
+        // the single chunk starts at the mdat payload
+        let stco: &[u8] = child(stbl, b"stco");
+        let offset: usize = u32_at(stco, 8) as usize;
+        assert_eq!(file[offset..offset + first.len()], first[..]);
+
+        // av01 sample entry (78 bytes) followed by av1C
+        let stsd: &[u8] = child(stbl, b"stsd");
+        let av01: &[u8] = child(&stsd[8..], b"av01");
+        assert_eq!(av01[24..28], [0x00, 0x40, 0x00, 0x40]);
+        let seq: SequenceHeader = SequenceHeader::parse(&SEQUENCE_HEADER).unwrap();
+        assert_eq!(child(&av01[78..], b"av1C"), av1c(&seq_obu, &seq));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let timebase: aom_rational = aom_rational { num: 1, den: 0 };
+        assert!(Mp4Av1Writer::new(Cursor::new(Vec::new()), 64, 64, timebase).is_err());
+
+        // no sequence header to build av1C from
+        let timebase: aom_rational = aom_rational { num: 1, den: 30 };
+        let mut writer = Mp4Av1Writer::new(Cursor::new(Vec::new()), 64, 64, timebase).unwrap();
+        writer
+            .write_packet(&packet(&[&[0x32, 0x01, 0xaa]], 0, true))
+            .unwrap();
+        let err: io::Error = writer.finish().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,741 @@
+use super::errors::AomError;
+use crate::aom::{
+    OBU_TYPE, OBU_TYPE_OBU_FRAME, OBU_TYPE_OBU_FRAME_HEADER, OBU_TYPE_OBU_METADATA,
+    OBU_TYPE_OBU_PADDING, OBU_TYPE_OBU_REDUNDANT_FRAME_HEADER, OBU_TYPE_OBU_SEQUENCE_HEADER,
+    OBU_TYPE_OBU_TEMPORAL_DELIMITER, OBU_TYPE_OBU_TILE_GROUP, OBU_TYPE_OBU_TILE_LIST,
+};
+
+/// obu_type field of the OBU header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObuType {
+    SequenceHeader,
+    TemporalDelimiter,
+    FrameHeader,
+    TileGroup,
+    Metadata,
+    Frame,
+    RedundantFrameHeader,
+    TileList,
+    Padding,
+    Reserved(u8),
+}
+
+impl From<OBU_TYPE> for ObuType {
+    fn from(obu_type: OBU_TYPE) -> Self {
+        match obu_type {
+            OBU_TYPE_OBU_SEQUENCE_HEADER => ObuType::SequenceHeader,
+            OBU_TYPE_OBU_TEMPORAL_DELIMITER => ObuType::TemporalDelimiter,
+            OBU_TYPE_OBU_FRAME_HEADER => ObuType::FrameHeader,
+            OBU_TYPE_OBU_TILE_GROUP => ObuType::TileGroup,
+            OBU_TYPE_OBU_METADATA => ObuType::Metadata,
+            OBU_TYPE_OBU_FRAME => ObuType::Frame,
+            OBU_TYPE_OBU_REDUNDANT_FRAME_HEADER => ObuType::RedundantFrameHeader,
+            OBU_TYPE_OBU_TILE_LIST => ObuType::TileList,
+            OBU_TYPE_OBU_PADDING => ObuType::Padding,
+            other => ObuType::Reserved(other),
+        }
+    }
+}
+
+/// obu_extension_header(), present on OBUs of scalable streams
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObuExtension {
+    pub temporal_id: u8,
+    pub spatial_id: u8,
+}
+
+/// One OBU of a low-overhead (Section 5) bitstream, borrowing from the parsed buffer
+#[derive(Debug, Clone, Copy)]
+pub struct Obu<'a> {
+    pub obu_type: ObuType,
+    pub has_size_field: bool,
+    pub extension: Option<ObuExtension>,
+    /// obu_header() bytes, including the extension byte if there is one
+    pub header: &'a [u8],
+    pub payload: &'a [u8],
+    /// The whole OBU (header, size field and payload)
+    pub data: &'a [u8],
+}
+
+impl<'a> Obu<'a> {
+    /// Writes the OBU back with a (possibly different) payload and an explicit size field
+    pub(crate) fn write_with_payload(&self, payload: &[u8], out: &mut Vec<u8>) {
+        // obu_has_size_field is bit 1 of the first header byte
+        out.push(self.header[0] | 0x02);
+        out.extend_from_slice(&self.header[1..]);
+        write_leb128(payload.len() as u64, out);
+        out.extend_from_slice(payload);
+    }
+}
+
+/// Header fields and position of an OBU, as returned by `parse_obus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObuHeader {
+    pub obu_type: ObuType,
+    pub has_size_field: bool,
+    pub extension: Option<ObuExtension>,
+    /// Offset of the OBU in the parsed buffer
+    pub offset: usize,
+    /// Size of the whole OBU (header, size field and payload)
+    pub size: usize,
+    pub payload_size: usize,
+}
+
+impl ObuHeader {
+    pub fn has_extension(&self) -> bool {
+        self.extension.is_some()
+    }
+}
+
+/// Operating point of a sequence header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperatingPoint {
+    pub idc: u16,
+    pub seq_level_idx: u8,
+    pub seq_tier: u8,
+}
+
+/// The fields of sequence_header_obu() that describe the stream
+/// (profile, level, size, color config)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceHeader {
+    pub seq_profile: u8,
+    pub still_picture: bool,
+    pub reduced_still_picture_header: bool,
+    pub operating_points: Vec<OperatingPoint>,
+    pub initial_display_delay_present: bool,
+    pub max_frame_width: u32,
+    pub max_frame_height: u32,
+    pub use_128x128_superblock: bool,
+    pub bit_depth: u8,
+    pub mono_chrome: bool,
+    pub color_primaries: u8,
+    pub transfer_characteristics: u8,
+    pub matrix_coefficients: u8,
+    pub color_range: bool,
+    pub subsampling_x: bool,
+    pub subsampling_y: bool,
+    pub chroma_sample_position: u8,
+    pub film_grain_params_present: bool,
+}
+
+/// MSB first bit reader for the f(n) / uvlc() descriptors of the spec
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    fn f(&mut self, n: u32) -> Result<u32, AomError> {
+        let mut value: u32 = 0;
+        for _ in 0..n {
+            let byte: u8 = *self.data.get(self.pos / 8).ok_or(AomError::CorruptFrame)?;
+            let bit: u8 = (byte >> (7 - self.pos % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn flag(&mut self) -> Result<bool, AomError> {
+        Ok(self.f(1)? == 1)
+    }
+
+    fn uvlc(&mut self) -> Result<u32, AomError> {
+        let mut leading_zeros: u32 = 0;
+        while !self.flag()? {
+            leading_zeros += 1;
+        }
+        if leading_zeros >= 32 {
+            return Ok(u32::MAX);
+        }
+        Ok(self.f(leading_zeros)? + ((1u64 << leading_zeros) - 1) as u32)
+    }
+}
+
+impl SequenceHeader {
+    /// Parses the payload of a sequence header OBU (Section 5.5 of the spec)
+    pub fn parse(payload: &[u8]) -> Result<Self, AomError> {
+        let mut r: BitReader<'_> = BitReader::new(payload);
+
+        let seq_profile: u8 = r.f(3)? as u8;
+        let still_picture: bool = r.flag()?;
+        let reduced_still_picture_header: bool = r.flag()?;
+
+        let mut operating_points: Vec<OperatingPoint> = Vec::new();
+        let mut initial_display_delay_present: bool = false;
+        if reduced_still_picture_header {
+            operating_points.push(OperatingPoint {
+                idc: 0,
+                seq_level_idx: r.f(5)? as u8,
+                seq_tier: 0,
+            });
+        } else {
+            let mut decoder_model_info_present: bool = false;
+            let mut buffer_delay_length: u32 = 0;
+            if r.flag()? {
+                // timing_info()
+                r.f(32)?;
+                r.f(32)?;
+                if r.flag()? {
+                    r.uvlc()?;
+                }
+                decoder_model_info_present = r.flag()?;
+                if decoder_model_info_present {
+                    buffer_delay_length = r.f(5)? + 1;
+                    r.f(32)?;
+                    r.f(5)?;
+                    r.f(5)?;
+                }
+            }
+            initial_display_delay_present = r.flag()?;
+
+            let count: u32 = r.f(5)? + 1;
+            for _ in 0..count {
+                let idc: u16 = r.f(12)? as u16;
+                let seq_level_idx: u8 = r.f(5)? as u8;
+                let seq_tier: u8 = if seq_level_idx > 7 { r.f(1)? as u8 } else { 0 };
+                if decoder_model_info_present && r.flag()? {
+                    // operating_parameters_info()
+                    r.f(buffer_delay_length)?;
+                    r.f(buffer_delay_length)?;
+                    r.f(1)?;
+                }
+                if initial_display_delay_present && r.flag()? {
+                    r.f(4)?;
+                }
+                operating_points.push(OperatingPoint {
+                    idc,
+                    seq_level_idx,
+                    seq_tier,
+                });
+            }
+        }
+
+        let width_bits: u32 = r.f(4)? + 1;
+        let height_bits: u32 = r.f(4)? + 1;
+        let max_frame_width: u32 = r.f(width_bits)? + 1;
+        let max_frame_height: u32 = r.f(height_bits)? + 1;
+
+        if !reduced_still_picture_header && r.flag()? {
+            // delta_frame_id_length_minus_2, additional_frame_id_length_minus_1
+            r.f(4)?;
+            r.f(3)?;
+        }
+
+        let use_128x128_superblock: bool = r.flag()?;
+        // enable_filter_intra, enable_intra_edge_filter
+        r.f(2)?;
+
+        if !reduced_still_picture_header {
+            // enable_interintra_compound, enable_masked_compound,
+            // enable_warped_motion, enable_dual_filter
+            r.f(4)?;
+            let enable_order_hint: bool = r.flag()?;
+            if enable_order_hint {
+                // enable_jnt_comp, enable_ref_frame_mvs
+                r.f(2)?;
+            }
+            let seq_force_screen_content_tools: u32 = if r.flag()? { 2 } else { r.f(1)? };
+            if seq_force_screen_content_tools > 0 && !r.flag()? {
+                // seq_force_integer_mv
+                r.f(1)?;
+            }
+            if enable_order_hint {
+                r.f(3)?;
+            }
+        }
+
+        // enable_superres, enable_cdef, enable_restoration
+        r.f(3)?;
+
+        // color_config()
+        let high_bitdepth: bool = r.flag()?;
+        let bit_depth: u8 = if seq_profile == 2 && high_bitdepth {
+            if r.flag()? {
+                12
+            } else {
+                10
+            }
+        } else if high_bitdepth {
+            10
+        } else {
+            8
+        };
+        let mono_chrome: bool = if seq_profile == 1 { false } else { r.flag()? };
+
+        // CP/TC/MC_UNSPECIFIED
+        let (mut color_primaries, mut transfer_characteristics, mut matrix_coefficients) =
+            (2, 2, 2);
+        if r.flag()? {
+            color_primaries = r.f(8)? as u8;
+            transfer_characteristics = r.f(8)? as u8;
+            matrix_coefficients = r.f(8)? as u8;
+        }
+
+        let color_range: bool;
+        let (mut subsampling_x, mut subsampling_y) = (true, true);
+        let mut chroma_sample_position: u8 = 0;
+        if mono_chrome {
+            color_range = r.flag()?;
+        } else {
+            // BT.709 primaries, sRGB transfer and identity matrix is 4:4:4 full range RGB
+            if color_primaries == 1 && transfer_characteristics == 13 && matrix_coefficients == 0 {
+                color_range = true;
+                subsampling_x = false;
+                subsampling_y = false;
+            } else {
+                color_range = r.flag()?;
+                match seq_profile {
+                    0 => {}
+                    1 => {
+                        subsampling_x = false;
+                        subsampling_y = false;
+                    }
+                    _ => {
+                        if bit_depth == 12 {
+                            subsampling_x = r.flag()?;
+                            subsampling_y = if subsampling_x { r.flag()? } else { false };
+                        } else {
+                            subsampling_y = false;
+                        }
+                    }
+                }
+                if subsampling_x && subsampling_y {
+                    chroma_sample_position = r.f(2)? as u8;
+                }
+            }
+            // separate_uv_delta_q
+            r.f(1)?;
+        }
+
+        let film_grain_params_present: bool = r.flag()?;
+
+        Ok(SequenceHeader {
+            seq_profile,
+            still_picture,
+            reduced_still_picture_header,
+            operating_points,
+            initial_display_delay_present,
+            max_frame_width,
+            max_frame_height,
+            use_128x128_superblock,
+            bit_depth,
+            mono_chrome,
+            color_primaries,
+            transfer_characteristics,
+            matrix_coefficients,
+            color_range,
+            subsampling_x,
+            subsampling_y,
+            chroma_sample_position,
+            film_grain_params_present,
+        })
+    }
+}
+
+/// Iterates the OBUs of a frame packet (sequence header, frame header, tile group,
+/// metadata, ...). Iteration stops at the first OBU that can't be parsed.
+///
+/// ```rust,no_run
+/// # use aom_rs::core::{encoder::AOMPacket, obu::iter_obus};
+/// # fn example(pkt: AOMPacket) {
+/// # let AOMPacket::Frame(packet) = pkt else { return };
+/// for obu in iter_obus(&packet.data) {
+///     println!("{:?} {} bytes", obu.obu_type, obu.payload.len());
+/// }
+/// # }
+/// ```
+pub fn iter_obus(data: &[u8]) -> impl Iterator<Item = Obu<'_>> {
+    let mut rest: &[u8] = data;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        match read_obu(rest) {
+            Ok((obu, remaining)) => {
+                rest = remaining;
+                Some(obu)
+            }
+            Err(_) => {
+                rest = &[];
+                None
+            }
+        }
+    })
+}
+
+/// Lists the OBUs of a low-overhead (Section 5) bitstream with their boundaries, e.g.
+/// the data of packets from `AV1Encoder::get_packet`. Pure Rust, libaom is not involved.
+/// Parsing stops at the first malformed OBU, the list then ends with the OBU before it.
+/// Annex B output (save_as_annexb) is length delimited rather than made of bare OBUs
+/// and is not understood.
+///
+/// ```rust,no_run
+/// # use aom_rs::core::{encoder::AOMPacket, obu::parse_obus};
+/// # fn example(pkt: AOMPacket) {
+/// # let AOMPacket::Frame(packet) = pkt else { return };
+/// for obu in parse_obus(&packet.data) {
+///     println!("{:?} at {}, {} bytes", obu.obu_type, obu.offset, obu.size);
+/// }
+/// # }
+/// ```
+pub fn parse_obus(data: &[u8]) -> Vec<ObuHeader> {
+    let mut offset: usize = 0;
+
+    iter_obus(data)
+        .map(|obu| {
+            let header: ObuHeader = ObuHeader {
+                obu_type: obu.obu_type,
+                has_size_field: obu.has_size_field,
+                extension: obu.extension,
+                offset,
+                size: obu.data.len(),
+                payload_size: obu.payload.len(),
+            };
+            offset += obu.data.len();
+            header
+        })
+        .collect()
+}
+
+/// Rewrites the temporal/spatial ids of a frame packet, adding the extension header to
+/// the OBUs that don't have one. Sequence headers, temporal delimiters and padding are
+/// layer independent and are copied as is. Prefer `AV1Encoder::set_svc_layer_id`, which
+/// gets the encoder to signal the ids itself; this is for packets produced elsewhere.
+pub fn set_layer_ids(data: &[u8], ids: ObuExtension) -> Result<Vec<u8>, AomError> {
+    let mut out: Vec<u8> = Vec::with_capacity(data.len() + 8);
+
+    for obu in split_obus(data)? {
+        match obu.obu_type {
+            ObuType::SequenceHeader | ObuType::TemporalDelimiter | ObuType::Padding => {
+                out.extend_from_slice(obu.data);
+            }
+            _ => {
+                // obu_extension_flag and obu_has_size_field
+                out.push(obu.header[0] | 0x04 | 0x02);
+                out.push((ids.temporal_id & 0x07) << 5 | (ids.spatial_id & 0x03) << 3);
+                write_leb128(obu.payload.len() as u64, &mut out);
+                out.extend_from_slice(obu.payload);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Reads an unsigned LEB128 value. Returns the value and the number of bytes it used.
+pub(crate) fn read_leb128(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value: u64 = 0;
+
+    // AV1 limits leb128() to 8 bytes
+    for (i, byte) in data.iter().take(8).enumerate() {
+        value |= ((byte & 0x7f) as u64) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+
+    None
+}
+
+/// Appends `value` as unsigned LEB128
+pub(crate) fn write_leb128(mut value: u64, out: &mut Vec<u8>) {
+    loop {
+        let byte: u8 = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Parses the OBU at the start of `data`, returns it and the bytes after it
+pub(crate) fn read_obu(data: &[u8]) -> Result<(Obu<'_>, &[u8]), AomError> {
+    let first: u8 = *data.first().ok_or(AomError::CorruptFrame)?;
+    // obu_forbidden_bit
+    if first & 0x80 != 0 {
+        return Err(AomError::CorruptFrame);
+    }
+    let has_extension: bool = first & 0x04 != 0;
+    let has_size_field: bool = first & 0x02 != 0;
+
+    let header_len: usize = if has_extension { 2 } else { 1 };
+    if data.len() < header_len {
+        return Err(AomError::CorruptFrame);
+    }
+    let extension: Option<ObuExtension> = has_extension.then(|| ObuExtension {
+        temporal_id: data[1] >> 5,
+        spatial_id: (data[1] >> 3) & 0x03,
+    });
+
+    let (payload_len, size_len) = if has_size_field {
+        let (size, len) = read_leb128(&data[header_len..]).ok_or(AomError::CorruptFrame)?;
+        (
+            usize::try_from(size).map_err(|_| AomError::CorruptFrame)?,
+            len,
+        )
+    } else {
+        // Without a size field the OBU runs until the end of the buffer
+        (data.len() - header_len, 0)
+    };
+
+    let start: usize = header_len + size_len;
+    let end: usize = start
+        .checked_add(payload_len)
+        .filter(|end| *end <= data.len())
+        .ok_or(AomError::CorruptFrame)?;
+
+    let obu: Obu<'_> = Obu {
+        obu_type: ObuType::from((first >> 3) & 0x0f),
+        has_size_field,
+        extension,
+        header: &data[..header_len],
+        payload: &data[start..end],
+        data: &data[..end],
+    };
+
+    Ok((obu, &data[end..]))
+}
+
+/// Splits a low-overhead (Section 5) bitstream into OBUs, failing on malformed data
+pub(crate) fn split_obus(mut data: &[u8]) -> Result<Vec<Obu<'_>>, AomError> {
+    let mut obus: Vec<Obu<'_>> = Vec::new();
+
+    while !data.is_empty() {
+        let (obu, rest) = read_obu(data)?;
+        obus.push(obu);
+        data = rest;
+    }
+
+    Ok(obus)
+}
+
+/// Splits a low-overhead bitstream into temporal units, each starting at a temporal
+/// delimiter OBU. Data before the first delimiter forms a unit of its own.
+pub(crate) fn temporal_units(data: &[u8]) -> Result<Vec<&[u8]>, AomError> {
+    let mut units: Vec<&[u8]> = Vec::new();
+    let mut unit_start: usize = 0;
+    let mut pos: usize = 0;
+
+    while pos < data.len() {
+        let (obu, _) = read_obu(&data[pos..])?;
+        // The low-overhead format requires a size field on every OBU
+        if !obu.has_size_field {
+            return Err(AomError::CorruptFrame);
+        }
+        if obu.obu_type == ObuType::TemporalDelimiter && pos > unit_start {
+            units.push(&data[unit_start..pos]);
+            unit_start = pos;
+        }
+        pos += obu.data.len();
+    }
+    if pos > unit_start {
+        units.push(&data[unit_start..pos]);
+    }
+
+    Ok(units)
+}
+
+/// Splits an Annex B (length delimited) bitstream into temporal units. Every unit keeps
+/// its temporal_unit_size prefix, which is what libaom expects in Annex B mode. The
+/// frame unit and OBU lengths inside each unit are checked for consistency.
+pub(crate) fn annexb_temporal_units(data: &[u8]) -> Result<Vec<&[u8]>, AomError> {
+    // Reads a leb128 size and the `size` bytes after it, returns (whole, contents)
+    let sized = |data: &[u8]| -> Result<(usize, usize), AomError> {
+        let (size, len) = read_leb128(data).ok_or(AomError::CorruptFrame)?;
+        let size: usize = usize::try_from(size).map_err(|_| AomError::CorruptFrame)?;
+        let end: usize = len
+            .checked_add(size)
+            .filter(|end| *end <= data.len())
+            .ok_or(AomError::CorruptFrame)?;
+        Ok((end, len))
+    };
+    // Checks that `data` is completely covered by size-prefixed children
+    let covered = |mut data: &[u8], check: &dyn Fn(&[u8]) -> Result<(), AomError>| {
+        while !data.is_empty() {
+            let (end, len) = sized(data)?;
+            check(&data[len..end])?;
+            data = &data[end..];
+        }
+        Ok::<(), AomError>(())
+    };
+
+    let mut units: Vec<&[u8]> = Vec::new();
+    let mut rest: &[u8] = data;
+
+    while !rest.is_empty() {
+        let (end, len) = sized(rest)?;
+        covered(&rest[len..end], &|frame_unit: &[u8]| {
+            covered(frame_unit, &|obu: &[u8]| {
+                // obu_length includes the header, the OBU itself must be well formed
+                read_obu(obu).map(|_| ())
+            })
+        })?;
+
+        units.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    Ok(units)
+}
+
+/// Stitches independently encoded segments (each starting with a keyframe, e.g. GOPs
+/// encoded in parallel with the same configuration) into one stream.
+///
+/// The sequence header of the first segment is kept. Later segments repeat it in
+/// front of their keyframes, those copies are dropped. A later sequence header that
+/// differs starts a new coded video sequence and is kept, since the frames after it
+/// depend on it. Segments have to be low-overhead (Section 5) OBU streams.
+pub fn concat_segments(segments: &[Vec<u8>]) -> Result<Vec<u8>, AomError> {
+    let mut out: Vec<u8> = Vec::with_capacity(segments.iter().map(|s| s.len()).sum());
+    let mut sequence_header: Option<&[u8]> = None;
+
+    for (index, segment) in segments.iter().enumerate() {
+        for obu in split_obus(segment)? {
+            if obu.obu_type == ObuType::SequenceHeader {
+                match sequence_header {
+                    Some(current) if index > 0 && current == obu.payload => continue,
+                    _ => sequence_header = Some(obu.payload),
+                }
+            }
+            out.extend_from_slice(obu.data);
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // temporal delimiter, a 3 byte sequence header and a frame OBU with temporal id 2
+    // and spatial id 1 in its extension header
+    #[rustfmt::skip]
+    const STREAM: [u8; 11] = [
+        0x12, 0x00,
+        0x0a, 0x03, 1, 2, 3,
+        0x36, 0x48, 0x01, 0xff,
+    ];
+
+    #[test]
+    fn leb128_round_trip() {
+        for value in [0, 1, 127, 128, 300, 16383, 16384, u32::MAX as u64] {
+            let mut out: Vec<u8> = Vec::new();
+            write_leb128(value, &mut out);
+            assert_eq!(read_leb128(&out), Some((value, out.len())));
+        }
+
+        let mut out: Vec<u8> = Vec::new();
+        write_leb128(300, &mut out);
+        assert_eq!(out, [0xac, 0x02]);
+        // trailing bytes are not part of the value
+        assert_eq!(read_leb128(&[0xac, 0x02, 0xff]), Some((300, 2)));
+    }
+
+    #[test]
+    fn leb128_rejects_truncated_values() {
+        assert_eq!(read_leb128(&[]), None);
+        assert_eq!(read_leb128(&[0x80]), None);
+        assert_eq!(read_leb128(&[0xff, 0xff]), None);
+        // at most 8 bytes, even if a 9th would end the value
+        assert_eq!(read_leb128(&[0x80; 9]), None);
+        assert_eq!(
+            read_leb128(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00]),
+            None
+        );
+    }
+
+    #[test]
+    fn read_obu_with_extension_header() {
+        let (obu, rest) = read_obu(&STREAM[7..]).unwrap();
+        assert_eq!(obu.obu_type, ObuType::Frame);
+        assert!(obu.has_size_field);
+        assert_eq!(
+            obu.extension,
+            Some(ObuExtension {
+                temporal_id: 2,
+                spatial_id: 1,
+            })
+        );
+        assert_eq!(obu.header, [0x36, 0x48]);
+        assert_eq!(obu.payload, [0xff]);
+        assert_eq!(obu.data, &STREAM[7..]);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn read_obu_without_size_field() {
+        // tile group OBU running until the end of the buffer
+        let data: [u8; 4] = [0x20, 1, 2, 3];
+        let (obu, rest) = read_obu(&data).unwrap();
+        assert_eq!(obu.obu_type, ObuType::TileGroup);
+        assert!(!obu.has_size_field);
+        assert_eq!(obu.extension, None);
+        assert_eq!(obu.payload, [1, 2, 3]);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn read_obu_rejects_malformed_headers() {
+        // obu_forbidden_bit set
+        assert!(matches!(
+            read_obu(&[0x92, 0x00]),
+            Err(AomError::CorruptFrame)
+        ));
+        // size field cut short
+        assert!(matches!(
+            read_obu(&[0x32, 0x80]),
+            Err(AomError::CorruptFrame)
+        ));
+        // extension flag without the extension byte
+        assert!(matches!(read_obu(&[0x36]), Err(AomError::CorruptFrame)));
+        // payload larger than the data left
+        assert!(matches!(
+            read_obu(&[0x32, 0x05, 1, 2]),
+            Err(AomError::CorruptFrame)
+        ));
+        assert!(matches!(read_obu(&[]), Err(AomError::CorruptFrame)));
+    }
+
+    #[test]
+    fn parse_obus_offsets_and_sizes() {
+        let headers: Vec<ObuHeader> = parse_obus(&STREAM);
+        let types: Vec<ObuType> = headers.iter().map(|obu| obu.obu_type).collect();
+        assert_eq!(
+            types,
+            [
+                ObuType::TemporalDelimiter,
+                ObuType::SequenceHeader,
+                ObuType::Frame
+            ]
+        );
+
+        let layout: Vec<(usize, usize, usize)> = headers
+            .iter()
+            .map(|obu| (obu.offset, obu.size, obu.payload_size))
+            .collect();
+        assert_eq!(layout, [(0, 2, 0), (2, 5, 3), (7, 4, 1)]);
+        assert!(headers.iter().all(|obu| obu.has_size_field));
+        assert!(!headers[1].has_extension());
+        assert!(headers[2].has_extension());
+    }
+
+    #[test]
+    fn parsing_stops_at_the_first_malformed_obu() {
+        let mut data: Vec<u8> = STREAM[..7].to_vec();
+        data.extend_from_slice(&[0x92, 0x00]);
+        data.extend_from_slice(&STREAM[7..]);
+
+        assert_eq!(parse_obus(&data).len(), 2);
+        assert_eq!(iter_obus(&data).count(), 2);
+        assert!(split_obus(&data).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use super::errors::AomError;
+use av_data::frame::{Frame, FrameBufferConv, MediaKind, VideoInfo};
+
+/// Objective quality of a decoded frame against its source, see `compare_frames`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityMetrics {
+    /// PSNR of the luma plane in dB
+    pub psnr_y: f64,
+    /// PSNR of the U plane in dB, 0 for monochrome frames
+    pub psnr_u: f64,
+    /// PSNR of the V plane in dB, 0 for monochrome frames
+    pub psnr_v: f64,
+    /// SSIM in 0..=1, weighted 0.8 luma / 0.1 U / 0.1 V like libaom's internal metric
+    pub ssim: f64,
+}
+
+// libaom reports identical planes as 100 dB instead of infinity
+const MAX_PSNR: f64 = 100.0;
+// SSIM is computed on 8x8 windows every 4 samples, as libaom's aom_ssim2 does
+const SSIM_WINDOW: usize = 8;
+const SSIM_STEP: usize = 4;
+
+/// Compares a decoded frame to the frame it was encoded from.
+///
+/// Both frames must be planar YUV with the same size, subsampling and bit depth
+/// (8-bit samples are read as u8, deeper ones as u16). Strides may differ. Planes are
+/// compared over their visible area only, chroma dimensions follow the subsampling
+/// (rounded up). Returns `AomError::InvalidParam` when the frames don't match up.
+pub fn compare_frames(reference: &Frame, decoded: &Frame) -> Result<QualityMetrics, AomError> {
+    let (ref_info, dec_info) = match (&reference.kind, &decoded.kind) {
+        (MediaKind::Video(r), MediaKind::Video(d)) => (r, d),
+        _ => return Err(AomError::InvalidParam),
+    };
+    if ref_info.width != dec_info.width || ref_info.height != dec_info.height {
+        return Err(AomError::InvalidParam);
+    }
+
+    let planes: usize = ref_info.format.get_num_comp().min(3);
+    if planes != dec_info.format.get_num_comp().min(3) {
+        return Err(AomError::InvalidParam);
+    }
+
+    let mut psnr: [f64; 3] = [0.0; 3];
+    let mut ssim: [f64; 3] = [0.0; 3];
+    for idx in 0..planes {
+        let a: Plane<'_> = Plane::new(reference, ref_info, idx)?;
+        let b: Plane<'_> = Plane::new(decoded, dec_info, idx)?;
+        if (a.width, a.height, a.depth) != (b.width, b.height, b.depth) {
+            return Err(AomError::InvalidParam);
+        }
+
+        psnr[idx] = plane_psnr(&a, &b);
+        ssim[idx] = plane_ssim(&a, &b);
+    }
+
+    let ssim: f64 = if planes == 1 {
+        ssim[0]
+    } else {
+        0.8 * ssim[0] + 0.1 * (ssim[1] + ssim[2])
+    };
+
+    Ok(QualityMetrics {
+        psnr_y: psnr[0],
+        psnr_u: psnr[1],
+        psnr_v: psnr[2],
+        ssim,
+    })
+}
+
+enum Samples<'a> {
+    Low(&'a [u8]),
+    High(&'a [u16]),
+}
+
+// One plane of a frame with its visible dimensions, stride in samples
+struct Plane<'a> {
+    samples: Samples<'a>,
+    stride: usize,
+    width: usize,
+    height: usize,
+    depth: u8,
+}
+
+impl<'a> Plane<'a> {
+    fn new(frame: &'a Frame, info: &VideoInfo, idx: usize) -> Result<Self, AomError> {
+        let chroma = info
+            .format
+            .get_chromaton(idx)
+            .ok_or(AomError::InvalidParam)?;
+        if chroma.is_packed() {
+            return Err(AomError::InvalidParam);
+        }
+
+        let (h_ss, v_ss) = chroma.get_subsampling();
+        let width: usize = (info.width + (1 << h_ss) - 1) >> h_ss;
+        let height: usize = (info.height + (1 << v_ss) - 1) >> v_ss;
+        let depth: u8 = chroma.get_depth();
+        let linesize: usize = frame
+            .buf
+            .linesize(idx)
+            .map_err(|_| AomError::InvalidParam)?;
+
+        let (samples, stride): (Samples<'a>, usize) = if depth > 8 {
+            let data: &[u16] = frame
+                .buf
+                .as_slice(idx)
+                .map_err(|_| AomError::InvalidParam)?;
+            (Samples::High(data), linesize / 2)
+        } else {
+            let data: &[u8] = frame
+                .buf
+                .as_slice(idx)
+                .map_err(|_| AomError::InvalidParam)?;
+            (Samples::Low(data), linesize)
+        };
+
+        let len: usize = match samples {
+            Samples::Low(data) => data.len(),
+            Samples::High(data) => data.len(),
+        };
+        if width == 0 || height == 0 || stride < width || len < stride * (height - 1) + width {
+            return Err(AomError::InvalidParam);
+        }
+
+        Ok(Plane {
+            samples,
+            stride,
+            width,
+            height,
+            depth,
+        })
+    }
+
+    fn get(&self, x: usize, y: usize) -> f64 {
+        match self.samples {
+            Samples::Low(data) => data[y * self.stride + x] as f64,
+            Samples::High(data) => data[y * self.stride + x] as f64,
+        }
+    }
+
+    fn max_value(&self) -> f64 {
+        ((1u32 << self.depth) - 1) as f64
+    }
+}
+
+fn plane_psnr(a: &Plane<'_>, b: &Plane<'_>) -> f64 {
+    let mut sse: f64 = 0.0;
+    for y in 0..a.height {
+        for x in 0..a.width {
+            let diff: f64 = a.get(x, y) - b.get(x, y);
+            sse += diff * diff;
+        }
+    }
+
+    if sse == 0.0 {
+        return MAX_PSNR;
+    }
+    let mse: f64 = sse / (a.width * a.height) as f64;
+    let max: f64 = a.max_value();
+    (10.0 * (max * max / mse).log10()).min(MAX_PSNR)
+}
+
+// Mean SSIM over 8x8 windows; planes smaller than a window are one window
+fn plane_ssim(a: &Plane<'_>, b: &Plane<'_>) -> f64 {
+    let max: f64 = a.max_value();
+    let c1: f64 = (0.01 * max) * (0.01 * max);
+    let c2: f64 = (0.03 * max) * (0.03 * max);
+
+    let win_w: usize = SSIM_WINDOW.min(a.width);
+    let win_h: usize = SSIM_WINDOW.min(a.height);
+    let mut total: f64 = 0.0;
+    let mut windows: usize = 0;
+
+    let mut y: usize = 0;
+    while y + win_h <= a.height {
+        let mut x: usize = 0;
+        while x + win_w <= a.width {
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) =
+                (0.0, 0.0, 0.0, 0.0, 0.0);
+            for wy in y..y + win_h {
+                for wx in x..x + win_w {
+                    let (va, vb) = (a.get(wx, wy), b.get(wx, wy));
+                    sum_a += va;
+                    sum_b += vb;
+                    sum_aa += va * va;
+                    sum_bb += vb * vb;
+                    sum_ab += va * vb;
+                }
+            }
+
+            let n: f64 = (win_w * win_h) as f64;
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a: f64 = sum_aa / n - mean_a * mean_a;
+            let var_b: f64 = sum_bb / n - mean_b * mean_b;
+            let cov: f64 = sum_ab / n - mean_a * mean_b;
+
+            total += ((2.0 * mean_a * mean_b + c1) * (2.0 * cov + c2))
+                / ((mean_a * mean_a + mean_b * mean_b + c1) * (var_a + var_b + c2));
+            windows += 1;
+            x += SSIM_STEP;
+        }
+        y += SSIM_STEP;
+    }
+
+    total / windows as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use av_data::{
+        frame::{FrameType, VideoInfo},
+        pixel::formats::YUV420,
+    };
+    use std::sync::Arc;
+
+    // I420 frame with the given luma and flat chroma
+    fn frame(width: usize, height: usize, luma: impl Fn(usize, usize) -> u8) -> Frame {
+        let info: VideoInfo =
+            VideoInfo::new(width, height, false, FrameType::OTHER, Arc::new(*YUV420));
+        let mut frame: Frame = Frame::new_default_frame(info, None);
+
+        let stride: usize = frame.buf.linesize(0).unwrap();
+        let data: &mut [u8] = frame.buf.as_mut_slice(0).unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                data[y * stride + x] = luma(x, y);
+            }
+        }
+        for plane in 1..3 {
+            let chroma: &mut [u8] = frame.buf.as_mut_slice(plane).unwrap();
+            chroma.fill(128);
+        }
+
+        frame
+    }
+
+    fn gradient(x: usize, y: usize) -> u8 {
+        (x * 3 + y * 5) as u8
+    }
+
+    #[test]
+    fn identical_frames() {
+        let a: Frame = frame(32, 24, gradient);
+        let b: Frame = frame(32, 24, gradient);
+
+        let metrics: QualityMetrics = compare_frames(&a, &b).unwrap();
+        assert_eq!(
+            (metrics.psnr_y, metrics.psnr_u, metrics.psnr_v),
+            (MAX_PSNR, MAX_PSNR, MAX_PSNR)
+        );
+        assert!((metrics.ssim - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn psnr_of_a_known_mse() {
+        let a: Frame = frame(32, 24, gradient);
+        // every other column is off by 4, a luma MSE of 8
+        let b: Frame = frame(32, 24, |x, y| {
+            gradient(x, y) + if x % 2 == 0 { 4 } else { 0 }
+        });
+
+        let metrics: QualityMetrics = compare_frames(&a, &b).unwrap();
+        let expected: f64 = 10.0 * (255.0 * 255.0 / 8.0f64).log10();
+        assert!((metrics.psnr_y - expected).abs() < 1e-9);
+        assert_eq!((metrics.psnr_u, metrics.psnr_v), (MAX_PSNR, MAX_PSNR));
+        assert!(metrics.ssim < 1.0);
+
+        // the metrics don't depend on which frame is the reference
+        assert_eq!(compare_frames(&b, &a).unwrap(), metrics);
+    }
+
+    #[test]
+    fn rejects_mismatched_sizes() {
+        let a: Frame = frame(32, 24, gradient);
+        let b: Frame = frame(32, 16, gradient);
+        let c: Frame = frame(16, 24, gradient);
+
+        assert!(matches!(
+            compare_frames(&a, &b),
+            Err(AomError::InvalidParam)
+        ));
+        assert!(matches!(
+            compare_frames(&a, &c),
+            Err(AomError::InvalidParam)
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+use super::{encoder::AV1Encoder, errors::AomError};
+use crate::aom::{aom_enc_frame_flags_t, AOM_EFLAG_FORCE_KF};
+use av_data::frame::{Frame, FrameBufferConv, MediaKind};
+
+// Luma is bucketed into 64 bins after scaling to 8 bits
+const HISTOGRAM_BINS: usize = 64;
+
+/// Forces keyframes at scene cuts, independently of libaom's own keyframe placement.
+///
+/// Each frame's luma histogram is compared to the one of the previous frame; the score
+/// is the share of samples that moved to another bin, from 0 (same distribution) to 1
+/// (disjoint). A frame scoring above the threshold starts a new scene and is encoded
+/// with AOM_EFLAG_FORCE_KF. libaom may still place keyframes of its own (kf_max_dist,
+/// its internal detection); set kf_mode to AOM_KF_DISABLED to only get these ones.
+///
+/// # Example
+/// ```rust,no_run
+/// # use aom_rs::core::{encoder::AV1Encoder, scenecut::SceneCutDetector};
+/// # use av_data::frame::Frame;
+/// # fn example(mut encoder: AV1Encoder, frames: Vec<Frame>) -> Result<(), aom_rs::core::AomError> {
+/// let mut detector = SceneCutDetector::new(0.4)?.min_distance(12);
+/// for frame in frames {
+///     detector.encode(&mut encoder, &frame)?;
+///     while let Some(pkt) = encoder.get_packet() { /* ... */ }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct SceneCutDetector {
+    threshold: f64,
+    min_distance: u64,
+    prev: Option<[u64; HISTOGRAM_BINS]>,
+    since_cut: u64,
+    last_score: Option<f64>,
+}
+
+impl SceneCutDetector {
+    /// `threshold` must be in 0..=1, otherwise `AomError::InvalidParam` is returned.
+    /// Around 0.3 to 0.5 catches hard cuts without firing on fast motion.
+    pub fn new(threshold: f64) -> Result<Self, AomError> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(AomError::InvalidParam);
+        }
+
+        Ok(SceneCutDetector {
+            threshold,
+            min_distance: 0,
+            prev: None,
+            since_cut: 0,
+            last_score: None,
+        })
+    }
+
+    /// Ignores cuts less than `frames` frames after the previous one, so flashes and
+    /// strobing don't produce a run of keyframes.
+    pub fn min_distance(mut self, frames: u32) -> Self {
+        self.min_distance = frames as u64;
+        self
+    }
+
+    /// Compares `frame` with the previous frame and remembers it for the next call.
+    /// The first frame is never a cut, libaom codes it as a keyframe anyway.
+    ///
+    /// Needs a planar frame with an 8 to 16 bit luma plane, `AomError::UnsupFeature`
+    /// otherwise.
+    pub fn is_scene_cut(&mut self, frame: &Frame) -> Result<bool, AomError> {
+        let hist: [u64; HISTOGRAM_BINS] = luma_histogram(frame)?;
+        self.since_cut += 1;
+
+        let prev = match self.prev.replace(hist) {
+            Some(prev) => prev,
+            None => {
+                self.since_cut = 0;
+                return Ok(false);
+            }
+        };
+
+        let total: u64 = hist.iter().sum();
+        let moved: u64 = prev
+            .iter()
+            .zip(hist.iter())
+            .map(|(a, b)| a.abs_diff(*b))
+            .sum();
+        let score: f64 = if total == 0 {
+            0.0
+        } else {
+            moved as f64 / (2 * total) as f64
+        };
+        self.last_score = Some(score);
+
+        if score > self.threshold && self.since_cut >= self.min_distance {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(score, pts = frame.t.pts, "scene cut");
+            self.since_cut = 0;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Score of the last compared frame pair
+    pub fn last_score(&self) -> Option<f64> {
+        self.last_score
+    }
+
+    /// Forgets the previous frame, e.g. after seeking in the source
+    pub fn reset(&mut self) {
+        self.prev = None;
+        self.since_cut = 0;
+        self.last_score = None;
+    }
+
+    /// Runs the detection on `frame` and encodes it with `encoder`, forcing a keyframe
+    /// at a scene cut. Returns whether the frame was a cut. The duration is the one of
+    /// `aom_codec_encode` (the frame's TimeInfo, one tick otherwise).
+    pub fn encode(&mut self, encoder: &mut AV1Encoder, frame: &Frame) -> Result<bool, AomError> {
+        let cut: bool = self.is_scene_cut(frame)?;
+        let flags: aom_enc_frame_flags_t = if cut {
+            AOM_EFLAG_FORCE_KF as aom_enc_frame_flags_t
+        } else {
+            0
+        };
+
+        encoder.encode_with(frame, frame.t.duration.unwrap_or(1), flags)?;
+        Ok(cut)
+    }
+}
+
+fn luma_histogram(frame: &Frame) -> Result<[u64; HISTOGRAM_BINS], AomError> {
+    let info = match &frame.kind {
+        MediaKind::Video(info) => info,
+        _ => return Err(AomError::InvalidParam),
+    };
+    let luma = info.format.get_chromaton(0).ok_or(AomError::UnsupFeature)?;
+    let depth: u8 = luma.get_depth();
+    if luma.is_packed() || !(8..=16).contains(&depth) {
+        return Err(AomError::UnsupFeature);
+    }
+
+    let linesize: usize = frame.buf.linesize(0).map_err(|_| AomError::InvalidParam)?;
+    // in samples
+    let stride: usize = if depth > 8 { linesize / 2 } else { linesize };
+    if stride == 0 {
+        return Err(AomError::InvalidParam);
+    }
+    // bins are 4 wide at 8 bits
+    let shift: u8 = depth - 8 + 2;
+    let mut hist: [u64; HISTOGRAM_BINS] = [0; HISTOGRAM_BINS];
+
+    if depth > 8 {
+        let data: &[u16] = frame.buf.as_slice(0).map_err(|_| AomError::InvalidParam)?;
+        for row in data.chunks(stride).take(info.height) {
+            for &sample in row.iter().take(info.width) {
+                hist[((sample >> shift) as usize).min(HISTOGRAM_BINS - 1)] += 1;
+            }
+        }
+    } else {
+        let data: &[u8] = frame.buf.as_slice(0).map_err(|_| AomError::InvalidParam)?;
+        for row in data.chunks(stride).take(info.height) {
+            for &sample in row.iter().take(info.width) {
+                hist[(sample >> shift) as usize] += 1;
+            }
+        }
+    }
+
+    Ok(hist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use av_data::{
+        frame::{FrameType, VideoInfo},
+        pixel::formats::YUV420,
+    };
+    use std::sync::Arc;
+
+    const WIDTH: usize = 32;
+    const HEIGHT: usize = 16;
+
+    // I420 frame with the given luma, chroma is left as allocated
+    fn frame(luma: impl Fn(usize, usize) -> u8) -> Frame {
+        let info: VideoInfo =
+            VideoInfo::new(WIDTH, HEIGHT, false, FrameType::OTHER, Arc::new(*YUV420));
+        let mut frame: Frame = Frame::new_default_frame(info, None);
+
+        let stride: usize = frame.buf.linesize(0).unwrap();
+        let data: &mut [u8] = frame.buf.as_mut_slice(0).unwrap();
+        // samples past the visible width must not be counted
+        data.fill(255);
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                data[y * stride + x] = luma(x, y);
+            }
+        }
+
+        frame
+    }
+
+    fn black() -> Frame {
+        frame(|_, _| 0)
+    }
+
+    // right half white
+    fn split() -> Frame {
+        frame(|x, _| if x < WIDTH / 2 { 0 } else { 255 })
+    }
+
+    #[test]
+    fn histogram_bins() {
+        let hist: [u64; HISTOGRAM_BINS] = luma_histogram(&frame(|x, _| x as u8 * 4)).unwrap();
+        // one column per 4 wide bin
+        let expected: Vec<u64> = (0..HISTOGRAM_BINS)
+            .map(|bin| if bin < WIDTH { HEIGHT as u64 } else { 0 })
+            .collect();
+        assert_eq!(hist[..], expected[..]);
+
+        let hist: [u64; HISTOGRAM_BINS] = luma_histogram(&split()).unwrap();
+        assert_eq!(hist[0], (WIDTH * HEIGHT / 2) as u64);
+        assert_eq!(hist[HISTOGRAM_BINS - 1], (WIDTH * HEIGHT / 2) as u64);
+        assert_eq!(hist.iter().sum::<u64>(), (WIDTH * HEIGHT) as u64);
+    }
+
+    #[test]
+    fn scores() {
+        let mut detector: SceneCutDetector = SceneCutDetector::new(0.4).unwrap();
+        assert!(!detector.is_scene_cut(&black()).unwrap());
+        assert_eq!(detector.last_score(), None);
+
+        assert!(!detector.is_scene_cut(&black()).unwrap());
+        assert_eq!(detector.last_score(), Some(0.0));
+
+        // half the samples moved
+        assert!(detector.is_scene_cut(&split()).unwrap());
+        assert_eq!(detector.last_score(), Some(0.5));
+
+        // disjoint histograms
+        assert!(detector.is_scene_cut(&frame(|_, _| 128)).unwrap());
+        assert_eq!(detector.last_score(), Some(1.0));
+
+        // the first frame after a reset is never a cut
+        detector.reset();
+        assert_eq!(detector.last_score(), None);
+        assert!(!detector.is_scene_cut(&black()).unwrap());
+    }
+
+    #[test]
+    fn min_distance() {
+        let mut detector: SceneCutDetector = SceneCutDetector::new(0.4).unwrap().min_distance(3);
+        let cuts: Vec<bool> = [black(), split(), black(), split(), black(), split()]
+            .iter()
+            .map(|f| detector.is_scene_cut(f).unwrap())
+            .collect();
+        // every frame scores 0.5, cuts are at least 3 frames apart
+        assert_eq!(cuts, [false, false, false, true, false, false]);
+
+        let mut detector: SceneCutDetector = SceneCutDetector::new(0.4).unwrap();
+        let cuts: Vec<bool> = [black(), split(), black()]
+            .iter()
+            .map(|f| detector.is_scene_cut(f).unwrap())
+            .collect();
+        assert_eq!(cuts, [false, true, true]);
+    }
+
+    #[test]
+    fn rejects_invalid_thresholds() {
+        for threshold in [-0.1, 1.1, f64::NAN] {
+            assert!(matches!(
+                SceneCutDetector::new(threshold),
+                Err(AomError::InvalidParam)
+            ));
+        }
+        assert!(SceneCutDetector::new(0.0).is_ok());
+        assert!(SceneCutDetector::new(1.0).is_ok());
+    }
+}
@@ -0,0 +1,240 @@
+use super::{
+    errors::AomError,
+    obu::{split_obus, ObuType},
+};
+
+/// One tile_list_entry() of a tile list OBU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileListEntry {
+    pub anchor_frame_idx: u8,
+    pub anchor_tile_row: u8,
+    pub anchor_tile_col: u8,
+    pub coded_tile_data: Vec<u8>,
+}
+
+/// Parsed tile list OBU, the unit of the large_scale_tile (360/VR) streaming workflow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileList {
+    pub output_frame_width_in_tiles_minus_1: u8,
+    pub output_frame_height_in_tiles_minus_1: u8,
+    pub entries: Vec<TileListEntry>,
+}
+
+impl TileList {
+    /// Parses the payload of a tile list OBU (without the OBU header)
+    pub fn parse(payload: &[u8]) -> Result<Self, AomError> {
+        if payload.len() < 4 {
+            return Err(AomError::CorruptFrame);
+        }
+
+        let tile_count: usize = u16::from_be_bytes([payload[2], payload[3]]) as usize + 1;
+        let mut entries: Vec<TileListEntry> = Vec::with_capacity(tile_count);
+        let mut pos: usize = 4;
+
+        for _ in 0..tile_count {
+            let head: &[u8] = payload.get(pos..pos + 5).ok_or(AomError::CorruptFrame)?;
+            let size: usize = u16::from_be_bytes([head[3], head[4]]) as usize + 1;
+            let data: &[u8] = payload
+                .get(pos + 5..pos + 5 + size)
+                .ok_or(AomError::CorruptFrame)?;
+
+            entries.push(TileListEntry {
+                anchor_frame_idx: head[0],
+                anchor_tile_row: head[1],
+                anchor_tile_col: head[2],
+                coded_tile_data: data.to_vec(),
+            });
+            pos += 5 + size;
+        }
+
+        Ok(TileList {
+            output_frame_width_in_tiles_minus_1: payload[0],
+            output_frame_height_in_tiles_minus_1: payload[1],
+            entries,
+        })
+    }
+
+    /// Serializes the tile list back into an OBU payload.
+    ///
+    /// The tile count and the size of each tile are coded minus 1 in 16 bits, so a list
+    /// needs 1 to 65536 entries with 1 to 65536 bytes of tile data each. Anything else
+    /// fails with `AomError::InvalidParam`.
+    pub fn to_payload(&self) -> Result<Vec<u8>, AomError> {
+        let tile_count_minus_1: u16 = minus_1(self.entries.len())?;
+        let mut out: Vec<u8> = vec![
+            self.output_frame_width_in_tiles_minus_1,
+            self.output_frame_height_in_tiles_minus_1,
+        ];
+        out.extend_from_slice(&tile_count_minus_1.to_be_bytes());
+
+        for entry in &self.entries {
+            let size_minus_1: u16 = minus_1(entry.coded_tile_data.len())?;
+            out.push(entry.anchor_frame_idx);
+            out.push(entry.anchor_tile_row);
+            out.push(entry.anchor_tile_col);
+            out.extend_from_slice(&size_minus_1.to_be_bytes());
+            out.extend_from_slice(&entry.coded_tile_data);
+        }
+
+        Ok(out)
+    }
+}
+
+// `len - 1` as the 16 bit field of the tile list syntax, for 1 <= len <= 65536
+fn minus_1(len: usize) -> Result<u16, AomError> {
+    len.checked_sub(1)
+        .and_then(|n| u16::try_from(n).ok())
+        .ok_or(AomError::InvalidParam)
+}
+
+/// Keeps only the chosen tiles of the tile list OBUs in a large_scale_tile stream.
+///
+/// Tiles are identified by their (anchor_tile_row, anchor_tile_col) position. All other
+/// OBUs pass through untouched, tile lists left without any tile are dropped.
+///
+/// ```rust,no_run
+/// # use aom_rs::core::tile_list::TileSelector;
+/// # fn example(stream: &[u8]) -> Result<(), aom_rs::core::AomError> {
+/// let selector = TileSelector::new(&[(0, 1), (0, 2)]);
+/// let reduced: Vec<u8> = selector.select(&stream)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TileSelector {
+    tiles: Vec<(u8, u8)>,
+}
+
+impl TileSelector {
+    pub fn new(tiles: &[(u8, u8)]) -> Self {
+        TileSelector {
+            tiles: tiles.to_vec(),
+        }
+    }
+
+    fn keeps(&self, entry: &TileListEntry) -> bool {
+        self.tiles
+            .contains(&(entry.anchor_tile_row, entry.anchor_tile_col))
+    }
+
+    /// Returns the reduced bitstream
+    pub fn select(&self, data: &[u8]) -> Result<Vec<u8>, AomError> {
+        let mut out: Vec<u8> = Vec::with_capacity(data.len());
+
+        for obu in split_obus(data)? {
+            if obu.obu_type != ObuType::TileList {
+                obu.write_with_payload(obu.payload, &mut out);
+                continue;
+            }
+
+            let mut list: TileList = TileList::parse(obu.payload)?;
+            list.entries.retain(|entry| self.keeps(entry));
+            if !list.entries.is_empty() {
+                obu.write_with_payload(&list.to_payload()?, &mut out);
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(row: u8, col: u8, data: &[u8]) -> TileListEntry {
+        TileListEntry {
+            anchor_frame_idx: 1,
+            anchor_tile_row: row,
+            anchor_tile_col: col,
+            coded_tile_data: data.to_vec(),
+        }
+    }
+
+    fn list(entries: Vec<TileListEntry>) -> TileList {
+        TileList {
+            output_frame_width_in_tiles_minus_1: 1,
+            output_frame_height_in_tiles_minus_1: 0,
+            entries,
+        }
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        let tiles: TileList = list(vec![entry(0, 1, &[0xaa, 0xbb, 0xcc]), entry(2, 3, &[0xdd])]);
+        let payload: Vec<u8> = tiles.to_payload().unwrap();
+
+        #[rustfmt::skip]
+        let expected: [u8; 18] = [
+            1, 0, 0, 1,
+            1, 0, 1, 0, 2, 0xaa, 0xbb, 0xcc,
+            1, 2, 3, 0, 0, 0xdd,
+        ];
+        assert_eq!(payload, expected);
+        assert_eq!(TileList::parse(&payload).unwrap(), tiles);
+    }
+
+    #[test]
+    fn to_payload_rejects_unrepresentable_lists() {
+        assert!(matches!(
+            list(Vec::new()).to_payload(),
+            Err(AomError::InvalidParam)
+        ));
+        assert!(matches!(
+            list(vec![entry(0, 0, &[])]).to_payload(),
+            Err(AomError::InvalidParam)
+        ));
+        assert!(matches!(
+            list(vec![entry(0, 0, &vec![0; 65537])]).to_payload(),
+            Err(AomError::InvalidParam)
+        ));
+        assert!(matches!(
+            list(vec![entry(0, 0, &[0]); 65537]).to_payload(),
+            Err(AomError::InvalidParam)
+        ));
+
+        // the largest sizes the fields can code
+        let big: Vec<u8> = list(vec![entry(0, 0, &vec![7; 65536])])
+            .to_payload()
+            .unwrap();
+        assert_eq!(big[7..9], [0xff, 0xff]);
+    }
+
+    #[test]
+    fn parse_rejects_truncated_payloads() {
+        let payload: Vec<u8> = list(vec![entry(0, 1, &[1, 2, 3])]).to_payload().unwrap();
+        for len in 0..payload.len() {
+            assert!(
+                matches!(
+                    TileList::parse(&payload[..len]),
+                    Err(AomError::CorruptFrame)
+                ),
+                "{}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn selector_keeps_the_chosen_tiles() {
+        let payload: Vec<u8> = list(vec![
+            entry(0, 0, &[1]),
+            entry(0, 1, &[2, 3]),
+            entry(1, 0, &[4]),
+        ])
+        .to_payload()
+        .unwrap();
+        // temporal delimiter, then the tile list OBU with its size field
+        let mut stream: Vec<u8> = vec![0x12, 0x00, 0x42, payload.len() as u8];
+        stream.extend_from_slice(&payload);
+
+        let reduced: Vec<u8> = TileSelector::new(&[(0, 1)]).select(&stream).unwrap();
+        assert_eq!(reduced[..3], [0x12, 0x00, 0x42]);
+        let kept: TileList = TileList::parse(&reduced[4..]).unwrap();
+        assert_eq!(kept.entries, vec![entry(0, 1, &[2, 3])]);
+        assert_eq!(reduced[3] as usize, reduced.len() - 4);
+
+        // a tile list left empty is dropped, the rest passes through
+        let none: Vec<u8> = TileSelector::new(&[(5, 5)]).select(&stream).unwrap();
+        assert_eq!(none, [0x12, 0x00]);
+    }
+}
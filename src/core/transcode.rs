@@ -0,0 +1,227 @@
+use super::{
+    config::AV1EncoderConfig,
+    decoder::{open_stream, AV1Decoder},
+    encoder::{AOMPacket, AV1Encoder, InitFlags},
+    errors::AomError,
+};
+use crate::aom::{
+    aom_codec_decode, aom_codec_get_frame, aom_codec_iter_t, aom_image, AOM_IMG_FMT_HIGHBITDEPTH,
+};
+use std::ptr;
+
+/// Decodes an AV1 stream and encodes every frame again with `cfg`, e.g. to bring a
+/// stream down to a lower bitrate. Returns the new stream as low-overhead (Section 5)
+/// OBUs, or Annex B when `cfg.save_as_annexb` is set.
+///
+/// The input may use either framing. The encoder is created when the first frame is
+/// decoded: g_w, g_h, g_bit_depth and g_input_bit_depth of `cfg` are replaced by the
+/// ones of the decoded frames (the output keeps the bit depth of the input), everything
+/// else is used as given. Decoded frames have no timestamps, so frame `n` is encoded at
+/// pts `n` with a duration of one g_timebase tick; set g_timebase to the frame period
+/// of the input.
+///
+/// When the frame size changes mid-stream the new size is applied with
+/// `AV1Encoder::update_config`, which needs a one pass `cfg` with g_lag_in_frames 0 or
+/// 1; otherwise, or when the bit depth changes, the transcode fails with its
+/// `AomError::InvalidConfig`.
+pub fn transcode(input: &[u8], cfg: &AV1EncoderConfig) -> Result<Vec<u8>, AomError> {
+    let (mut decoder, units) = open_stream(input)?;
+    let mut encoder: Option<AV1Encoder> = None;
+    let mut output: Vec<u8> = Vec::new();
+    let mut pts: i64 = 0;
+
+    for unit in units {
+        decode(&mut decoder, unit.as_ptr(), unit.len())?;
+        reencode(&mut decoder, &mut encoder, cfg, &mut pts, &mut output)?;
+    }
+    // flush the decoder, then the encoder
+    decode(&mut decoder, ptr::null(), 0)?;
+    reencode(&mut decoder, &mut encoder, cfg, &mut pts, &mut output)?;
+
+    if let Some(encoder) = encoder {
+        for pkt in encoder.finish()? {
+            append(&mut output, pkt);
+        }
+    }
+
+    Ok(output)
+}
+
+fn decode(decoder: &mut AV1Decoder, data: *const u8, size: usize) -> Result<(), AomError> {
+    let result: u32 = unsafe { aom_codec_decode(&mut decoder.ctx, data, size, ptr::null_mut()) };
+    if result != 0 {
+        return Err(result.into());
+    }
+    Ok(())
+}
+
+// Encodes the frames the last decode call produced. Their planes belong to the
+// decoder and stay valid until the next decode, libaom copies them on encode.
+fn reencode(
+    decoder: &mut AV1Decoder,
+    encoder: &mut Option<AV1Encoder>,
+    cfg: &AV1EncoderConfig,
+    pts: &mut i64,
+    output: &mut Vec<u8>,
+) -> Result<(), AomError> {
+    let mut iter: aom_codec_iter_t = ptr::null();
+    loop {
+        let img: *mut aom_image = unsafe { aom_codec_get_frame(&mut decoder.ctx, &mut iter) };
+        if img.is_null() {
+            return Ok(());
+        }
+        let img: &aom_image = unsafe { &*img };
+
+        let enc: &mut AV1Encoder = match encoder {
+            Some(enc) => enc,
+            None => encoder.insert(encoder_for(img, cfg)?),
+        };
+        if (img.d_w, img.d_h, img.bit_depth) != (enc.width, enc.height, enc.cfg.g_input_bit_depth) {
+            enc.update_config(&frame_config(img, cfg))?;
+        }
+        enc.encode_img(img, *pts, 1, 0)?;
+        *pts += 1;
+
+        while let Some(pkt) = enc.get_packet() {
+            append(output, pkt);
+        }
+    }
+}
+
+// `cfg` with the size and bit depth of a decoded frame
+fn frame_config(img: &aom_image, cfg: &AV1EncoderConfig) -> AV1EncoderConfig {
+    let mut cfg: AV1EncoderConfig = cfg.clone();
+    cfg.enc_cfg.g_w = img.d_w;
+    cfg.enc_cfg.g_h = img.d_h;
+    cfg.enc_cfg.g_bit_depth = img.bit_depth;
+    cfg.enc_cfg.g_input_bit_depth = img.bit_depth;
+    cfg
+}
+
+fn encoder_for(img: &aom_image, cfg: &AV1EncoderConfig) -> Result<AV1Encoder, AomError> {
+    let mut cfg: AV1EncoderConfig = frame_config(img, cfg);
+
+    // libaom hands out 16-bit planes when it was built with high bit depth support,
+    // even for 8-bit streams; the encoder only takes those with the matching flag
+    let high_bit_depth: bool = img.fmt & AOM_IMG_FMT_HIGHBITDEPTH != 0;
+    Ok(AV1Encoder::new_with_flags(
+        &mut cfg,
+        InitFlags::new().high_bit_depth(high_bit_depth),
+    )?)
+}
+
+fn append(output: &mut Vec<u8>, pkt: AOMPacket) {
+    if let AOMPacket::Frame(packet) = pkt {
+        output.extend_from_slice(&packet.data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{aom::aom_rational, core::AomCodecEncCfgTrait};
+    use av_data::{
+        frame::{Frame, FrameBufferConv, FrameType, VideoInfo},
+        pixel::formats::YUV420,
+        timeinfo::TimeInfo,
+    };
+    use std::sync::Arc;
+
+    // I420 frame with a gradient moving with `index` and flat chroma
+    fn frame(width: usize, height: usize, index: usize) -> Frame {
+        let info: VideoInfo =
+            VideoInfo::new(width, height, false, FrameType::OTHER, Arc::new(*YUV420));
+        let t: TimeInfo = TimeInfo {
+            pts: Some(index as i64),
+            duration: Some(1),
+            ..Default::default()
+        };
+        let mut frame: Frame = Frame::new_default_frame(info, Some(t));
+
+        let stride: usize = frame.buf.linesize(0).unwrap();
+        let luma: &mut [u8] = frame.buf.as_mut_slice(0).unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                luma[y * stride + x] = ((x + y + index * 4) % 256) as u8;
+            }
+        }
+        for plane in 1..3 {
+            let chroma: &mut [u8] = frame.buf.as_mut_slice(plane).unwrap();
+            chroma.fill(128);
+        }
+
+        frame
+    }
+
+    fn realtime_config(width: u32, height: u32) -> AV1EncoderConfig {
+        let mut cfg: AV1EncoderConfig = AV1EncoderConfig::for_realtime().unwrap();
+        cfg.g_w(width)
+            .g_h(height)
+            .g_timebase(aom_rational { num: 1, den: 30 })
+            .g_lag_in_frames(0);
+        cfg
+    }
+
+    // three 320x240 frames, then three 160x120 ones
+    fn resized_stream() -> Vec<u8> {
+        let mut cfg: AV1EncoderConfig = realtime_config(320, 240);
+        let mut encoder: AV1Encoder = AV1Encoder::new(&mut cfg).unwrap();
+        let mut packets: Vec<AOMPacket> = Vec::new();
+        for i in 0..3 {
+            packets.extend(encoder.encode_frame(&frame(320, 240, i)).unwrap());
+        }
+        cfg.g_w(160).g_h(120);
+        encoder.update_config(&cfg).unwrap();
+        for i in 3..6 {
+            packets.extend(encoder.encode_frame(&frame(160, 120, i)).unwrap());
+        }
+        packets.extend(encoder.finish().unwrap());
+
+        let mut stream: Vec<u8> = Vec::new();
+        for pkt in packets {
+            append(&mut stream, pkt);
+        }
+        stream
+    }
+
+    // sizes of the frames decoded from `stream`
+    fn frame_sizes(stream: &[u8]) -> Vec<(u32, u32)> {
+        let (mut decoder, units) = open_stream(stream).unwrap();
+        let mut sizes: Vec<(u32, u32)> = Vec::new();
+        for unit in units {
+            decode(&mut decoder, unit.as_ptr(), unit.len()).unwrap();
+            let mut iter: aom_codec_iter_t = ptr::null();
+            loop {
+                let img: *mut aom_image =
+                    unsafe { aom_codec_get_frame(&mut decoder.ctx, &mut iter) };
+                if img.is_null() {
+                    break;
+                }
+                let img: &aom_image = unsafe { &*img };
+                sizes.push((img.d_w, img.d_h));
+            }
+        }
+        sizes
+    }
+
+    #[test]
+    fn follows_resolution_changes() {
+        let input: Vec<u8> = resized_stream();
+        let expected: Vec<(u32, u32)> =
+            [(320, 240); 3].into_iter().chain([(160, 120); 3]).collect();
+        assert_eq!(frame_sizes(&input), expected);
+
+        let output: Vec<u8> = transcode(&input, &realtime_config(16, 16)).unwrap();
+        assert_eq!(frame_sizes(&output), expected);
+    }
+
+    #[test]
+    fn resolution_change_needs_a_short_lookahead() {
+        let mut cfg: AV1EncoderConfig = realtime_config(16, 16);
+        cfg.g_lag_in_frames(10);
+        match transcode(&resized_stream(), &cfg) {
+            Err(AomError::InvalidConfig { field, .. }) => assert_eq!(field, "g_w"),
+            other => panic!("unexpected result {:?}", other.map(|out| out.len())),
+        }
+    }
+}
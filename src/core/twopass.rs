@@ -0,0 +1,230 @@
+use super::{
+    config::{AV1EncoderConfig, EncodePass, FixedBuf},
+    encoder::{AOMPacket, AV1Encoder},
+    errors::AomError,
+    AomCodecEncCfgTrait,
+};
+use crate::aom::aom_fixed_buf_t;
+use av_data::frame::Frame;
+use std::os::raw::c_void;
+
+/// Concatenation of the AOMPacket::TwoPassStats packets of a first pass, which is
+/// what rc_twopass_stats_in expects for the second pass
+#[derive(Debug, Clone, Default)]
+pub struct StatsBuffer {
+    data: Vec<u8>,
+}
+
+impl StatsBuffer {
+    pub fn new() -> Self {
+        StatsBuffer { data: Vec::new() }
+    }
+
+    /// Appends the stats of a TwoPassStats packet, other packets are ignored.
+    /// Returns whether the packet was consumed.
+    pub fn push(&mut self, pkt: &AOMPacket) -> bool {
+        match pkt {
+            AOMPacket::TwoPassStats(stats) => {
+                self.data.extend_from_slice(stats);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// aom_fixed_buf_t pointing into the stats, for code that fills an
+    /// aom_codec_enc_cfg itself. Nothing ties the pointer to `self`: the buffer must
+    /// not be modified or dropped while an encoder may read it, i.e. until the second
+    /// pass encoder is destroyed. `into_fixed_buf` with
+    /// `AV1EncoderConfig::rc_twopass_stats_in` handles that by sharing ownership.
+    pub fn as_fixed_buf(&self) -> aom_fixed_buf_t {
+        aom_fixed_buf_t {
+            buf: self.data.as_ptr() as *mut c_void,
+            sz: self.data.len(),
+        }
+    }
+
+    /// Turns the stats into the owned buffer the second pass configuration takes
+    pub fn into_fixed_buf(self) -> FixedBuf {
+        self.data.into()
+    }
+}
+
+/// Concatenation of the AOMPacket::FirstPassMBStats packets of a first pass, for
+/// rc_firstpass_mb_stats_in of the second pass.
+///
+/// These per macroblock stats are an optional libaom feature: stock builds never emit
+/// the packets (and there is no control or init flag to request them), so the buffer
+/// stays empty unless libaom was built with first pass MB stats support.
+#[derive(Debug, Clone, Default)]
+pub struct MbStatsBuffer {
+    data: Vec<u8>,
+}
+
+impl MbStatsBuffer {
+    pub fn new() -> Self {
+        MbStatsBuffer { data: Vec::new() }
+    }
+
+    /// Appends the stats of a FirstPassMBStats packet, other packets are ignored.
+    /// Returns whether the packet was consumed.
+    pub fn push(&mut self, pkt: &AOMPacket) -> bool {
+        match pkt {
+            AOMPacket::FirstPassMBStats(stats) => {
+                self.data.extend_from_slice(stats);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Turns the stats into the owned buffer the second pass configuration takes
+    pub fn into_fixed_buf(self) -> FixedBuf {
+        self.data.into()
+    }
+}
+
+/// Two-pass encode driven frame by frame, without any file I/O.
+///
+/// `first_pass` runs every frame through an encoder configured for AOM_RC_FIRST_PASS
+/// and collects its stats in memory. `second_pass` then flushes that encoder and returns
+/// a fresh one for AOM_RC_LAST_PASS whose rc_twopass_stats_in points at the stats, to be
+/// fed the same frames again. libaom needs the whole first pass before the second one
+/// starts, so the frames have to be kept or read twice by the caller.
+///
+/// # Example
+/// ```rust,no_run
+/// # use aom_rs::core::{config::AV1EncoderConfig, twopass::TwoPassEncoder};
+/// # use av_data::frame::Frame;
+/// # fn example(config: AV1EncoderConfig, frames: Vec<Frame>) -> Result<(), aom_rs::core::AomError> {
+/// let mut two_pass = TwoPassEncoder::new(&config)?;
+/// for frame in &frames {
+///     two_pass.first_pass(frame)?;
+/// }
+/// let mut encoder = two_pass.second_pass()?;
+/// for frame in &frames {
+///     encoder.aom_codec_encode(frame)?;
+///     while let Some(pkt) = encoder.get_packet() { /* ... */ }
+/// }
+/// let packets = encoder.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TwoPassEncoder {
+    cfg: AV1EncoderConfig,
+    encoder: AV1Encoder,
+    stats: StatsBuffer,
+    mb_stats: MbStatsBuffer,
+}
+
+impl TwoPassEncoder {
+    /// Starts the first pass. `cfg` is the configuration of the second pass (rate
+    /// control, bitrate, ...); `cfg.g_pass` and `cfg.rc_twopass_stats_in` are
+    /// overridden, and so is `cfg.rc_firstpass_mb_stats_in` when the first pass
+    /// produces MB stats.
+    pub fn new(cfg: &AV1EncoderConfig) -> Result<Self, AomError> {
+        let mut first_cfg: AV1EncoderConfig = cfg.clone();
+        first_cfg.encode_pass(EncodePass::First);
+
+        Ok(TwoPassEncoder {
+            cfg: cfg.clone(),
+            encoder: AV1Encoder::new(&mut first_cfg)?,
+            stats: StatsBuffer::new(),
+            mb_stats: MbStatsBuffer::new(),
+        })
+    }
+
+    /// Analyzes the next frame. Frames are timed like in `AV1Encoder::aom_codec_encode`,
+    /// the second pass has to get them with the same timestamps.
+    pub fn first_pass(&mut self, frame: &Frame) -> Result<(), AomError> {
+        self.encoder.aom_codec_encode(frame)?;
+        while let Some(pkt) = self.encoder.get_packet() {
+            self.collect(&pkt);
+        }
+        Ok(())
+    }
+
+    /// Size of the stats collected so far
+    pub fn stats_len(&self) -> usize {
+        self.stats.len()
+    }
+
+    /// Ends the first pass and creates the encoder of the second pass
+    pub fn second_pass(self) -> Result<AV1Encoder, AomError> {
+        let TwoPassEncoder {
+            mut cfg,
+            encoder,
+            mut stats,
+            mut mb_stats,
+        } = self;
+        for pkt in encoder.finish()? {
+            stats.push(&pkt);
+            mb_stats.push(&pkt);
+        }
+
+        cfg.encode_pass(EncodePass::Last);
+        cfg.rc_twopass_stats_in(stats.into_fixed_buf());
+        if !mb_stats.is_empty() {
+            cfg.rc_firstpass_mb_stats_in(mb_stats.into_fixed_buf());
+        }
+
+        AV1Encoder::new(&mut cfg)
+    }
+
+    fn collect(&mut self, pkt: &AOMPacket) {
+        self.stats.push(pkt);
+        self.mb_stats.push(pkt);
+    }
+}
+
+/// Two-pass encode of a whole clip with `TwoPassEncoder`.
+///
+/// Runs both passes over `frames` with `cfg` and returns the packets of the second
+/// pass. The frames are kept in memory between the passes.
+pub fn run_two_pass(
+    frames: impl Iterator<Item = Frame>,
+    cfg: &AV1EncoderConfig,
+) -> Result<Vec<AOMPacket>, AomError> {
+    let frames: Vec<Frame> = frames.collect();
+
+    let mut two_pass: TwoPassEncoder = TwoPassEncoder::new(cfg)?;
+    for frame in &frames {
+        two_pass.first_pass(frame)?;
+    }
+
+    let mut packets: Vec<AOMPacket> = Vec::new();
+    let mut encoder: AV1Encoder = two_pass.second_pass()?;
+    for frame in &frames {
+        encoder.aom_codec_encode(frame)?;
+        while let Some(pkt) = encoder.get_packet() {
+            packets.push(pkt);
+        }
+    }
+    packets.extend(encoder.finish()?);
+
+    Ok(packets)
+}
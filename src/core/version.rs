@@ -0,0 +1,75 @@
+use super::errors::AomError;
+use crate::aom::aom_codec_version;
+
+/// (major, minor, patch) of the libaom the crate was built against, from the
+/// aom_version.h of the build (see build.rs). The bindings are generated from the
+/// same headers.
+pub const AOM_BUILD_VERSION: (u32, u32, u32) = parse_version(env!("AOM_BUILD_VERSION"));
+
+// The wrapper uses controls and fields up to this release
+const MIN_BUILD_VERSION: (u32, u32, u32) = (3, 9, 0);
+
+const _: () = assert!(
+    version_at_least(AOM_BUILD_VERSION, MIN_BUILD_VERSION),
+    "aom-rs needs libaom 3.9.0 or newer"
+);
+
+/// aom_codec_set_option, used by `AV1Encoder::apply_options`
+pub(crate) const SET_OPTION_VERSION: (u32, u32, u32) = (3, 2, 0);
+
+/// `version >= min`, usable in const contexts
+pub const fn version_at_least(version: (u32, u32, u32), min: (u32, u32, u32)) -> bool {
+    if version.0 != min.0 {
+        return version.0 > min.0;
+    }
+    if version.1 != min.1 {
+        return version.1 > min.1;
+    }
+    version.2 >= min.2
+}
+
+/// (major, minor, patch) of the libaom loaded at runtime (aom_codec_version). With a
+/// shared libaom this can be older than `AOM_BUILD_VERSION`.
+pub fn runtime_version() -> (u32, u32, u32) {
+    let version: u32 = unsafe { aom_codec_version() } as u32;
+    (
+        (version >> 16) & 0xff,
+        (version >> 8) & 0xff,
+        version & 0xff,
+    )
+}
+
+/// Fails with `AomError::VersionTooOld` when the libaom loaded at runtime is older
+/// than `min`. Called by the wrappers of APIs that only newer releases have, so they
+/// fail with the version at fault instead of an unspecified libaom error.
+pub fn require_version(min: (u32, u32, u32)) -> Result<(), AomError> {
+    let found: (u32, u32, u32) = runtime_version();
+    if version_at_least(found, min) {
+        Ok(())
+    } else {
+        Err(AomError::VersionTooOld {
+            required: min,
+            found,
+        })
+    }
+}
+
+// "3.9.1" -> (3, 9, 1); stops at the first character that is neither a digit nor a
+// dot, so suffixes like "-rc1" are ignored
+const fn parse_version(version: &str) -> (u32, u32, u32) {
+    let bytes: &[u8] = version.as_bytes();
+    let mut parts: [u32; 3] = [0; 3];
+    let mut part: usize = 0;
+    let mut i: usize = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'.' if part < 2 => part += 1,
+            digit @ b'0'..=b'9' => parts[part] = parts[part] * 10 + (digit - b'0') as u32,
+            _ => break,
+        }
+        i += 1;
+    }
+
+    (parts[0], parts[1], parts[2])
+}
@@ -0,0 +1,89 @@
+//! Items of aom_codec.h, aom_image.h, aom_integer.h and aom.h that the encoder and
+//! the decoder share, re-exported by `enc` and `dec`.
+
+pub use super::aom::{
+    aom_bit_depth, aom_bit_depth_AOM_BITS_10, aom_bit_depth_AOM_BITS_12, aom_bit_depth_AOM_BITS_8,
+    aom_chroma_sample_position, aom_chroma_sample_position_AOM_CSP_COLOCATED,
+    aom_chroma_sample_position_AOM_CSP_RESERVED, aom_chroma_sample_position_AOM_CSP_UNKNOWN,
+    aom_chroma_sample_position_AOM_CSP_VERTICAL, aom_codec_build_config, aom_codec_caps_t,
+    aom_codec_control, aom_codec_control_type_AV1_COPY_NEW_FRAME_IMAGE,
+    aom_codec_control_type_AV1_COPY_REFERENCE, aom_codec_control_type_AV1_GET_NEW_FRAME_IMAGE,
+    aom_codec_control_type_AV1_GET_REFERENCE, aom_codec_control_type_AV1_SET_REFERENCE,
+    aom_codec_ctx, aom_codec_ctx__bindgen_ty_1, aom_codec_ctx_t, aom_codec_destroy,
+    aom_codec_err_t, aom_codec_err_t_AOM_CODEC_ABI_MISMATCH,
+    aom_codec_err_t_AOM_CODEC_CORRUPT_FRAME, aom_codec_err_t_AOM_CODEC_ERROR,
+    aom_codec_err_t_AOM_CODEC_INCAPABLE, aom_codec_err_t_AOM_CODEC_INVALID_PARAM,
+    aom_codec_err_t_AOM_CODEC_LIST_END, aom_codec_err_t_AOM_CODEC_MEM_ERROR,
+    aom_codec_err_t_AOM_CODEC_OK, aom_codec_err_t_AOM_CODEC_UNSUP_BITSTREAM,
+    aom_codec_err_t_AOM_CODEC_UNSUP_FEATURE, aom_codec_err_to_string, aom_codec_error,
+    aom_codec_error_detail, aom_codec_flags_t, aom_codec_get_caps, aom_codec_iface,
+    aom_codec_iface_name, aom_codec_iface_t, aom_codec_iter_t, aom_codec_priv, aom_codec_priv_t,
+    aom_codec_set_option, aom_codec_version, aom_codec_version_extra_str, aom_codec_version_str,
+    aom_color_primaries, aom_color_primaries_AOM_CICP_CP_BT_2020,
+    aom_color_primaries_AOM_CICP_CP_BT_470_B_G, aom_color_primaries_AOM_CICP_CP_BT_470_M,
+    aom_color_primaries_AOM_CICP_CP_BT_601, aom_color_primaries_AOM_CICP_CP_BT_709,
+    aom_color_primaries_AOM_CICP_CP_EBU_3213, aom_color_primaries_AOM_CICP_CP_GENERIC_FILM,
+    aom_color_primaries_AOM_CICP_CP_RESERVED_0, aom_color_primaries_AOM_CICP_CP_RESERVED_13,
+    aom_color_primaries_AOM_CICP_CP_RESERVED_23, aom_color_primaries_AOM_CICP_CP_RESERVED_3,
+    aom_color_primaries_AOM_CICP_CP_SMPTE_240, aom_color_primaries_AOM_CICP_CP_SMPTE_431,
+    aom_color_primaries_AOM_CICP_CP_SMPTE_432, aom_color_primaries_AOM_CICP_CP_UNSPECIFIED,
+    aom_color_primaries_AOM_CICP_CP_XYZ, aom_color_range, aom_color_range_AOM_CR_FULL_RANGE,
+    aom_color_range_AOM_CR_STUDIO_RANGE, aom_com_control_id,
+    aom_com_control_id_AOM_DECODER_CTRL_ID_START, aom_com_control_id_AV1_COPY_NEW_FRAME_IMAGE,
+    aom_com_control_id_AV1_COPY_REFERENCE, aom_com_control_id_AV1_GET_NEW_FRAME_IMAGE,
+    aom_com_control_id_AV1_GET_REFERENCE, aom_com_control_id_AV1_SET_REFERENCE, aom_image,
+    aom_image_t, aom_img_add_metadata, aom_img_alloc, aom_img_alloc_with_border, aom_img_flip,
+    aom_img_fmt, aom_img_fmt_AOM_IMG_FMT_AOMI420, aom_img_fmt_AOM_IMG_FMT_AOMYV12,
+    aom_img_fmt_AOM_IMG_FMT_I420, aom_img_fmt_AOM_IMG_FMT_I42016, aom_img_fmt_AOM_IMG_FMT_I422,
+    aom_img_fmt_AOM_IMG_FMT_I42216, aom_img_fmt_AOM_IMG_FMT_I444, aom_img_fmt_AOM_IMG_FMT_I44416,
+    aom_img_fmt_AOM_IMG_FMT_NONE, aom_img_fmt_AOM_IMG_FMT_NV12, aom_img_fmt_AOM_IMG_FMT_YV12,
+    aom_img_fmt_AOM_IMG_FMT_YV1216, aom_img_free, aom_img_get_metadata, aom_img_metadata_alloc,
+    aom_img_metadata_free, aom_img_num_metadata, aom_img_plane_height, aom_img_plane_width,
+    aom_img_remove_metadata, aom_img_set_rect, aom_img_wrap, aom_matrix_coefficients,
+    aom_matrix_coefficients_AOM_CICP_MC_BT_2020_CL,
+    aom_matrix_coefficients_AOM_CICP_MC_BT_2020_NCL,
+    aom_matrix_coefficients_AOM_CICP_MC_BT_470_B_G, aom_matrix_coefficients_AOM_CICP_MC_BT_601,
+    aom_matrix_coefficients_AOM_CICP_MC_BT_709, aom_matrix_coefficients_AOM_CICP_MC_CHROMAT_CL,
+    aom_matrix_coefficients_AOM_CICP_MC_CHROMAT_NCL, aom_matrix_coefficients_AOM_CICP_MC_FCC,
+    aom_matrix_coefficients_AOM_CICP_MC_ICTCP, aom_matrix_coefficients_AOM_CICP_MC_IDENTITY,
+    aom_matrix_coefficients_AOM_CICP_MC_RESERVED_15,
+    aom_matrix_coefficients_AOM_CICP_MC_RESERVED_3, aom_matrix_coefficients_AOM_CICP_MC_SMPTE_2085,
+    aom_matrix_coefficients_AOM_CICP_MC_SMPTE_240, aom_matrix_coefficients_AOM_CICP_MC_SMPTE_YCGCO,
+    aom_matrix_coefficients_AOM_CICP_MC_UNSPECIFIED, aom_metadata, aom_metadata_array,
+    aom_metadata_array_t, aom_metadata_insert_flags, aom_metadata_insert_flags_AOM_MIF_ANY_FRAME,
+    aom_metadata_insert_flags_AOM_MIF_KEY_FRAME, aom_metadata_insert_flags_AOM_MIF_NON_KEY_FRAME,
+    aom_metadata_t, aom_obu_type_to_string, aom_rational, aom_rational_t,
+    aom_transfer_characteristics, aom_transfer_characteristics_AOM_CICP_TC_BT_1361,
+    aom_transfer_characteristics_AOM_CICP_TC_BT_2020_10_BIT,
+    aom_transfer_characteristics_AOM_CICP_TC_BT_2020_12_BIT,
+    aom_transfer_characteristics_AOM_CICP_TC_BT_470_B_G,
+    aom_transfer_characteristics_AOM_CICP_TC_BT_470_M,
+    aom_transfer_characteristics_AOM_CICP_TC_BT_601,
+    aom_transfer_characteristics_AOM_CICP_TC_BT_709, aom_transfer_characteristics_AOM_CICP_TC_HLG,
+    aom_transfer_characteristics_AOM_CICP_TC_IEC_61966,
+    aom_transfer_characteristics_AOM_CICP_TC_LINEAR,
+    aom_transfer_characteristics_AOM_CICP_TC_LOG_100,
+    aom_transfer_characteristics_AOM_CICP_TC_LOG_100_SQRT10,
+    aom_transfer_characteristics_AOM_CICP_TC_RESERVED_0,
+    aom_transfer_characteristics_AOM_CICP_TC_RESERVED_19,
+    aom_transfer_characteristics_AOM_CICP_TC_RESERVED_3,
+    aom_transfer_characteristics_AOM_CICP_TC_SMPTE_2084,
+    aom_transfer_characteristics_AOM_CICP_TC_SMPTE_240,
+    aom_transfer_characteristics_AOM_CICP_TC_SMPTE_428,
+    aom_transfer_characteristics_AOM_CICP_TC_SRGB,
+    aom_transfer_characteristics_AOM_CICP_TC_UNSPECIFIED, aom_uleb_decode, aom_uleb_encode,
+    aom_uleb_encode_fixed_size, aom_uleb_size_in_bytes, AOM_CODEC_ABI_VERSION,
+    AOM_CODEC_CAP_DECODER, AOM_CODEC_CAP_ENCODER, AOM_CODEC_CAP_HIGHBITDEPTH,
+    AOM_FRAME_IS_DELAYED_RANDOM_ACCESS_POINT, AOM_FRAME_IS_DROPPABLE, AOM_FRAME_IS_ERROR_RESILIENT,
+    AOM_FRAME_IS_INTRAONLY, AOM_FRAME_IS_KEY, AOM_FRAME_IS_SWITCH, AOM_HAVE_IMG_FMT_NV12,
+    AOM_IMAGE_ABI_VERSION, AOM_IMG_FMT_HIGHBITDEPTH, AOM_IMG_FMT_PLANAR, AOM_IMG_FMT_UV_FLIP,
+    AOM_MAXIMUM_REF_BUFFERS, AOM_MAXIMUM_WORK_BUFFERS, AOM_MAX_SEGMENTS, AOM_MAX_TILE_COLS,
+    AOM_MAX_TILE_ROWS, AOM_PLANE_PACKED, AOM_PLANE_U, AOM_PLANE_V, AOM_PLANE_Y, OBU_METADATA_TYPE,
+    OBU_METADATA_TYPE_OBU_METADATA_TYPE_AOM_RESERVED_0,
+    OBU_METADATA_TYPE_OBU_METADATA_TYPE_HDR_CLL, OBU_METADATA_TYPE_OBU_METADATA_TYPE_HDR_MDCV,
+    OBU_METADATA_TYPE_OBU_METADATA_TYPE_ITUT_T35, OBU_METADATA_TYPE_OBU_METADATA_TYPE_SCALABILITY,
+    OBU_METADATA_TYPE_OBU_METADATA_TYPE_TIMECODE, OBU_TYPE, OBU_TYPE_OBU_FRAME,
+    OBU_TYPE_OBU_FRAME_HEADER, OBU_TYPE_OBU_METADATA, OBU_TYPE_OBU_PADDING,
+    OBU_TYPE_OBU_REDUNDANT_FRAME_HEADER, OBU_TYPE_OBU_SEQUENCE_HEADER,
+    OBU_TYPE_OBU_TEMPORAL_DELIMITER, OBU_TYPE_OBU_TILE_GROUP, OBU_TYPE_OBU_TILE_LIST,
+};
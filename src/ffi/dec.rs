@@ -0,0 +1,60 @@
+//! Decoder side of the bindings: aom_decoder.h, aom_frame_buffer.h and aomdx.h,
+//! plus the items shared with the encoder.
+
+pub use super::aom::{
+    aom_codec_av1_dx, aom_codec_av1_dx_algo, aom_codec_control_type_AOMD_GET_ALTREF_PRESENT,
+    aom_codec_control_type_AOMD_GET_BASE_Q_IDX, aom_codec_control_type_AOMD_GET_FRAME_CORRUPTED,
+    aom_codec_control_type_AOMD_GET_FRAME_FLAGS, aom_codec_control_type_AOMD_GET_FWD_KF_PRESENT,
+    aom_codec_control_type_AOMD_GET_LAST_QUANTIZER,
+    aom_codec_control_type_AOMD_GET_LAST_REF_UPDATES,
+    aom_codec_control_type_AOMD_GET_LAST_REF_USED, aom_codec_control_type_AOMD_GET_ORDER_HINT,
+    aom_codec_control_type_AOMD_GET_SB_SIZE,
+    aom_codec_control_type_AOMD_GET_SCREEN_CONTENT_TOOLS_INFO,
+    aom_codec_control_type_AOMD_GET_SHOW_EXISTING_FRAME_FLAG,
+    aom_codec_control_type_AOMD_GET_SHOW_FRAME_FLAG, aom_codec_control_type_AOMD_GET_STILL_PICTURE,
+    aom_codec_control_type_AOMD_GET_S_FRAME_INFO, aom_codec_control_type_AOMD_GET_TILE_INFO,
+    aom_codec_control_type_AV1D_EXT_TILE_DEBUG, aom_codec_control_type_AV1D_GET_BIT_DEPTH,
+    aom_codec_control_type_AV1D_GET_DISPLAY_SIZE,
+    aom_codec_control_type_AV1D_GET_FRAME_HEADER_INFO, aom_codec_control_type_AV1D_GET_FRAME_SIZE,
+    aom_codec_control_type_AV1D_GET_IMG_FORMAT, aom_codec_control_type_AV1D_GET_TILE_COUNT,
+    aom_codec_control_type_AV1D_GET_TILE_DATA, aom_codec_control_type_AV1D_GET_TILE_SIZE,
+    aom_codec_control_type_AV1D_SET_EXT_REF_PTR, aom_codec_control_type_AV1D_SET_IS_ANNEXB,
+    aom_codec_control_type_AV1D_SET_OPERATING_POINT,
+    aom_codec_control_type_AV1D_SET_OUTPUT_ALL_LAYERS, aom_codec_control_type_AV1D_SET_ROW_MT,
+    aom_codec_control_type_AV1D_SET_SKIP_FILM_GRAIN, aom_codec_control_type_AV1_GET_ACCOUNTING,
+    aom_codec_control_type_AV1_INVERT_TILE_DECODE_ORDER,
+    aom_codec_control_type_AV1_SET_DECODE_TILE_COL, aom_codec_control_type_AV1_SET_DECODE_TILE_ROW,
+    aom_codec_control_type_AV1_SET_INSPECTION_CALLBACK,
+    aom_codec_control_type_AV1_SET_SKIP_LOOP_FILTER, aom_codec_control_type_AV1_SET_TILE_MODE,
+    aom_codec_dec_cfg, aom_codec_dec_cfg_t, aom_codec_dec_init_ver, aom_codec_decode,
+    aom_codec_frame_buffer, aom_codec_frame_buffer_t, aom_codec_get_frame,
+    aom_codec_get_stream_info, aom_codec_peek_stream_info, aom_codec_set_frame_buffer_functions,
+    aom_codec_stream_info, aom_codec_stream_info_t, aom_dec_control_id,
+    aom_dec_control_id_AOMD_GET_ALTREF_PRESENT, aom_dec_control_id_AOMD_GET_BASE_Q_IDX,
+    aom_dec_control_id_AOMD_GET_FRAME_CORRUPTED, aom_dec_control_id_AOMD_GET_FRAME_FLAGS,
+    aom_dec_control_id_AOMD_GET_FWD_KF_PRESENT, aom_dec_control_id_AOMD_GET_LAST_QUANTIZER,
+    aom_dec_control_id_AOMD_GET_LAST_REF_UPDATES, aom_dec_control_id_AOMD_GET_LAST_REF_USED,
+    aom_dec_control_id_AOMD_GET_ORDER_HINT, aom_dec_control_id_AOMD_GET_SB_SIZE,
+    aom_dec_control_id_AOMD_GET_SCREEN_CONTENT_TOOLS_INFO,
+    aom_dec_control_id_AOMD_GET_SHOW_EXISTING_FRAME_FLAG,
+    aom_dec_control_id_AOMD_GET_SHOW_FRAME_FLAG, aom_dec_control_id_AOMD_GET_STILL_PICTURE,
+    aom_dec_control_id_AOMD_GET_S_FRAME_INFO, aom_dec_control_id_AOMD_GET_TILE_INFO,
+    aom_dec_control_id_AV1D_EXT_TILE_DEBUG, aom_dec_control_id_AV1D_GET_BIT_DEPTH,
+    aom_dec_control_id_AV1D_GET_DISPLAY_SIZE, aom_dec_control_id_AV1D_GET_FRAME_HEADER_INFO,
+    aom_dec_control_id_AV1D_GET_FRAME_SIZE, aom_dec_control_id_AV1D_GET_IMG_FORMAT,
+    aom_dec_control_id_AV1D_GET_MI_INFO, aom_dec_control_id_AV1D_GET_TILE_COUNT,
+    aom_dec_control_id_AV1D_GET_TILE_DATA, aom_dec_control_id_AV1D_GET_TILE_SIZE,
+    aom_dec_control_id_AV1D_SET_EXT_REF_PTR, aom_dec_control_id_AV1D_SET_IS_ANNEXB,
+    aom_dec_control_id_AV1D_SET_OPERATING_POINT, aom_dec_control_id_AV1D_SET_OUTPUT_ALL_LAYERS,
+    aom_dec_control_id_AV1D_SET_ROW_MT, aom_dec_control_id_AV1D_SET_SKIP_FILM_GRAIN,
+    aom_dec_control_id_AV1_GET_ACCOUNTING, aom_dec_control_id_AV1_INVERT_TILE_DECODE_ORDER,
+    aom_dec_control_id_AV1_SET_BYTE_ALIGNMENT, aom_dec_control_id_AV1_SET_DECODE_TILE_COL,
+    aom_dec_control_id_AV1_SET_DECODE_TILE_ROW, aom_dec_control_id_AV1_SET_INSPECTION_CALLBACK,
+    aom_dec_control_id_AV1_SET_SKIP_LOOP_FILTER, aom_dec_control_id_AV1_SET_TILE_MODE,
+    aom_get_frame_buffer_cb_fn_t, aom_inspect_cb, aom_inspect_init,
+    aom_release_frame_buffer_cb_fn_t, aom_s_frame_info, aom_screen_content_tools_info,
+    aom_still_picture_info, aom_tile_data, aom_tile_info, av1_ext_ref_frame, av1_ext_ref_frame_t,
+    av1_ref_frame, av1_ref_frame_t, Accounting, Av1DecodeReturn,
+    AOM_CODEC_CAP_EXTERNAL_FRAME_BUFFER, AOM_DECODER_ABI_VERSION,
+};
+pub use super::common::*;
@@ -0,0 +1,314 @@
+//! Encoder side of the bindings: aom_encoder.h, aomcx.h and the
+//! encoder parts of aom_external_partition.h, plus the items shared with the decoder.
+
+pub use super::aom::{
+    aom_active_map, aom_active_map_t, aom_codec_av1_cx, aom_codec_av1_cx_algo,
+    aom_codec_control_type_AOME_GET_LAST_QUANTIZER,
+    aom_codec_control_type_AOME_GET_LAST_QUANTIZER_64,
+    aom_codec_control_type_AOME_GET_LOOPFILTER_LEVEL, aom_codec_control_type_AOME_SET_ACTIVEMAP,
+    aom_codec_control_type_AOME_SET_ARNR_MAXFRAMES, aom_codec_control_type_AOME_SET_ARNR_STRENGTH,
+    aom_codec_control_type_AOME_SET_CPUUSED, aom_codec_control_type_AOME_SET_CQ_LEVEL,
+    aom_codec_control_type_AOME_SET_ENABLEAUTOALTREF,
+    aom_codec_control_type_AOME_SET_ENABLEAUTOBWDREF,
+    aom_codec_control_type_AOME_SET_MAX_INTER_BITRATE_PCT,
+    aom_codec_control_type_AOME_SET_MAX_INTRA_BITRATE_PCT,
+    aom_codec_control_type_AOME_SET_NUMBER_SPATIAL_LAYERS, aom_codec_control_type_AOME_SET_ROI_MAP,
+    aom_codec_control_type_AOME_SET_SCALEMODE, aom_codec_control_type_AOME_SET_SHARPNESS,
+    aom_codec_control_type_AOME_SET_SPATIAL_LAYER_ID,
+    aom_codec_control_type_AOME_SET_STATIC_THRESHOLD, aom_codec_control_type_AOME_SET_TUNING,
+    aom_codec_control_type_AOME_USE_REFERENCE, aom_codec_control_type_AV1E_ENABLE_EXT_TILE_DEBUG,
+    aom_codec_control_type_AV1E_ENABLE_MOTION_VECTOR_UNIT_TEST,
+    aom_codec_control_type_AV1E_ENABLE_RATE_GUIDE_DELTAQ,
+    aom_codec_control_type_AV1E_ENABLE_SB_MULTIPASS_UNIT_TEST,
+    aom_codec_control_type_AV1E_ENABLE_SB_QP_SWEEP, aom_codec_control_type_AV1E_GET_ACTIVEMAP,
+    aom_codec_control_type_AV1E_GET_BASELINE_GF_INTERVAL,
+    aom_codec_control_type_AV1E_GET_LUMA_CDEF_STRENGTH,
+    aom_codec_control_type_AV1E_GET_NUM_OPERATING_POINTS,
+    aom_codec_control_type_AV1E_GET_SEQ_LEVEL_IDX,
+    aom_codec_control_type_AV1E_GET_TARGET_SEQ_LEVEL_IDX,
+    aom_codec_control_type_AV1E_SET_ALLOW_REF_FRAME_MVS,
+    aom_codec_control_type_AV1E_SET_ALLOW_WARPED_MOTION, aom_codec_control_type_AV1E_SET_AQ_MODE,
+    aom_codec_control_type_AV1E_SET_AUTO_INTRA_TOOLS_OFF,
+    aom_codec_control_type_AV1E_SET_BITRATE_ONE_PASS_CBR,
+    aom_codec_control_type_AV1E_SET_CDF_UPDATE_MODE,
+    aom_codec_control_type_AV1E_SET_CHROMA_SAMPLE_POSITION,
+    aom_codec_control_type_AV1E_SET_CHROMA_SUBSAMPLING_X,
+    aom_codec_control_type_AV1E_SET_CHROMA_SUBSAMPLING_Y,
+    aom_codec_control_type_AV1E_SET_COEFF_COST_UPD_FREQ,
+    aom_codec_control_type_AV1E_SET_COLOR_PRIMARIES, aom_codec_control_type_AV1E_SET_COLOR_RANGE,
+    aom_codec_control_type_AV1E_SET_DELTALF_MODE, aom_codec_control_type_AV1E_SET_DELTAQ_MODE,
+    aom_codec_control_type_AV1E_SET_DELTAQ_STRENGTH,
+    aom_codec_control_type_AV1E_SET_DENOISE_BLOCK_SIZE,
+    aom_codec_control_type_AV1E_SET_DENOISE_NOISE_LEVEL,
+    aom_codec_control_type_AV1E_SET_DISABLE_TRELLIS_QUANT,
+    aom_codec_control_type_AV1E_SET_DV_COST_UPD_FREQ,
+    aom_codec_control_type_AV1E_SET_ENABLE_1TO4_PARTITIONS,
+    aom_codec_control_type_AV1E_SET_ENABLE_AB_PARTITIONS,
+    aom_codec_control_type_AV1E_SET_ENABLE_ANGLE_DELTA,
+    aom_codec_control_type_AV1E_SET_ENABLE_CDEF, aom_codec_control_type_AV1E_SET_ENABLE_CFL_INTRA,
+    aom_codec_control_type_AV1E_SET_ENABLE_CHROMA_DELTAQ,
+    aom_codec_control_type_AV1E_SET_ENABLE_DIAGONAL_INTRA,
+    aom_codec_control_type_AV1E_SET_ENABLE_DIFF_WTD_COMP,
+    aom_codec_control_type_AV1E_SET_ENABLE_DIRECTIONAL_INTRA,
+    aom_codec_control_type_AV1E_SET_ENABLE_DIST_8X8,
+    aom_codec_control_type_AV1E_SET_ENABLE_DIST_WTD_COMP,
+    aom_codec_control_type_AV1E_SET_ENABLE_DNL_DENOISING,
+    aom_codec_control_type_AV1E_SET_ENABLE_DUAL_FILTER,
+    aom_codec_control_type_AV1E_SET_ENABLE_FILTER_INTRA,
+    aom_codec_control_type_AV1E_SET_ENABLE_FLIP_IDTX,
+    aom_codec_control_type_AV1E_SET_ENABLE_GLOBAL_MOTION,
+    aom_codec_control_type_AV1E_SET_ENABLE_INTERINTER_WEDGE,
+    aom_codec_control_type_AV1E_SET_ENABLE_INTERINTRA_COMP,
+    aom_codec_control_type_AV1E_SET_ENABLE_INTERINTRA_WEDGE,
+    aom_codec_control_type_AV1E_SET_ENABLE_INTRABC,
+    aom_codec_control_type_AV1E_SET_ENABLE_INTRA_EDGE_FILTER,
+    aom_codec_control_type_AV1E_SET_ENABLE_KEYFRAME_FILTERING,
+    aom_codec_control_type_AV1E_SET_ENABLE_MASKED_COMP,
+    aom_codec_control_type_AV1E_SET_ENABLE_OBMC,
+    aom_codec_control_type_AV1E_SET_ENABLE_ONESIDED_COMP,
+    aom_codec_control_type_AV1E_SET_ENABLE_ORDER_HINT,
+    aom_codec_control_type_AV1E_SET_ENABLE_OVERLAY,
+    aom_codec_control_type_AV1E_SET_ENABLE_PAETH_INTRA,
+    aom_codec_control_type_AV1E_SET_ENABLE_PALETTE, aom_codec_control_type_AV1E_SET_ENABLE_QM,
+    aom_codec_control_type_AV1E_SET_ENABLE_RECT_PARTITIONS,
+    aom_codec_control_type_AV1E_SET_ENABLE_RECT_TX,
+    aom_codec_control_type_AV1E_SET_ENABLE_REF_FRAME_MVS,
+    aom_codec_control_type_AV1E_SET_ENABLE_RESTORATION,
+    aom_codec_control_type_AV1E_SET_ENABLE_SMOOTH_INTERINTRA,
+    aom_codec_control_type_AV1E_SET_ENABLE_SMOOTH_INTRA,
+    aom_codec_control_type_AV1E_SET_ENABLE_SUPERRES,
+    aom_codec_control_type_AV1E_SET_ENABLE_TPL_MODEL, aom_codec_control_type_AV1E_SET_ENABLE_TX64,
+    aom_codec_control_type_AV1E_SET_ENABLE_TX_SIZE_SEARCH,
+    aom_codec_control_type_AV1E_SET_ENABLE_WARPED_MOTION,
+    aom_codec_control_type_AV1E_SET_ERROR_RESILIENT_MODE,
+    aom_codec_control_type_AV1E_SET_EXTERNAL_PARTITION,
+    aom_codec_control_type_AV1E_SET_FILM_GRAIN_TABLE,
+    aom_codec_control_type_AV1E_SET_FILM_GRAIN_TEST_VECTOR,
+    aom_codec_control_type_AV1E_SET_FORCE_VIDEO_MODE, aom_codec_control_type_AV1E_SET_FP_MT,
+    aom_codec_control_type_AV1E_SET_FP_MT_UNIT_TEST,
+    aom_codec_control_type_AV1E_SET_FRAME_PARALLEL_DECODING,
+    aom_codec_control_type_AV1E_SET_FRAME_PERIODIC_BOOST,
+    aom_codec_control_type_AV1E_SET_GF_CBR_BOOST_PCT,
+    aom_codec_control_type_AV1E_SET_GF_MAX_PYRAMID_HEIGHT,
+    aom_codec_control_type_AV1E_SET_GF_MIN_PYRAMID_HEIGHT,
+    aom_codec_control_type_AV1E_SET_INTER_DCT_ONLY, aom_codec_control_type_AV1E_SET_INTRA_DCT_ONLY,
+    aom_codec_control_type_AV1E_SET_INTRA_DEFAULT_TX_ONLY,
+    aom_codec_control_type_AV1E_SET_LOOPFILTER_CONTROL, aom_codec_control_type_AV1E_SET_LOSSLESS,
+    aom_codec_control_type_AV1E_SET_MATRIX_COEFFICIENTS,
+    aom_codec_control_type_AV1E_SET_MAX_CONSEC_FRAME_DROP_CBR,
+    aom_codec_control_type_AV1E_SET_MAX_GF_INTERVAL,
+    aom_codec_control_type_AV1E_SET_MAX_PARTITION_SIZE,
+    aom_codec_control_type_AV1E_SET_MAX_REFERENCE_FRAMES, aom_codec_control_type_AV1E_SET_MIN_CR,
+    aom_codec_control_type_AV1E_SET_MIN_GF_INTERVAL,
+    aom_codec_control_type_AV1E_SET_MIN_PARTITION_SIZE,
+    aom_codec_control_type_AV1E_SET_MODE_COST_UPD_FREQ, aom_codec_control_type_AV1E_SET_MTU,
+    aom_codec_control_type_AV1E_SET_MV_COST_UPD_FREQ,
+    aom_codec_control_type_AV1E_SET_NOISE_SENSITIVITY, aom_codec_control_type_AV1E_SET_NUM_TG,
+    aom_codec_control_type_AV1E_SET_PARTITION_INFO_PATH, aom_codec_control_type_AV1E_SET_QM_MAX,
+    aom_codec_control_type_AV1E_SET_QM_MIN, aom_codec_control_type_AV1E_SET_QM_U,
+    aom_codec_control_type_AV1E_SET_QM_V, aom_codec_control_type_AV1E_SET_QM_Y,
+    aom_codec_control_type_AV1E_SET_QUANTIZER_ONE_PASS,
+    aom_codec_control_type_AV1E_SET_QUANT_B_ADAPT,
+    aom_codec_control_type_AV1E_SET_RATE_DISTRIBUTION_INFO,
+    aom_codec_control_type_AV1E_SET_REDUCED_REFERENCE_SET,
+    aom_codec_control_type_AV1E_SET_REDUCED_TX_TYPE_SET,
+    aom_codec_control_type_AV1E_SET_RENDER_SIZE, aom_codec_control_type_AV1E_SET_ROW_MT,
+    aom_codec_control_type_AV1E_SET_RTC_EXTERNAL_RC,
+    aom_codec_control_type_AV1E_SET_SINGLE_TILE_DECODING,
+    aom_codec_control_type_AV1E_SET_SKIP_POSTPROC_FILTERING,
+    aom_codec_control_type_AV1E_SET_SUPERBLOCK_SIZE,
+    aom_codec_control_type_AV1E_SET_SVC_FRAME_DROP_MODE,
+    aom_codec_control_type_AV1E_SET_SVC_LAYER_ID, aom_codec_control_type_AV1E_SET_SVC_PARAMS,
+    aom_codec_control_type_AV1E_SET_SVC_REF_FRAME_COMP_PRED,
+    aom_codec_control_type_AV1E_SET_SVC_REF_FRAME_CONFIG,
+    aom_codec_control_type_AV1E_SET_S_FRAME_MODE,
+    aom_codec_control_type_AV1E_SET_TARGET_SEQ_LEVEL_IDX,
+    aom_codec_control_type_AV1E_SET_TIER_MASK, aom_codec_control_type_AV1E_SET_TILE_COLUMNS,
+    aom_codec_control_type_AV1E_SET_TILE_ROWS, aom_codec_control_type_AV1E_SET_TIMING_INFO_TYPE,
+    aom_codec_control_type_AV1E_SET_TRANSFER_CHARACTERISTICS,
+    aom_codec_control_type_AV1E_SET_TUNE_CONTENT,
+    aom_codec_control_type_AV1E_SET_VBR_CORPUS_COMPLEXITY_LAP,
+    aom_codec_control_type_AV1E_SET_VMAF_MODEL_PATH, aom_codec_cx_pkt,
+    aom_codec_cx_pkt__bindgen_ty_1, aom_codec_cx_pkt__bindgen_ty_1__bindgen_ty_1,
+    aom_codec_cx_pkt__bindgen_ty_1_aom_psnr_pkt, aom_codec_cx_pkt_kind,
+    aom_codec_cx_pkt_kind_AOM_CODEC_CUSTOM_PKT, aom_codec_cx_pkt_kind_AOM_CODEC_CX_FRAME_PKT,
+    aom_codec_cx_pkt_kind_AOM_CODEC_FPMB_STATS_PKT, aom_codec_cx_pkt_kind_AOM_CODEC_PSNR_PKT,
+    aom_codec_cx_pkt_kind_AOM_CODEC_STATS_PKT, aom_codec_cx_pkt_t, aom_codec_enc_cfg,
+    aom_codec_enc_cfg_t, aom_codec_enc_config_default, aom_codec_enc_config_set,
+    aom_codec_enc_init_ver, aom_codec_encode, aom_codec_er_flags_t, aom_codec_frame_flags_t,
+    aom_codec_get_cx_data, aom_codec_get_global_headers, aom_codec_get_preview_frame,
+    aom_codec_pts_t, aom_codec_set_cx_data_buf, aom_dist_metric,
+    aom_dist_metric_AOM_DIST_METRIC_PSNR, aom_dist_metric_AOM_DIST_METRIC_QM_PSNR,
+    aom_enc_frame_flags_t, aom_enc_pass, aom_enc_pass_AOM_RC_FIRST_PASS,
+    aom_enc_pass_AOM_RC_LAST_PASS, aom_enc_pass_AOM_RC_ONE_PASS, aom_enc_pass_AOM_RC_SECOND_PASS,
+    aom_enc_pass_AOM_RC_THIRD_PASS, aom_ext_part_config, aom_ext_part_config_t,
+    aom_ext_part_create_model_fn_t, aom_ext_part_decision_mode,
+    aom_ext_part_decision_mode_AOM_EXT_PART_RECURSIVE,
+    aom_ext_part_decision_mode_AOM_EXT_PART_WHOLE_TREE, aom_ext_part_delete_model_fn_t,
+    aom_ext_part_funcs, aom_ext_part_funcs_t, aom_ext_part_get_decision_fn_t, aom_ext_part_model_t,
+    aom_ext_part_send_features_fn_t, aom_ext_part_send_partition_stats_fn_t, aom_ext_part_status,
+    aom_ext_part_status_AOM_EXT_PART_ERROR, aom_ext_part_status_AOM_EXT_PART_OK,
+    aom_ext_part_status_AOM_EXT_PART_TEST, aom_fixed_buf, aom_fixed_buf_t, aom_kf_mode,
+    aom_kf_mode_AOM_KF_AUTO, aom_kf_mode_AOM_KF_DISABLED, aom_kf_mode_AOM_KF_FIXED,
+    aom_partition_decision, aom_partition_decision_t, aom_partition_features,
+    aom_partition_features_ab, aom_partition_features_ab_t, aom_partition_features_before_none,
+    aom_partition_features_before_none_t, aom_partition_features_none,
+    aom_partition_features_none_t, aom_partition_features_rect, aom_partition_features_rect_t,
+    aom_partition_features_split, aom_partition_features_split_t, aom_partition_features_t,
+    aom_partition_stats, aom_partition_stats_t, aom_rc_mode, aom_rc_mode_AOM_CBR,
+    aom_rc_mode_AOM_CQ, aom_rc_mode_AOM_Q, aom_rc_mode_AOM_VBR, aom_roi_map, aom_roi_map_t,
+    aom_sb_features, aom_sb_features_t, aom_sb_simple_motion_features,
+    aom_sb_simple_motion_features_t, aom_sb_tpl_features, aom_sb_tpl_features_t, aom_scaling_mode,
+    aom_scaling_mode_1d, aom_scaling_mode_1d_AOME_FOURFIVE, aom_scaling_mode_1d_AOME_NORMAL,
+    aom_scaling_mode_1d_AOME_ONEEIGHT, aom_scaling_mode_1d_AOME_ONEFOUR,
+    aom_scaling_mode_1d_AOME_ONETHREE, aom_scaling_mode_1d_AOME_ONETWO,
+    aom_scaling_mode_1d_AOME_THREEFIVE, aom_scaling_mode_1d_AOME_THREEFOUR,
+    aom_scaling_mode_1d_AOME_TWOTHREE, aom_scaling_mode_t, aom_superblock_size,
+    aom_superblock_size_AOM_SUPERBLOCK_SIZE_128X128, aom_superblock_size_AOM_SUPERBLOCK_SIZE_64X64,
+    aom_superblock_size_AOM_SUPERBLOCK_SIZE_DYNAMIC, aom_superres_mode,
+    aom_superres_mode_AOM_SUPERRES_AUTO, aom_superres_mode_AOM_SUPERRES_FIXED,
+    aom_superres_mode_AOM_SUPERRES_NONE, aom_superres_mode_AOM_SUPERRES_QTHRESH,
+    aom_superres_mode_AOM_SUPERRES_RANDOM, aom_svc_layer_id, aom_svc_layer_id_t, aom_svc_params,
+    aom_svc_params_t, aom_svc_ref_frame_comp_pred, aom_svc_ref_frame_comp_pred_t,
+    aom_svc_ref_frame_config, aom_svc_ref_frame_config_t, aom_timing_info_type_t,
+    aom_timing_info_type_t_AOM_TIMING_DEC_MODEL, aom_timing_info_type_t_AOM_TIMING_EQUAL,
+    aom_timing_info_type_t_AOM_TIMING_UNSPECIFIED, aom_tune_content,
+    aom_tune_content_AOM_CONTENT_DEFAULT, aom_tune_content_AOM_CONTENT_FILM,
+    aom_tune_content_AOM_CONTENT_INVALID, aom_tune_content_AOM_CONTENT_SCREEN, aom_tune_metric,
+    aom_tune_metric_AOM_TUNE_BUTTERAUGLI, aom_tune_metric_AOM_TUNE_PSNR,
+    aom_tune_metric_AOM_TUNE_SSIM, aom_tune_metric_AOM_TUNE_VMAF_MAX_GAIN,
+    aom_tune_metric_AOM_TUNE_VMAF_NEG_MAX_GAIN, aom_tune_metric_AOM_TUNE_VMAF_SALIENCY_MAP,
+    aom_tune_metric_AOM_TUNE_VMAF_WITHOUT_PREPROCESSING,
+    aom_tune_metric_AOM_TUNE_VMAF_WITH_PREPROCESSING, aome_enc_control_id,
+    aome_enc_control_id_AOME_GET_LAST_QUANTIZER, aome_enc_control_id_AOME_GET_LAST_QUANTIZER_64,
+    aome_enc_control_id_AOME_GET_LOOPFILTER_LEVEL, aome_enc_control_id_AOME_SET_ACTIVEMAP,
+    aome_enc_control_id_AOME_SET_ARNR_MAXFRAMES, aome_enc_control_id_AOME_SET_ARNR_STRENGTH,
+    aome_enc_control_id_AOME_SET_CPUUSED, aome_enc_control_id_AOME_SET_CQ_LEVEL,
+    aome_enc_control_id_AOME_SET_ENABLEAUTOALTREF, aome_enc_control_id_AOME_SET_ENABLEAUTOBWDREF,
+    aome_enc_control_id_AOME_SET_MAX_INTRA_BITRATE_PCT,
+    aome_enc_control_id_AOME_SET_NUMBER_SPATIAL_LAYERS, aome_enc_control_id_AOME_SET_ROI_MAP,
+    aome_enc_control_id_AOME_SET_SCALEMODE, aome_enc_control_id_AOME_SET_SHARPNESS,
+    aome_enc_control_id_AOME_SET_SPATIAL_LAYER_ID, aome_enc_control_id_AOME_SET_STATIC_THRESHOLD,
+    aome_enc_control_id_AOME_SET_TUNING, aome_enc_control_id_AOME_USE_REFERENCE,
+    aome_enc_control_id_AV1E_ENABLE_EXT_TILE_DEBUG,
+    aome_enc_control_id_AV1E_ENABLE_MOTION_VECTOR_UNIT_TEST,
+    aome_enc_control_id_AV1E_ENABLE_RATE_GUIDE_DELTAQ,
+    aome_enc_control_id_AV1E_ENABLE_SB_MULTIPASS_UNIT_TEST,
+    aome_enc_control_id_AV1E_ENABLE_SB_QP_SWEEP, aome_enc_control_id_AV1E_GET_ACTIVEMAP,
+    aome_enc_control_id_AV1E_GET_BASELINE_GF_INTERVAL,
+    aome_enc_control_id_AV1E_GET_LUMA_CDEF_STRENGTH,
+    aome_enc_control_id_AV1E_GET_NUM_OPERATING_POINTS, aome_enc_control_id_AV1E_GET_SEQ_LEVEL_IDX,
+    aome_enc_control_id_AV1E_GET_TARGET_SEQ_LEVEL_IDX,
+    aome_enc_control_id_AV1E_SET_ALLOW_REF_FRAME_MVS,
+    aome_enc_control_id_AV1E_SET_ALLOW_WARPED_MOTION, aome_enc_control_id_AV1E_SET_AQ_MODE,
+    aome_enc_control_id_AV1E_SET_AUTO_INTRA_TOOLS_OFF,
+    aome_enc_control_id_AV1E_SET_BITRATE_ONE_PASS_CBR,
+    aome_enc_control_id_AV1E_SET_CDF_UPDATE_MODE,
+    aome_enc_control_id_AV1E_SET_CHROMA_SAMPLE_POSITION,
+    aome_enc_control_id_AV1E_SET_CHROMA_SUBSAMPLING_X,
+    aome_enc_control_id_AV1E_SET_CHROMA_SUBSAMPLING_Y,
+    aome_enc_control_id_AV1E_SET_COEFF_COST_UPD_FREQ, aome_enc_control_id_AV1E_SET_COLOR_PRIMARIES,
+    aome_enc_control_id_AV1E_SET_COLOR_RANGE, aome_enc_control_id_AV1E_SET_DELTALF_MODE,
+    aome_enc_control_id_AV1E_SET_DELTAQ_MODE, aome_enc_control_id_AV1E_SET_DELTAQ_STRENGTH,
+    aome_enc_control_id_AV1E_SET_DENOISE_BLOCK_SIZE,
+    aome_enc_control_id_AV1E_SET_DENOISE_NOISE_LEVEL,
+    aome_enc_control_id_AV1E_SET_DISABLE_TRELLIS_QUANT,
+    aome_enc_control_id_AV1E_SET_DV_COST_UPD_FREQ,
+    aome_enc_control_id_AV1E_SET_ENABLE_1TO4_PARTITIONS,
+    aome_enc_control_id_AV1E_SET_ENABLE_AB_PARTITIONS,
+    aome_enc_control_id_AV1E_SET_ENABLE_ANGLE_DELTA, aome_enc_control_id_AV1E_SET_ENABLE_CDEF,
+    aome_enc_control_id_AV1E_SET_ENABLE_CFL_INTRA,
+    aome_enc_control_id_AV1E_SET_ENABLE_CHROMA_DELTAQ,
+    aome_enc_control_id_AV1E_SET_ENABLE_DIAGONAL_INTRA,
+    aome_enc_control_id_AV1E_SET_ENABLE_DIFF_WTD_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_DIRECTIONAL_INTRA,
+    aome_enc_control_id_AV1E_SET_ENABLE_DIST_WTD_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_DNL_DENOISING,
+    aome_enc_control_id_AV1E_SET_ENABLE_DUAL_FILTER,
+    aome_enc_control_id_AV1E_SET_ENABLE_FILTER_INTRA,
+    aome_enc_control_id_AV1E_SET_ENABLE_FLIP_IDTX,
+    aome_enc_control_id_AV1E_SET_ENABLE_GLOBAL_MOTION,
+    aome_enc_control_id_AV1E_SET_ENABLE_INTERINTER_WEDGE,
+    aome_enc_control_id_AV1E_SET_ENABLE_INTERINTRA_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_INTERINTRA_WEDGE,
+    aome_enc_control_id_AV1E_SET_ENABLE_INTRABC,
+    aome_enc_control_id_AV1E_SET_ENABLE_INTRA_EDGE_FILTER,
+    aome_enc_control_id_AV1E_SET_ENABLE_KEYFRAME_FILTERING,
+    aome_enc_control_id_AV1E_SET_ENABLE_MASKED_COMP, aome_enc_control_id_AV1E_SET_ENABLE_OBMC,
+    aome_enc_control_id_AV1E_SET_ENABLE_ONESIDED_COMP,
+    aome_enc_control_id_AV1E_SET_ENABLE_ORDER_HINT, aome_enc_control_id_AV1E_SET_ENABLE_OVERLAY,
+    aome_enc_control_id_AV1E_SET_ENABLE_PAETH_INTRA, aome_enc_control_id_AV1E_SET_ENABLE_PALETTE,
+    aome_enc_control_id_AV1E_SET_ENABLE_QM, aome_enc_control_id_AV1E_SET_ENABLE_RECT_PARTITIONS,
+    aome_enc_control_id_AV1E_SET_ENABLE_RECT_TX, aome_enc_control_id_AV1E_SET_ENABLE_REF_FRAME_MVS,
+    aome_enc_control_id_AV1E_SET_ENABLE_RESTORATION,
+    aome_enc_control_id_AV1E_SET_ENABLE_SMOOTH_INTERINTRA,
+    aome_enc_control_id_AV1E_SET_ENABLE_SMOOTH_INTRA, aome_enc_control_id_AV1E_SET_ENABLE_SUPERRES,
+    aome_enc_control_id_AV1E_SET_ENABLE_TPL_MODEL, aome_enc_control_id_AV1E_SET_ENABLE_TX64,
+    aome_enc_control_id_AV1E_SET_ENABLE_TX_SIZE_SEARCH,
+    aome_enc_control_id_AV1E_SET_ENABLE_WARPED_MOTION,
+    aome_enc_control_id_AV1E_SET_ERROR_RESILIENT_MODE,
+    aome_enc_control_id_AV1E_SET_EXTERNAL_PARTITION, aome_enc_control_id_AV1E_SET_FILM_GRAIN_TABLE,
+    aome_enc_control_id_AV1E_SET_FILM_GRAIN_TEST_VECTOR,
+    aome_enc_control_id_AV1E_SET_FORCE_VIDEO_MODE, aome_enc_control_id_AV1E_SET_FP_MT,
+    aome_enc_control_id_AV1E_SET_FP_MT_UNIT_TEST,
+    aome_enc_control_id_AV1E_SET_FRAME_PARALLEL_DECODING,
+    aome_enc_control_id_AV1E_SET_FRAME_PERIODIC_BOOST,
+    aome_enc_control_id_AV1E_SET_GF_CBR_BOOST_PCT,
+    aome_enc_control_id_AV1E_SET_GF_MAX_PYRAMID_HEIGHT,
+    aome_enc_control_id_AV1E_SET_GF_MIN_PYRAMID_HEIGHT,
+    aome_enc_control_id_AV1E_SET_INTER_DCT_ONLY, aome_enc_control_id_AV1E_SET_INTRA_DCT_ONLY,
+    aome_enc_control_id_AV1E_SET_INTRA_DEFAULT_TX_ONLY,
+    aome_enc_control_id_AV1E_SET_LOOPFILTER_CONTROL, aome_enc_control_id_AV1E_SET_LOSSLESS,
+    aome_enc_control_id_AV1E_SET_MATRIX_COEFFICIENTS,
+    aome_enc_control_id_AV1E_SET_MAX_CONSEC_FRAME_DROP_CBR,
+    aome_enc_control_id_AV1E_SET_MAX_GF_INTERVAL,
+    aome_enc_control_id_AV1E_SET_MAX_INTER_BITRATE_PCT,
+    aome_enc_control_id_AV1E_SET_MAX_PARTITION_SIZE,
+    aome_enc_control_id_AV1E_SET_MAX_REFERENCE_FRAMES, aome_enc_control_id_AV1E_SET_MIN_CR,
+    aome_enc_control_id_AV1E_SET_MIN_GF_INTERVAL, aome_enc_control_id_AV1E_SET_MIN_PARTITION_SIZE,
+    aome_enc_control_id_AV1E_SET_MODE_COST_UPD_FREQ, aome_enc_control_id_AV1E_SET_MTU,
+    aome_enc_control_id_AV1E_SET_MV_COST_UPD_FREQ, aome_enc_control_id_AV1E_SET_NOISE_SENSITIVITY,
+    aome_enc_control_id_AV1E_SET_NUM_TG, aome_enc_control_id_AV1E_SET_PARTITION_INFO_PATH,
+    aome_enc_control_id_AV1E_SET_QM_MAX, aome_enc_control_id_AV1E_SET_QM_MIN,
+    aome_enc_control_id_AV1E_SET_QM_U, aome_enc_control_id_AV1E_SET_QM_V,
+    aome_enc_control_id_AV1E_SET_QM_Y, aome_enc_control_id_AV1E_SET_QUANTIZER_ONE_PASS,
+    aome_enc_control_id_AV1E_SET_QUANT_B_ADAPT,
+    aome_enc_control_id_AV1E_SET_RATE_DISTRIBUTION_INFO,
+    aome_enc_control_id_AV1E_SET_REDUCED_REFERENCE_SET,
+    aome_enc_control_id_AV1E_SET_REDUCED_TX_TYPE_SET, aome_enc_control_id_AV1E_SET_RENDER_SIZE,
+    aome_enc_control_id_AV1E_SET_ROW_MT, aome_enc_control_id_AV1E_SET_RTC_EXTERNAL_RC,
+    aome_enc_control_id_AV1E_SET_SINGLE_TILE_DECODING,
+    aome_enc_control_id_AV1E_SET_SKIP_POSTPROC_FILTERING,
+    aome_enc_control_id_AV1E_SET_SUPERBLOCK_SIZE, aome_enc_control_id_AV1E_SET_SVC_FRAME_DROP_MODE,
+    aome_enc_control_id_AV1E_SET_SVC_LAYER_ID, aome_enc_control_id_AV1E_SET_SVC_PARAMS,
+    aome_enc_control_id_AV1E_SET_SVC_REF_FRAME_COMP_PRED,
+    aome_enc_control_id_AV1E_SET_SVC_REF_FRAME_CONFIG, aome_enc_control_id_AV1E_SET_S_FRAME_MODE,
+    aome_enc_control_id_AV1E_SET_TARGET_SEQ_LEVEL_IDX, aome_enc_control_id_AV1E_SET_TIER_MASK,
+    aome_enc_control_id_AV1E_SET_TILE_COLUMNS, aome_enc_control_id_AV1E_SET_TILE_ROWS,
+    aome_enc_control_id_AV1E_SET_TIMING_INFO_TYPE,
+    aome_enc_control_id_AV1E_SET_TRANSFER_CHARACTERISTICS,
+    aome_enc_control_id_AV1E_SET_TUNE_CONTENT,
+    aome_enc_control_id_AV1E_SET_VBR_CORPUS_COMPLEXITY_LAP,
+    aome_enc_control_id_AV1E_SET_VMAF_MODEL_PATH, cfg_options, cfg_options_t, AOM_CODEC_CAP_PSNR,
+    AOM_CODEC_USE_HIGHBITDEPTH, AOM_CODEC_USE_PSNR, AOM_EFLAG_ERROR_RESILIENT, AOM_EFLAG_FORCE_KF,
+    AOM_EFLAG_NO_REF_ARF, AOM_EFLAG_NO_REF_ARF2, AOM_EFLAG_NO_REF_BWD, AOM_EFLAG_NO_REF_FRAME_MVS,
+    AOM_EFLAG_NO_REF_GF, AOM_EFLAG_NO_REF_LAST, AOM_EFLAG_NO_REF_LAST2, AOM_EFLAG_NO_REF_LAST3,
+    AOM_EFLAG_NO_UPD_ARF, AOM_EFLAG_NO_UPD_ENTROPY, AOM_EFLAG_NO_UPD_GF, AOM_EFLAG_NO_UPD_LAST,
+    AOM_EFLAG_SET_PRIMARY_REF_NONE, AOM_EFLAG_SET_S_FRAME, AOM_ENCODER_ABI_VERSION,
+    AOM_ERROR_RESILIENT_DEFAULT, AOM_EXT_PART_ABI_VERSION, AOM_EXT_PART_FEATURE_ID,
+    AOM_EXT_PART_FEATURE_ID_AOM_EXT_PART_FEATURE_AFTER_AB,
+    AOM_EXT_PART_FEATURE_ID_AOM_EXT_PART_FEATURE_AFTER_NONE,
+    AOM_EXT_PART_FEATURE_ID_AOM_EXT_PART_FEATURE_AFTER_NONE_PART2,
+    AOM_EXT_PART_FEATURE_ID_AOM_EXT_PART_FEATURE_AFTER_RECT,
+    AOM_EXT_PART_FEATURE_ID_AOM_EXT_PART_FEATURE_AFTER_SPLIT,
+    AOM_EXT_PART_FEATURE_ID_AOM_EXT_PART_FEATURE_AFTER_SPLIT_PART2,
+    AOM_EXT_PART_FEATURE_ID_AOM_EXT_PART_FEATURE_BEFORE_NONE,
+    AOM_EXT_PART_FEATURE_ID_AOM_EXT_PART_FEATURE_BEFORE_NONE_PART2, AOM_EXT_PART_SIZE_DIRECT_SPLIT,
+    AOM_EXT_PART_SIZE_PRUNE_4_WAY, AOM_EXT_PART_SIZE_PRUNE_AB, AOM_EXT_PART_SIZE_PRUNE_NONE,
+    AOM_EXT_PART_SIZE_PRUNE_PART, AOM_EXT_PART_SIZE_PRUNE_RECT, AOM_EXT_PART_SIZE_TERM_NONE,
+    AOM_EXT_PART_SIZE_TERM_SPLIT, AOM_MAX_LAYERS, AOM_MAX_SS_LAYERS, AOM_MAX_TS_LAYERS,
+    AOM_SVC_FRAME_DROP_MODE, AOM_SVC_FRAME_DROP_MODE_AOM_FULL_SUPERFRAME_DROP,
+    AOM_SVC_FRAME_DROP_MODE_AOM_LAYER_DROP, AOM_USAGE_ALL_INTRA, AOM_USAGE_GOOD_QUALITY,
+    AOM_USAGE_REALTIME, MAX_TILE_HEIGHTS, MAX_TILE_WIDTHS,
+};
+pub use super::common::*;
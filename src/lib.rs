@@ -1,6 +1,9 @@
 #![allow(warnings)]
 pub mod ffi {
     pub mod aom;
+    mod common;
+    pub mod dec;
+    pub mod enc;
     pub mod errors;
 }
 
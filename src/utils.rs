@@ -1,19 +1,51 @@
-use crate::aom::{aom_fixed_buf_t, aom_image, aom_img_fmt, aom_img_fmt_AOM_IMG_FMT_I420};
+use crate::aom::{
+    aom_color_range_AOM_CR_FULL_RANGE, aom_fixed_buf_t, aom_image, aom_img_alloc, aom_img_fmt,
+    aom_img_fmt_AOM_IMG_FMT_I420, aom_img_fmt_AOM_IMG_FMT_I422, aom_img_fmt_AOM_IMG_FMT_I444,
+    aom_img_fmt_AOM_IMG_FMT_NV12, aom_img_free, aom_img_plane_height, aom_img_plane_width,
+    aom_matrix_coefficients, AOM_IMG_FMT_HIGHBITDEPTH,
+};
+use crate::core::controls::{ColorMatrix, ColorRange};
+use crate::core::AomError;
 use av_data::frame::FrameBufferConv;
-use av_data::frame::{Frame, MediaKind};
-use av_data::pixel::formats::YUV420;
-use av_data::pixel::Formaton;
-use std::{mem, ptr};
+use av_data::frame::{Frame, FrameType, MediaKind, VideoInfo};
+use av_data::pixel::formats::{YUV420, YUV422, YUV444};
+use av_data::pixel::{
+    Chromaton, ColorModel, Formaton, TrichromaticEncodingSystem, YUVRange, YUVSystem,
+};
+use std::{mem, ptr, slice, sync::Arc};
+
+/// aom_image only has room for Y, U and V planes
+const AOM_MAX_PLANES: usize = 3;
 
 /// Utility function to convert Frame to aom_image
-pub fn img_from_frame(frame: &Frame) -> aom_image {
+///
+/// libaom has no image format with an alpha channel, so frames whose format has alpha
+/// or that carry more than 3 planes are rejected with AOM_CODEC_UNSUP_FEATURE.
+/// Alpha has to be encoded as a separate monochrome stream (this is what AVIF does).
+/// NV12 frames (luma plane plus one plane of interleaved chroma) are passed as
+/// AOM_IMG_FMT_NV12 without conversion.
+/// Grayscale (single plane) frames become a monochrome image: only the luma plane is
+/// set, for an encoder configured with `monochrome`.
+///
+/// The planes of the returned image point into `frame`, but the image doesn't borrow
+/// it. Go through `ImageView`, which ties the image to the frame's lifetime, rather
+/// than calling this directly.
+pub(crate) fn img_from_frame(frame: &Frame) -> Result<aom_image, AomError> {
+    if frame.buf.count() > AOM_MAX_PLANES {
+        return Err(AomError::UnsupFeature);
+    }
+
     // create an uninitialized aom_image
     let mut img: aom_image = unsafe { mem::zeroed() };
 
     // if Frame is video
     if let MediaKind::Video(ref v) = frame.kind {
+        if v.format.has_alpha() {
+            return Err(AomError::UnsupFeature);
+        }
+
         // puts video to aom_image in a mutable img variable
-        map_formaton(&mut img, &v.format);
+        map_formaton(&mut img, &v.format)?;
         img.w = v.width as u32;
         img.h = v.height as u32;
         img.d_w = v.width as u32;
@@ -26,7 +58,356 @@ pub fn img_from_frame(frame: &Frame) -> aom_image {
         img.stride[i] = frame.buf.linesize(i).unwrap() as i32;
     }
 
-    img
+    // U and V share the second plane, V starts one byte in
+    if img.fmt == aom_img_fmt_AOM_IMG_FMT_NV12 {
+        if frame.buf.count() != 2 {
+            return Err(AomError::InvalidParam);
+        }
+        img.planes[2] = unsafe { img.planes[1].add(1) };
+        img.stride[2] = img.stride[1];
+    }
+
+    Ok(img)
+}
+
+/// Utility function to convert aom_image to Frame, the inverse of `img_from_frame`.
+///
+/// The visible area (d_w x d_h) of every plane is copied into a newly allocated frame,
+/// honoring the strides of both sides and the chroma subsampling of the image. I420,
+/// I422 and I444 are supported, 8-bit and high bit depth (`_HIGHBITDEPTH` formats,
+/// two bytes per sample with `bit_depth` significant bits; 8-bit content in a 16-bit
+/// image becomes an 8-bit frame). Anything else fails with AOM_CODEC_UNSUP_FEATURE.
+pub fn img_to_frame(img: &aom_image) -> Result<Frame, AomError> {
+    let mut fmt: Formaton = formaton_from_img(img)?;
+    let range: YUVRange = if img.range as u32 == aom_color_range_AOM_CR_FULL_RANGE as u32 {
+        YUVRange::Full
+    } else {
+        YUVRange::Limited
+    };
+    fmt.model = ColorModel::Trichromatic(TrichromaticEncodingSystem::YUV(YUVSystem::YCbCr(range)));
+
+    let (width, height) = (img.d_w as usize, img.d_h as usize);
+    let info: VideoInfo = VideoInfo::new(width, height, false, FrameType::OTHER, Arc::new(fmt));
+    let mut frame: Frame = Frame::new_default_frame(info, None);
+
+    let high_bit_depth: bool = img.fmt & AOM_IMG_FMT_HIGHBITDEPTH != 0;
+    // libaom can hand out 8-bit content in 16-bit buffers, it is narrowed to 8-bit
+    let narrow: bool = high_bit_depth && img.bit_depth == 8;
+    let src_bytes: usize = if high_bit_depth { 2 } else { 1 };
+
+    for i in 0..fmt.get_num_comp() {
+        let (w, h) = if i == 0 {
+            (width, height)
+        } else {
+            let (xs, ys) = (img.x_chroma_shift, img.y_chroma_shift);
+            (
+                (width + (1 << xs) - 1) >> xs,
+                (height + (1 << ys) - 1) >> ys,
+            )
+        };
+        if img.planes[i].is_null() || img.stride[i] < 0 || (img.stride[i] as usize) < w * src_bytes
+        {
+            return Err(AomError::InvalidParam);
+        }
+
+        let src_stride: usize = img.stride[i] as usize;
+        let dst_stride: usize = frame.buf.linesize(i).unwrap();
+        let dst: &mut [u8] = frame.buf.as_mut_slice(i).unwrap();
+        for row in 0..h {
+            let src: *const u8 = unsafe { img.planes[i].add(row * src_stride) };
+            let dst: &mut [u8] = &mut dst[row * dst_stride..];
+            if narrow {
+                let src: &[u16] = unsafe { slice::from_raw_parts(src as *const u16, w) };
+                for (d, s) in dst.iter_mut().zip(src) {
+                    *d = *s as u8;
+                }
+            } else {
+                unsafe { ptr::copy_nonoverlapping(src, dst.as_mut_ptr(), w * src_bytes) };
+            }
+        }
+    }
+
+    Ok(frame)
+}
+
+// Formaton matching the layout of an aom_image format. The plane count and chroma
+// subsampling come from the format, the depth of high bit depth formats from bit_depth.
+fn formaton_from_img(img: &aom_image) -> Result<Formaton, AomError> {
+    let (base, h_ss, v_ss): (&Formaton, u8, u8) = match img.fmt & !AOM_IMG_FMT_HIGHBITDEPTH {
+        aom_img_fmt_AOM_IMG_FMT_I420 => (YUV420, 1, 1),
+        aom_img_fmt_AOM_IMG_FMT_I422 => (YUV422, 1, 0),
+        aom_img_fmt_AOM_IMG_FMT_I444 => (YUV444, 0, 0),
+        _ => return Err(AomError::UnsupFeature),
+    };
+    if img.x_chroma_shift != h_ss as u32 || img.y_chroma_shift != v_ss as u32 {
+        return Err(AomError::InvalidParam);
+    }
+
+    if img.fmt & AOM_IMG_FMT_HIGHBITDEPTH == 0 || img.bit_depth == 8 {
+        return Ok(*base);
+    }
+    if !(9..=16).contains(&img.bit_depth) {
+        return Err(AomError::UnsupFeature);
+    }
+
+    let depth: u8 = img.bit_depth as u8;
+    let chromatons: [Chromaton; AOM_MAX_PLANES] = [
+        Chromaton::yuvhb(0, 0, depth, 0),
+        Chromaton::yuvhb(h_ss, v_ss, depth, 1),
+        Chromaton::yuvhb(h_ss, v_ss, depth, 2),
+    ];
+    let mut fmt: Formaton = Formaton::new(base.get_model(), &chromatons, 0, 2);
+    fmt.set_primaries(base.get_primaries());
+    fmt.set_xfer(base.get_xfer());
+    fmt.set_matrix(base.get_matrix());
+    Ok(fmt)
+}
+
+/// I420 aom_image for a single plane 8-bit frame: the luma plane points into the frame,
+/// both chroma planes point to `chroma`, a ((w+1)/2) * ((h+1)/2) buffer of 128s.
+pub(crate) fn neutral_chroma_img(frame: &Frame, chroma: &[u8]) -> Result<aom_image, AomError> {
+    let v = match frame.kind {
+        MediaKind::Video(ref v) if v.format.get_num_comp() == 1 => v,
+        _ => return Err(AomError::InvalidParam),
+    };
+    let (cw, ch) = ((v.width + 1) / 2, (v.height + 1) / 2);
+    if chroma.len() < cw * ch {
+        return Err(AomError::InvalidParam);
+    }
+
+    let mut img: aom_image = unsafe { mem::zeroed() };
+    img.fmt = aom_img_fmt_AOM_IMG_FMT_I420;
+    img.bit_depth = 8;
+    img.bps = 12;
+    img.x_chroma_shift = 1;
+    img.y_chroma_shift = 1;
+    img.w = v.width as u32;
+    img.h = v.height as u32;
+    img.d_w = v.width as u32;
+    img.d_h = v.height as u32;
+    map_fmt_to_img(&mut img, &v.format);
+
+    let luma: &[u8] = frame.buf.as_slice(0).unwrap();
+    img.planes[0] = luma.as_ptr() as *mut u8;
+    img.stride[0] = frame.buf.linesize(0).unwrap() as i32;
+    // libaom only reads input planes, U and V can share the buffer
+    for i in 1..AOM_MAX_PLANES {
+        img.planes[i] = chroma.as_ptr() as *mut u8;
+        img.stride[i] = cw as i32;
+    }
+
+    Ok(img)
+}
+
+/// Byte offsets of Y, U and V inside the 4 byte Y0 U Y1 V macropixel of packed 8-bit
+/// 4:2:2 formats (YUYV: [0, 1, 3], UYVY: [1, 0, 2]). None for every other format.
+pub(crate) fn packed_422_offsets(fmt: &Formaton) -> Option<[usize; 3]> {
+    if fmt.get_num_comp() != 3 {
+        return None;
+    }
+
+    let mut offsets: [usize; 3] = [0; 3];
+    for (i, offset) in offsets.iter_mut().enumerate() {
+        let c: Chromaton = fmt.get_chromaton(i)?;
+        let subsampling: (u8, u8) = if i == 0 { (0, 0) } else { (1, 0) };
+        if !c.is_packed() || c.get_depth() != 8 || c.get_subsampling() != subsampling {
+            return None;
+        }
+        *offset = c.get_offset() as usize;
+    }
+
+    Some(offsets)
+}
+
+/// Planar 8-bit 4:2:2 picture depacked from YUYV/UYVY, stored in one buffer
+pub(crate) struct I422Planes {
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+impl I422Planes {
+    fn chroma_width(&self) -> usize {
+        (self.width + 1) / 2
+    }
+
+    /// Builds an aom_image whose planes point into `self`.
+    /// The image must not be used after `self` is dropped.
+    pub(crate) fn to_img(&self, fmt: &Formaton) -> Result<aom_image, AomError> {
+        let mut img: aom_image = unsafe { mem::zeroed() };
+        img.fmt = aom_img_fmt_AOM_IMG_FMT_I422;
+        img.bit_depth = 8;
+        img.bps = 16;
+        img.x_chroma_shift = 1;
+        img.y_chroma_shift = 0;
+        img.w = self.width as u32;
+        img.h = self.height as u32;
+        img.d_w = self.width as u32;
+        img.d_h = self.height as u32;
+        map_fmt_to_img(&mut img, fmt);
+
+        let luma: usize = self.width * self.height;
+        let chroma: usize = self.chroma_width() * self.height;
+        let base: *mut u8 = self.data.as_ptr() as *mut u8;
+        unsafe {
+            img.planes[0] = base;
+            img.planes[1] = base.add(luma);
+            img.planes[2] = base.add(luma + chroma);
+        }
+        img.stride[0] = self.width as i32;
+        img.stride[1] = self.chroma_width() as i32;
+        img.stride[2] = self.chroma_width() as i32;
+
+        Ok(img)
+    }
+}
+
+/// Converts packed 4:2:2 (YUYV, UYVY) to planar I422, libaom only takes planar input.
+/// `offsets` comes from `packed_422_offsets`. Odd widths reuse the last chroma sample.
+pub(crate) fn depack_422(
+    src: &[u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+    offsets: [usize; 3],
+) -> Result<I422Planes, AomError> {
+    let cw: usize = (width + 1) / 2;
+    // every pair of pixels is one 4 byte macropixel
+    if stride < cw * 4 || src.len() < stride * (height.max(1) - 1) + cw * 4 {
+        return Err(AomError::InvalidParam);
+    }
+
+    let mut data: Vec<u8> = vec![0; width * height + 2 * cw * height];
+    let (y, chroma) = data.split_at_mut(width * height);
+    let (u, v) = chroma.split_at_mut(cw * height);
+    let [y_off, u_off, v_off] = offsets;
+
+    for row in 0..height {
+        let line: &[u8] = &src[row * stride..];
+        for cx in 0..cw {
+            let px: &[u8] = &line[cx * 4..cx * 4 + 4];
+            // Y1 sits 2 bytes after Y0 in both layouts
+            y[row * width + cx * 2] = px[y_off];
+            if cx * 2 + 1 < width {
+                y[row * width + cx * 2 + 1] = px[y_off + 2];
+            }
+            u[row * cw + cx] = px[u_off];
+            v[row * cw + cx] = px[v_off];
+        }
+    }
+
+    Ok(I422Planes {
+        data,
+        width,
+        height,
+    })
+}
+
+/// Planar 8-bit 4:2:0 picture, limited range, as produced by `rgb_to_i420`
+pub(crate) struct I420Planes {
+    pub(crate) y: Vec<u8>,
+    pub(crate) u: Vec<u8>,
+    pub(crate) v: Vec<u8>,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) matrix: ColorMatrix,
+}
+
+impl I420Planes {
+    /// Width of the chroma planes (rounded up for odd widths)
+    pub(crate) fn chroma_width(&self) -> usize {
+        (self.width + 1) / 2
+    }
+
+    /// Builds an aom_image whose planes point into `self`.
+    /// The image must not be used after `self` is dropped.
+    pub(crate) fn to_img(&self) -> aom_image {
+        let mut img: aom_image = unsafe { mem::zeroed() };
+        img.fmt = aom_img_fmt_AOM_IMG_FMT_I420;
+        img.bit_depth = 8;
+        img.bps = 12;
+        img.x_chroma_shift = 1;
+        img.y_chroma_shift = 1;
+        img.w = self.width as u32;
+        img.h = self.height as u32;
+        img.d_w = self.width as u32;
+        img.d_h = self.height as u32;
+        img.mc = aom_matrix_coefficients::from(self.matrix);
+        img.range = ColorRange::Limited.into();
+
+        img.planes[0] = self.y.as_ptr() as *mut u8;
+        img.planes[1] = self.u.as_ptr() as *mut u8;
+        img.planes[2] = self.v.as_ptr() as *mut u8;
+        img.stride[0] = self.width as i32;
+        img.stride[1] = self.chroma_width() as i32;
+        img.stride[2] = self.chroma_width() as i32;
+
+        img
+    }
+}
+
+/// Converts packed 8-bit RGB (3 bytes per pixel, no row padding) to limited range I420.
+///
+/// Chroma is computed from the average of each 2x2 block of RGB pixels, which is the same
+/// as averaging the full resolution chroma since the matrix is linear. Odd widths/heights
+/// reuse the last column/row.
+pub(crate) fn rgb_to_i420(
+    rgb: &[u8],
+    width: usize,
+    height: usize,
+    matrix: ColorMatrix,
+) -> I420Planes {
+    let (kr, kb) = matrix.coefficients();
+    let kg: f32 = 1.0 - kr - kb;
+    let cw: usize = (width + 1) / 2;
+    let ch: usize = (height + 1) / 2;
+
+    let mut y: Vec<u8> = vec![0; width * height];
+    let mut u: Vec<u8> = vec![0; cw * ch];
+    let mut v: Vec<u8> = vec![0; cw * ch];
+
+    let pixel = |x: usize, row: usize| -> (f32, f32, f32) {
+        let i: usize = (row * width + x) * 3;
+        (rgb[i] as f32, rgb[i + 1] as f32, rgb[i + 2] as f32)
+    };
+    let clamp = |val: f32| -> u8 { val.round().clamp(0.0, 255.0) as u8 };
+
+    for row in 0..height {
+        for x in 0..width {
+            let (r, g, b) = pixel(x, row);
+            let luma: f32 = kr * r + kg * g + kb * b;
+            y[row * width + x] = clamp(16.0 + luma * 219.0 / 255.0);
+        }
+    }
+
+    for cy in 0..ch {
+        for cx in 0..cw {
+            let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let px = (cx * 2 + dx).min(width - 1);
+                let py = (cy * 2 + dy).min(height - 1);
+                let (pr, pg, pb) = pixel(px, py);
+                r += pr;
+                g += pg;
+                b += pb;
+            }
+            let (r, g, b) = (r / 4.0, g / 4.0, b / 4.0);
+            let luma: f32 = kr * r + kg * g + kb * b;
+            let cb: f32 = (b - luma) / (2.0 * (1.0 - kb));
+            let cr: f32 = (r - luma) / (2.0 * (1.0 - kr));
+            u[cy * cw + cx] = clamp(128.0 + cb * 224.0 / 255.0);
+            v[cy * cw + cx] = clamp(128.0 + cr * 224.0 / 255.0);
+        }
+    }
+
+    I420Planes {
+        y,
+        u,
+        v,
+        width,
+        height,
+        matrix,
+    }
 }
 
 pub fn to_buffer(buf: aom_fixed_buf_t) -> Vec<u8> {
@@ -38,18 +419,134 @@ pub fn to_buffer(buf: aom_fixed_buf_t) -> Vec<u8> {
     v
 }
 
-// INCOMPLETE
-fn map_formaton(img: &mut aom_image, fmt: &Formaton) {
-    if fmt == YUV420 {
+// Formats libaom can't take as is are rejected with AOM_CODEC_UNSUP_FEATURE. Packed
+// 4:2:2 is depacked by ImageView before it gets here.
+//
+// Planar 4:2:0, 4:2:2 and 4:4:4 at 8, 10 or 12 bits map to the I42x formats; above 8
+// bits the samples are 16-bit words and the image gets AOM_IMG_FMT_HIGHBITDEPTH, which
+// the encoder only takes with InitFlags::high_bit_depth.
+fn map_formaton(img: &mut aom_image, fmt: &Formaton) -> Result<(), AomError> {
+    let (h_ss, v_ss, depth): (u8, u8, u8) = if is_nv12(fmt) {
+        img.fmt = aom_img_fmt_AOM_IMG_FMT_NV12;
+        (1, 1, 8)
+    } else if let Some(depth) = gray_depth(fmt) {
+        // luma only; libaom describes 4:0:0 as I420 with the monochrome flag and
+        // doesn't read the chroma planes, which stay null
         img.fmt = aom_img_fmt_AOM_IMG_FMT_I420;
+        img.monochrome = 1;
+        (1, 1, depth)
     } else {
-        unimplemented!();
+        let (h_ss, v_ss, depth) = planar_yuv_layout(fmt).ok_or(AomError::UnsupFeature)?;
+        img.fmt = match (h_ss, v_ss) {
+            (1, 1) => aom_img_fmt_AOM_IMG_FMT_I420,
+            (1, 0) => aom_img_fmt_AOM_IMG_FMT_I422,
+            (0, 0) => aom_img_fmt_AOM_IMG_FMT_I444,
+            _ => return Err(AomError::UnsupFeature),
+        };
+        (h_ss, v_ss, depth)
+    };
+
+    // bits per pixel over the three planes
+    let bps: i32 = match (h_ss, v_ss) {
+        (1, 1) => 12,
+        (1, 0) => 16,
+        _ => 24,
+    };
+    match depth {
+        8 => {
+            img.bit_depth = 8;
+            img.bps = bps;
+        }
+        10 | 12 => {
+            img.fmt |= AOM_IMG_FMT_HIGHBITDEPTH;
+            img.bit_depth = depth as u32;
+            img.bps = bps * 2;
+        }
+        _ => return Err(AomError::UnsupFeature),
     }
-    img.bit_depth = 8;
-    img.bps = 12;
-    img.x_chroma_shift = 1;
-    img.y_chroma_shift = 1;
+    img.x_chroma_shift = h_ss as u32;
+    img.y_chroma_shift = v_ss as u32;
     map_fmt_to_img(img, fmt);
+
+    Ok(())
+}
+
+// Depth of a single component planar formaton (grayscale)
+fn gray_depth(fmt: &Formaton) -> Option<u8> {
+    if fmt.get_num_comp() != 1 {
+        return None;
+    }
+
+    let luma: Chromaton = fmt.get_chromaton(0)?;
+    if luma.is_packed() || luma.get_subsampling() != (0, 0) {
+        return None;
+    }
+    Some(luma.get_depth())
+}
+
+// (h_ss, v_ss, depth) of a planar Y, U, V formaton whose components share a depth
+fn planar_yuv_layout(fmt: &Formaton) -> Option<(u8, u8, u8)> {
+    if fmt.get_num_comp() != 3 {
+        return None;
+    }
+
+    let luma: Chromaton = fmt.get_chromaton(0)?;
+    let chroma: Chromaton = fmt.get_chromaton(1)?;
+    let depth: u8 = luma.get_depth();
+    let (h_ss, v_ss) = chroma.get_subsampling();
+    for (i, c) in [luma, chroma, fmt.get_chromaton(2)?].iter().enumerate() {
+        let subsampling: (u8, u8) = if i == 0 { (0, 0) } else { (h_ss, v_ss) };
+        if c.is_packed()
+            || c.get_depth() != depth
+            || c.get_subsampling() != subsampling
+            || c.get_offset() as usize != i
+        {
+            return None;
+        }
+    }
+
+    Some((h_ss, v_ss, depth))
+}
+
+/// NV12: 8-bit 4:2:0 with a luma plane and a second plane of interleaved U/V samples,
+/// U first. NV21 (V first) has no libaom counterpart.
+fn is_nv12(fmt: &Formaton) -> bool {
+    if fmt.get_num_comp() != 3 {
+        return false;
+    }
+
+    (0..3).all(|i| match fmt.get_chromaton(i) {
+        Some(c) if i == 0 => !c.is_packed() && c.get_depth() == 8,
+        Some(c) => {
+            c.is_packed()
+                && c.get_depth() == 8
+                && c.get_subsampling() == (1, 1)
+                && c.get_step() == 2
+                && c.get_offset() as usize == i - 1
+        }
+        None => false,
+    })
+}
+
+/// YCbCr range described by the formaton's color model, if it has one
+pub fn range_from_formaton(fmt: &Formaton) -> Option<ColorRange> {
+    match fmt.get_model() {
+        ColorModel::Trichromatic(TrichromaticEncodingSystem::YUV(YUVSystem::YCbCr(range))) => {
+            match range {
+                YUVRange::Limited => Some(ColorRange::Limited),
+                YUVRange::Full => Some(ColorRange::Full),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Bit depth of the first component of a video frame, None for other media
+pub fn frame_bit_depth(frame: &Frame) -> Option<u32> {
+    match frame.kind {
+        MediaKind::Video(ref v) => v.format.get_chromaton(0).map(|c| c.get_depth() as u32),
+        _ => None,
+    }
 }
 
 #[cfg(target_os = "windows")]
@@ -57,6 +554,9 @@ fn map_fmt_to_img(img: &mut aom_image, fmt: &Formaton) {
     img.cp = fmt.get_primaries() as i32;
     img.tc = fmt.get_xfer() as i32;
     img.mc = fmt.get_matrix() as i32;
+    img.range = range_from_formaton(fmt)
+        .unwrap_or(ColorRange::Limited)
+        .into();
 }
 
 #[cfg(not(target_os = "windows"))]
@@ -64,4 +564,174 @@ fn map_fmt_to_img(img: &mut aom_image, fmt: &Formaton) {
     img.cp = fmt.get_primaries() as u32;
     img.tc = fmt.get_xfer() as u32;
     img.mc = fmt.get_matrix() as u32;
+    img.range = range_from_formaton(fmt)
+        .unwrap_or(ColorRange::Limited)
+        .into();
+}
+
+/// aom_image whose buffer is allocated and owned by libaom (aom_img_alloc), freed on
+/// drop. For pixels that don't come in an `av_data::Frame`, e.g. raw camera captures:
+/// allocate the image, write the planes and hand it to `AV1Encoder::encode_image`.
+///
+/// Planes are exposed as bytes with their stride, rows are padded to the alignment
+/// given to `new`. High bit depth formats take two bytes per sample.
+pub struct OwnedImage {
+    img: ptr::NonNull<aom_image>,
+}
+
+impl OwnedImage {
+    /// Allocates a `width` x `height` image of format `fmt` holding `bit_depth` bit
+    /// samples, with strides aligned to `align` bytes (a power of two, 1 for no
+    /// padding). `_HIGHBITDEPTH` formats take 8, 10 or 12 bits, the others 8 bits only.
+    /// Fails with AOM_CODEC_INVALID_PARAM for an empty size or another depth,
+    /// AOM_CODEC_MEM_ERROR when libaom doesn't allocate the image (unknown format, bad
+    /// alignment, out of memory).
+    pub fn new(
+        fmt: aom_img_fmt,
+        width: u32,
+        height: u32,
+        bit_depth: u32,
+        align: u32,
+    ) -> Result<Self, AomError> {
+        let depth_ok: bool = if fmt & AOM_IMG_FMT_HIGHBITDEPTH != 0 {
+            matches!(bit_depth, 8 | 10 | 12)
+        } else {
+            bit_depth == 8
+        };
+        if width == 0 || height == 0 || !depth_ok {
+            return Err(AomError::InvalidParam);
+        }
+
+        // a null image makes libaom allocate the aom_image itself, aom_img_free frees both
+        let img: *mut aom_image =
+            unsafe { aom_img_alloc(ptr::null_mut(), fmt, width, height, align) };
+        let mut img: ptr::NonNull<aom_image> = ptr::NonNull::new(img).ok_or(AomError::MemError)?;
+        // aom_img_alloc sets the container size (16 for high bit depth formats), the
+        // encoder checks the depth of the samples
+        unsafe { img.as_mut().bit_depth = bit_depth };
+
+        Ok(OwnedImage { img })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.as_raw().d_w
+    }
+
+    pub fn height(&self) -> u32 {
+        self.as_raw().d_h
+    }
+
+    pub fn fmt(&self) -> aom_img_fmt {
+        self.as_raw().fmt
+    }
+
+    /// Number of planes: 1 for monochrome images, 2 for NV12 (luma, then interleaved
+    /// U and V), 3 otherwise
+    pub fn plane_count(&self) -> usize {
+        if self.as_raw().monochrome != 0 {
+            1
+        } else if self.fmt() == aom_img_fmt_AOM_IMG_FMT_NV12 {
+            2
+        } else {
+            AOM_MAX_PLANES
+        }
+    }
+
+    /// Stride of `plane` in bytes
+    pub fn stride(&self, plane: usize) -> usize {
+        self.as_raw().stride[plane] as usize
+    }
+
+    /// Rows of `plane`, with the chroma subsampling applied
+    pub fn plane_height(&self, plane: usize) -> usize {
+        unsafe { aom_img_plane_height(self.img.as_ptr(), plane as i32) as usize }
+    }
+
+    /// Samples per row of `plane`, with the chroma subsampling applied
+    pub fn plane_width(&self, plane: usize) -> usize {
+        unsafe { aom_img_plane_width(self.img.as_ptr(), plane as i32) as usize }
+    }
+
+    /// Bytes of `plane`, `stride(plane) * plane_height(plane)` long.
+    /// Panics if `plane` is out of `plane_count`.
+    pub fn plane(&self, plane: usize) -> &[u8] {
+        assert!(plane < self.plane_count());
+        let len: usize = self.stride(plane) * self.plane_height(plane);
+        unsafe { slice::from_raw_parts(self.as_raw().planes[plane], len) }
+    }
+
+    /// Mutable bytes of `plane`, see `plane`
+    pub fn plane_mut(&mut self, plane: usize) -> &mut [u8] {
+        assert!(plane < self.plane_count());
+        let len: usize = self.stride(plane) * self.plane_height(plane);
+        unsafe { slice::from_raw_parts_mut(self.as_raw().planes[plane], len) }
+    }
+
+    /// The underlying aom_image, valid as long as `self`
+    pub fn as_raw(&self) -> &aom_image {
+        unsafe { self.img.as_ref() }
+    }
+
+    /// Mutable access to the image fields, e.g. to set the color description (cp, tc,
+    /// mc, range).
+    ///
+    /// # Safety
+    /// `fmt`, `planes`, `stride`, the sizes (`w`, `h`, `d_w`, `d_h`), the chroma shifts
+    /// and `img_data` must stay untouched: `plane`, `plane_mut` and `Drop` rely on them
+    /// describing the buffer libaom allocated.
+    pub unsafe fn as_raw_mut(&mut self) -> &mut aom_image {
+        self.img.as_mut()
+    }
+}
+
+impl Drop for OwnedImage {
+    fn drop(&mut self) {
+        unsafe { aom_img_free(self.img.as_ptr()) };
+    }
+}
+
+// The image and its buffer are uniquely owned
+unsafe impl Send for OwnedImage {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb_to_i420_primaries() {
+        // white, black, red, blue in a 2x2 block plus a grey odd column
+        #[rustfmt::skip]
+        let rgb: [u8; 18] = [
+            255, 255, 255,   0, 0, 0,     128, 128, 128,
+            255, 0, 0,       0, 0, 255,   128, 128, 128,
+        ];
+        let planes: I420Planes = rgb_to_i420(&rgb, 3, 2, ColorMatrix::Bt709);
+
+        assert_eq!(planes.chroma_width(), 2);
+        assert_eq!(planes.y.len(), 6);
+        assert_eq!(planes.u.len(), 2);
+        assert_eq!(planes.v.len(), 2);
+
+        assert_eq!(planes.y[0], 235);
+        assert_eq!(planes.y[1], 16);
+        assert_eq!(planes.y[3], 63);
+        assert_eq!(planes.y[4], 32);
+        // grey has no chroma
+        assert_eq!(planes.u[1], 128);
+        assert_eq!(planes.v[1], 128);
+    }
+
+    #[test]
+    fn i420_planes_to_img() {
+        let rgb: Vec<u8> = vec![0; 5 * 3 * 3];
+        let planes: I420Planes = rgb_to_i420(&rgb, 5, 3, ColorMatrix::Bt601);
+        let img: aom_image = planes.to_img();
+
+        assert_eq!(img.fmt, aom_img_fmt_AOM_IMG_FMT_I420);
+        assert_eq!((img.d_w, img.d_h), (5, 3));
+        assert_eq!(img.stride[0], 5);
+        assert_eq!(img.stride[1], 3);
+        assert_eq!(img.planes[0] as *const u8, planes.y.as_ptr());
+        assert_eq!(planes.u.len(), 3 * 2);
+    }
 }